@@ -0,0 +1,107 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+//! ⏱️ Core Kernel Benchmarks (核心算子基准)
+//!
+//! 为矩阵乘法、仿射复合、时间折叠与谱范数估算建立性能基线。
+//! 分块 matmul / SIMD / 并行折叠等优化都应以此为对照。
+//!
+//! Run: `cargo bench --bench kernels`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use htp_core::core::affine::AffineTuple;
+use htp_core::core::algebra::MANIFOLD_DIM;
+use htp_core::core::primes::{ConceptEmbedder, WeightInitializer};
+use htp_core::topology::folding::HyperFolder;
+
+/// 🎲 确定性的 D x D 仿射算子 (与测试中的 timeline 构造方式一致)
+fn affine(seed: u64) -> AffineTuple {
+    AffineTuple::new(
+        WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, seed),
+        ConceptEmbedder::embed_token(seed as u32).scale(0.1),
+    )
+}
+
+/// 🧮 Matrix Kernels: matmul (D³) 与 matmul_vec (D²)
+fn bench_matrix(c: &mut Criterion) {
+    let a = WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 1);
+    let b = WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 2);
+    let v = ConceptEmbedder::embed_token(3);
+
+    let mut group = c.benchmark_group("matrix");
+    // D³ 的朴素实现单次耗时较长，减少采样数以保持总时长可控
+    group.sample_size(10);
+    group.bench_function("matmul", |bench| bench.iter(|| black_box(&a).matmul(black_box(&b))));
+    group.finish();
+
+    c.bench_function("matrix/matmul_vec", |bench| {
+        bench.iter(|| black_box(&a).matmul_vec(black_box(&v)))
+    });
+}
+
+/// ⏳ AffineTuple::compose (一次 matmul + 一次 matmul_vec + 稳定性检查)
+/// Xavier 门的复合会被 Lipschitz 检查拒绝，但计算量与通过时相同，因此不 unwrap。
+fn bench_compose(c: &mut Criterion) {
+    let next = affine(10);
+    let prev = affine(11);
+
+    let mut group = c.benchmark_group("affine");
+    group.sample_size(10);
+    group.bench_function("compose", |bench| {
+        bench.iter(|| black_box(&next).compose(black_box(&prev)))
+    });
+    group.bench_function("compose_unchecked", |bench| {
+        bench.iter(|| black_box(&next).compose_unchecked(black_box(&prev)))
+    });
+    group.finish();
+}
+
+/// 📂 HyperFolder::fold_timeline (不同时间线长度)
+fn bench_fold_timeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fold_timeline");
+    group.sample_size(10);
+    for len in [2usize, 8, 32] {
+        let timeline: Vec<AffineTuple> = (0..len as u64).map(|i| affine(100 + i)).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(len), &timeline, |bench, timeline| {
+            bench.iter(|| HyperFolder::fold_timeline(black_box(timeline)))
+        });
+    }
+    group.finish();
+}
+
+/// 🧩 HyperFolder::fold_timeline_chunked (固定长度 32，扫描 chunk_size 寻找最佳并行粒度)
+fn bench_fold_timeline_chunked(c: &mut Criterion) {
+    let timeline: Vec<AffineTuple> = (0..32u64).map(|i| affine(100 + i)).collect();
+
+    let mut group = c.benchmark_group("fold_timeline_chunked");
+    group.sample_size(10);
+    for chunk_size in [1usize, 2, 4, 8, 16, 32] {
+        group.bench_with_input(BenchmarkId::from_parameter(chunk_size), &chunk_size, |bench, &chunk_size| {
+            bench.iter(|| HyperFolder::fold_timeline_chunked(black_box(&timeline), chunk_size))
+        });
+    }
+    group.finish();
+}
+
+/// 🛡️ Matrix::estimate_spectral_norm (不同迭代次数)
+fn bench_spectral_norm(c: &mut Criterion) {
+    let w = WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 5);
+
+    let mut group = c.benchmark_group("estimate_spectral_norm");
+    for iterations in [3usize, 10] {
+        group.bench_with_input(BenchmarkId::from_parameter(iterations), &iterations, |bench, &iterations| {
+            bench.iter(|| black_box(&w).estimate_spectral_norm(iterations))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    kernels,
+    bench_matrix,
+    bench_compose,
+    bench_fold_timeline,
+    bench_fold_timeline_chunked,
+    bench_spectral_norm
+);
+criterion_main!(kernels);
@@ -0,0 +1,106 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+use std::error::Error;
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use colored::Colorize;
+
+use htp_core::core::algebra::{Float, Vector};
+use htp_core::core::primes::ConceptEmbedder;
+use htp_core::net::transport::make_client_config;
+use htp_core::net::wire::{FoldMode, PacketType};
+
+/// 🔭 Evolver Client CLI
+/// 向一个 Worker 节点发送推理请求，并将结论解码为最近的 Token
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// 目标 Worker 地址 (如: "127.0.0.1:5001")
+    #[arg(short, long)]
+    target: SocketAddr,
+
+    /// 输入的 Token ID 序列 (空格分隔，如: 12 7 42)
+    #[arg(required = true, num_args = 1..)]
+    tokens: Vec<u32>,
+
+    /// 解码时搜索的词表大小 (Token ID 0..vocab)
+    #[arg(short, long, default_value_t = 1024)]
+    vocab: u32,
+
+    /// 等待回执的超时时间 (毫秒)
+    #[arg(long, default_value_t = 5000)]
+    timeout_ms: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    // 1. Embed: Token -> 流形坐标 (折叠交给 Worker 完成)
+    let tokens: Vec<Vector> = args.tokens.iter().map(|&t| ConceptEmbedder::embed_token(t)).collect();
+    let request_id = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+    println!("🧬 Embedded {} tokens (request #{})", tokens.len(), request_id);
+
+    // 2. Send: 通过 QUIC 双向流发送 FoldedInferenceRequest (Worker 侧时间折叠)
+    let request = PacketType::FoldedInferenceRequest { request_id, tokens, mode: FoldMode::Time };
+    let response = tokio::time::timeout(
+        Duration::from_millis(args.timeout_ms),
+        round_trip(args.target, &request),
+    ).await.map_err(|_| format!("Timed out waiting for {}", args.target))??;
+
+    // 3. Decode: 输出状态 -> 最近的 Token
+    match response {
+        PacketType::InferenceResponse { request_id: rid, output_state } if rid == request_id => {
+            let (token, similarity) = nearest_token(&output_state, args.vocab)
+                .ok_or("Empty vocabulary")?;
+            println!("{} token {} (cosine = {:.4})", "💡 Conclusion:".green().bold(), token, similarity);
+        }
+        PacketType::InferenceResponse { request_id: rid, .. } => {
+            return Err(format!("Response id mismatch: expected {}, got {}", request_id, rid).into());
+        }
+        PacketType::Error { code, message } => {
+            return Err(format!("Node rejected request ({:?}): {}", code, message).into());
+        }
+        other => return Err(format!("Unexpected response: {:?}", other).into()),
+    }
+
+    Ok(())
+}
+
+/// 🔎 在词表中寻找与输出状态余弦相似度最高的 Token
+fn nearest_token(state: &Vector, vocab: u32) -> Option<(u32, Float)> {
+    let state_norm = state.norm().max(1e-9);
+    (0..vocab)
+        .map(|t| {
+            let emb = ConceptEmbedder::embed_token(t); // 单位长度
+            let dot: Float = emb.data.iter().zip(&state.data).map(|(a, b)| a * b).sum();
+            (t, dot / state_norm)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// ==================================================================
+// 🛠️ Network Utilities (QUIC Boilerplate)
+// ==================================================================
+
+/// 发送一个包并在同一条双向流上等待回执
+async fn round_trip(target: SocketAddr, packet: &PacketType) -> Result<PacketType, Box<dyn Error>> {
+    let mut endpoint = quinn::Endpoint::client("0.0.0.0:0".parse()?)?;
+    endpoint.set_default_client_config(make_client_config());
+
+    let connection = endpoint.connect(target, "localhost")?.await?;
+    let (mut send, mut recv) = connection.open_bi().await?;
+
+    let bytes = packet.to_bytes()?;
+    send.write_all(&bytes).await?;
+    send.finish().await?;
+
+    let payload = recv.read_to_end(16 * 1024 * 1024).await?;
+    connection.close(0u32.into(), b"done");
+    if payload.is_empty() {
+        return Err(format!("{} closed the stream without a reply", target).into());
+    }
+    Ok(PacketType::from_bytes(&payload)?)
+}
@@ -6,15 +6,23 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use clap::Parser;
-use log::{info, error, warn, debug};
-use tokio::sync::mpsc;
+use tracing::{info, warn, debug, error, info_span, Instrument};
+use tracing_subscriber::EnvFilter;
 
 // 引入我们之前构建的模块
 use htp_core::net::node::{HTPNode, NodeRole};
-use htp_core::net::discovery::{DiscoveryService, PeerBrief}; // 假设 PeerBrief 已在 wire 或 discovery 中定义
+use htp_core::net::discovery::{DiscoveryService, PeerInfo, GOSSIP_INTERVAL_MS, HEARTBEAT_INTERVAL_MS};
+use htp_core::net::transport::make_node_endpoint;
 use htp_core::net::wire::{PacketType, PROTOCOL_VERSION};
 use htp_core::core::param::HyperParams;
 
+/// 🚦 每个来源节点的梯度推送限速 (每秒 / 突发)
+const GRADIENT_RATE_PER_SEC: f64 = 20.0;
+const GRADIENT_BURST: f64 = 40.0;
+
+/// 🔏 模型指纹比对周期 (秒)
+const FINGERPRINT_INTERVAL_SECS: u64 = 30;
+
 /// 🚀 Evolver Node CLI
 /// 启动一个 Hyper-Tensor 神经节点
 #[derive(Parser, Debug)]
@@ -30,54 +38,96 @@ struct Args {
 
     /// 节点角色 (worker 或 ps)
     #[arg(short, long, default_value = "worker")]
-    role: String,
+    role: NodeRole,
 
-    /// 种子节点地址 (可选，用于加入集群)
-    #[arg(short, long)]
-    seed: Option<String>, // 格式: "id@ip:port"
+    /// 种子节点地址 (可选，用于加入集群；逗号分隔多个种子，任一可达即可加入)
+    #[arg(short, long, value_delimiter = ',')]
+    seed: Vec<String>, // 格式: "id@ip:port,id@ip:port"
+
+    /// 🚇 模型并行: 本节点持有的第一层的全局层号
+    #[arg(long, default_value_t = 0)]
+    layer_offset: usize,
+
+    /// 🚇 模型并行: 全局模型总层数 (默认等于 layer_offset + 本地层数，即本节点是最后一段)
+    #[arg(long)]
+    total_layers: Option<usize>,
+
+    /// 📏 部署期望的流形维度 (默认取编译时的 MANIFOLD_DIM；不一致时拒绝启动)
+    #[arg(long)]
+    dimension: Option<usize>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // 1. 初始化日志
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
+    // 1. 初始化结构化日志 (tracing)
+    // 所有日志都挂在 Span 上 (node_id / request_id / epoch)，可跨 inference -> gradient -> sync 追踪
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
     let args = Args::parse();
 
-    info!("🚀 Starting Evolver Node [{}]...", args.id);
+    let node_span = info_span!("node", node_id = %args.id);
+    let _entered = node_span.enter();
+    info!("🚀 Starting Evolver Node...");
 
-    // 2. 确定角色
-    let role = match args.role.as_str() {
-        "ps" => NodeRole::ParameterServer,
-        "worker" => NodeRole::Worker,
-        _ => panic!("Invalid role. Use 'worker' or 'ps'."),
-    };
-    info!("🎭 Identity: {:?} | Listening on: {}", role, args.listen);
+    // 2. 确定角色 (非法取值已由 clap 通过 NodeRole::from_str 拒绝)
+    let role = args.role.clone();
+    info!(role = %role, listen = %args.listen, "🎭 Identity resolved");
 
-    // 3. 初始化核心组件
-    // (a) 大脑: HTPNode (负责推理与梯度)
-    let node = Arc::new(HTPNode::new(
-        args.id.clone(),
-        role.clone(),
-        12, // 默认深度，实际应从 Config 读取
-    ));
+    // 3. 加载并校验物理参数 (必须在打开 QUIC Endpoint 之前完成)
+    let mut params = HyperParams::default();
+    if let Some(dimension) = args.dimension {
+        params.dimension = dimension;
+    }
+
+    // 4. 初始化核心组件
+    // (a) 大脑: HTPNode (负责推理与梯度)，非法配置在此处中止启动
+    let local_depth = params.depth;
+    let node = match HTPNode::from_params(args.id.clone(), role.clone(), &params) {
+        Ok(node) => node,
+        Err(e) => {
+            error!(error = %e, "❌ Invalid configuration. Aborting startup.");
+            return Err(e.into());
+        }
+    };
+    let node = Arc::new(node
+        .with_layer_shard(args.layer_offset, args.total_layers.unwrap_or(args.layer_offset + local_depth))
+        .with_gradient_rate_limit(GRADIENT_RATE_PER_SEC, GRADIENT_BURST));
 
     // (b) 感官: DiscoveryService (负责发现邻居)
     let discovery = Arc::new(DiscoveryService::new(
         args.id.clone(),
         role.clone(),
         args.listen.to_string(),
-    ));
+    ).with_local_layers(node.layer_range()));
 
     // (c) 神经: Quinn Networking (QUIC Transport)
-    let (endpoint, mut incoming) = make_server_endpoint(args.listen)?;
-
-    // 4. 处理种子节点 (Bootstrapping)
-    if let Some(seed_str) = args.seed {
-        // 简单解析 "node-00@127.0.0.1:5000"
-        if let Some((seed_id, seed_addr)) = seed_str.split_once('@') {
-            info!("🌱 Bootstrapping via Seed: {} @ {}", seed_id, seed_addr);
-            // 假设 Seed 默认为 PS，实际应查询
-            discovery.add_seed_peer(seed_id.to_string(), seed_addr.to_string(), NodeRole::ParameterServer).await;
+    let endpoint = make_node_endpoint(args.listen)?;
+
+    // 5. 处理种子节点 (Bootstrapping)
+    // 所有种子都进入路由表 (假设 Seed 默认为 PS，实际应查询)，然后依次握手直到某个种子响应
+    if !args.seed.is_empty() {
+        let seeds = match discovery.add_seed_peers(&args.seed, NodeRole::ParameterServer).await {
+            Ok(seeds) => seeds,
+            Err(e) => {
+                error!(error = %e, "🛑 Invalid --seed list");
+                return Err(e.into());
+            }
+        };
+        let hello = PacketType::Handshake { node_id: args.id.clone(), protocol_ver: PROTOCOL_VERSION };
+        let mut joined = false;
+        for (seed_id, seed_addr) in &seeds {
+            info!(seed_id, seed_addr, "🌱 Bootstrapping via Seed");
+            match send_packet(&endpoint, seed_addr, &hello).await {
+                Ok(()) => {
+                    joined = true;
+                    break;
+                }
+                Err(e) => warn!(seed_id, error = %e, "🌱 Seed unreachable. Trying next"),
+            }
+        }
+        if !joined {
+            warn!(seeds = seeds.len(), "⚠️ No seed responded. Waiting for gossip / retries");
         }
     }
 
@@ -88,58 +138,197 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let disc_clone = discovery.clone();
     let endpoint_clone = endpoint.clone();
     
-    // Task A: Gossip & Heartbeat Loop
+    // Task A: Gossip Loop (低频: 交换完整的拓扑视图)
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_millis(2000));
+        let mut interval = tokio::time::interval(Duration::from_millis(GOSSIP_INTERVAL_MS));
         loop {
             interval.tick().await;
-            
-            // 1. 清理死节点
-            disc_clone.purge_dead_peers().await;
 
-            // 2. 生成八卦信息
+            // 1. 生成八卦信息 (死节点由高频的心跳任务清理)
             let (targets, peer_list) = disc_clone.generate_gossip().await;
             
-            // 3. 发送八卦
+            // 2. 发送八卦 (PeerInfo -> PeerBrief，附上本节点自己的名片)
             if !targets.is_empty() {
-                // 转换 PeerInfo -> PeerBrief (Wire Protocol)
-                let briefs: Vec<PeerBrief> = peer_list.iter().map(|p| PeerBrief {
-                    id: p.id.clone(),
-                    address: p.address.clone(),
-                    role_code: if p.role == NodeRole::ParameterServer { 1 } else { 0 },
-                }).collect();
-
-                let gossip_packet = PacketType::PeerDiscovery {
-                    sender_id: disc_clone.local_id(), // 需在 DiscoveryService 暴露此 getter
-                    peers: briefs,
-                };
+                let gossip_packet = disc_clone.gossip_packet(&peer_list);
 
                 // 尝试发送给随机选中的邻居
                 for target_addr in targets {
-                    let _ = send_packet(&endpoint_clone, &target_addr, &gossip_packet).await;
+                    if let Err(e) = send_packet(&endpoint_clone, &target_addr, &gossip_packet).await {
+                        debug!(peer_addr = %target_addr, error = %e, "Failed to deliver gossip");
+                    }
+                }
+            }
+        }
+    }.instrument(info_span!("gossip", node_id = %args.id)));
+
+    // Task A': Heartbeat Loop (高频: 固定大小的存活信号，不携带路由表)
+    // 每个周期先清理超时节点 (TTL 为若干个心跳周期)，再向有界的目标集合发送心跳
+    let disc_hb = discovery.clone();
+    let endpoint_hb = endpoint.clone();
+    let hb_node = node.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(HEARTBEAT_INTERVAL_MS));
+        loop {
+            interval.tick().await;
+            disc_hb.purge_dead_peers().await;
+
+            let heartbeat = hb_node.heartbeat_packet(0.0);
+            for target_addr in disc_hb.heartbeat_targets().await {
+                if let Err(e) = send_packet(&endpoint_hb, &target_addr, &heartbeat).await {
+                    debug!(peer_addr = %target_addr, error = %e, "Failed to deliver heartbeat");
+                }
+            }
+        }
+    }.instrument(info_span!("heartbeat", node_id = %args.id)));
+
+    // Task B: Topology Watcher (拓扑变化 -> 重建 Uplink)
+    // 不再轮询 build_topology：PS 掉线或新 PS 加入时，立即重新挂载到新的 Parent。
+    let mut topology_events = discovery.topology_changed();
+    let endpoint_uplink = endpoint.clone();
+    let uplink_id = args.id.clone();
+    let uplink_node = node.clone();
+    tokio::spawn(async move {
+        while topology_events.changed().await.is_ok() {
+            let topology = topology_events.borrow_and_update().clone();
+            match topology.parent {
+                Some(parent) => {
+                    info!(parent_id = %parent.id, parent_addr = %parent.address, "🔀 Topology changed. Re-parenting uplink");
+                    let hello = PacketType::Handshake {
+                        node_id: uplink_id.clone(),
+                        protocol_ver: PROTOCOL_VERSION,
+                    };
+                    if let Err(e) = send_packet(&endpoint_uplink, &parent.address, &hello).await {
+                        warn!(parent_id = %parent.id, error = %e, "🔥 Failed to establish uplink");
+                        continue;
+                    }
+
+                    // 重连追赶: 报告最后应用的纪元，PS 回执增量或完整快照
+                    if uplink_node.role == NodeRole::Worker {
+                        match round_trip(&endpoint_uplink, &parent.address, &uplink_node.sync_request_packet()).await {
+                            Ok(Some(snapshot @ PacketType::ParameterBroadcast(_))) => {
+                                info!(parent_id = %parent.id, "🔁 Catching up with Parameter Server");
+                                uplink_node.process_packet(snapshot).await;
+                            }
+                            Ok(_) => {}
+                            Err(e) => debug!(parent_id = %parent.id, error = %e, "Sync request failed"),
+                        }
+                    }
+                }
+                None if topology.is_root => {}
+                None => warn!("⚠️ Topology changed: no Parameter Server reachable. Uplink dropped."),
+            }
+        }
+    }.instrument(info_span!("topology_watch", node_id = %args.id)));
+
+    // Task C: Consensus Check (定期与 Parent 比对模型指纹，分叉时由 PS 回执快照并立即重新同步)
+    let fp_node = node.clone();
+    let fp_disc = discovery.clone();
+    let endpoint_fp = endpoint.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(FINGERPRINT_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let Some(parent) = fp_disc.build_topology().await.parent else { continue };
+            match round_trip(&endpoint_fp, &parent.address, &fp_node.fingerprint_packet()).await {
+                Ok(Some(snapshot @ PacketType::ParameterBroadcast(_))) => {
+                    warn!(parent_id = %parent.id, "🔏 Diverged from Parameter Server. Resyncing");
+                    fp_node.process_packet(snapshot).await;
                 }
+                Ok(_) => {}
+                Err(e) => debug!(parent_id = %parent.id, error = %e, "Fingerprint exchange failed"),
             }
         }
-    });
+    }.instrument(info_span!("consensus", node_id = %args.id)));
+
+    // Task D: Graceful Shutdown (Ctrl-C -> 广播 Leave -> 退出)
+    // 邻居收到 Leave 后立即重建拓扑，而不是等待心跳超时 (PEER_TTL_MS)。
+    let disc_leave = discovery.clone();
+    let endpoint_leave = endpoint.clone();
+    let leave_id = args.id.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        info!("👋 Shutting down. Announcing departure to peers");
+        let (_, peers) = disc_leave.generate_gossip().await;
+        let leave = PacketType::Leave { node_id: leave_id };
+        for peer in peers {
+            if let Err(e) = send_packet(&endpoint_leave, &peer.address, &leave).await {
+                debug!(peer_id = %peer.id, error = %e, "Failed to deliver Leave");
+            }
+        }
+        endpoint_leave.close(0u32.into(), b"leave");
+        std::process::exit(0);
+    }.instrument(info_span!("shutdown", node_id = %args.id)));
+
+    // Task E: Split-Brain Watch (分区愈合后两个 PS 可能各自推进过模型)
+    // 目前只告警，由运维按 Epoch 对账；Worker 侧的模型分叉由 Task C 的指纹比对自动修复。
+    let mut split_brain_events = discovery.split_brain_events();
+    tokio::spawn(async move {
+        loop {
+            match split_brain_events.recv().await {
+                Ok(event) => warn!(known_ps = %event.known_ps, rejoined_ps = %event.rejoined_ps,
+                    "🧠 Split brain: two Parameter Servers ran in separate partitions. Reconcile by epoch"),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "🧠 Split-brain watcher lagged behind");
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }.instrument(info_span!("split_brain", node_id = %args.id)));
 
     // ==================================================================
     // 🔁 Main Loop (主事件循环)
     // ==================================================================
     info!("👂 Node is active. Waiting for signals...");
 
-    while let Some(conn) = incoming.next().await {
+    while let Some(conn) = endpoint.accept().await {
         let node_ref = node.clone();
         let disc_ref = discovery.clone();
         let endpoint_ref = endpoint.clone();
+        let conn_span = info_span!("connection", node_id = %args.id, remote = tracing::field::Empty);
 
         // 为每个连接启动一个处理协程
         tokio::spawn(async move {
             let connection = match conn.await {
                 Ok(c) => c,
-                Err(e) => { warn!("🔥 Connection failed: {}", e); return; },
+                Err(e) => { warn!(error = %e, "🔥 Connection failed"); return; },
             };
+            let remote = connection.remote_address().to_string();
+            tracing::Span::current().record("remote", remote.as_str());
+
+            // 双向流: 请求-回执模式 (如 htp-cli 发来的 InferenceRequest)，回执写回同一条流
+            let bi_conn = connection.clone();
+            let bi_node = node_ref.clone();
+            let bi_remote = remote.clone();
+            let bi_disc = disc_ref.clone();
+            let bi_endpoint = endpoint_ref.clone();
+            tokio::spawn(async move {
+                while let Ok((mut send_stream, mut recv_stream)) = bi_conn.accept_bi().await {
+                    let Ok(payload) = recv_stream.read_to_end(1024 * 1024).await else { break };
+                    let Ok(packet) = PacketType::from_bytes(&payload) else { continue };
+                    let mut response = bi_node.process_packet_from(&bi_remote, packet).await;
+
+                    // 🚇 流水线推理: 本段处理完毕，转发给持有下一层的节点，并把最终回执转交给请求方
+                    if let Some(forward @ PacketType::InferencePipelineRequest { .. }) = response {
+                        response = match forward_pipeline(&bi_endpoint, &bi_disc, forward).await {
+                            Ok(reply) => Some(reply),
+                            Err(e) => {
+                                warn!(error = %e, "🔥 Pipeline forwarding failed");
+                                None
+                            }
+                        };
+                    }
 
-            // 每一个流代表一个请求/消息包
+                    // 无回执 (例如指纹一致) 时直接关闭流，请求方读到空负载即知道没有回执
+                    if let Some(Ok(bytes)) = response.map(|r| r.to_bytes()) {
+                        let _ = send_stream.write_all(&bytes).await;
+                    }
+                    let _ = send_stream.finish().await;
+                }
+            }.in_current_span());
+
+            // 单向流: 每一个流代表一个单向消息包 (Gossip / Gradient)
             loop {
                 // 读取流
                 let mut recv_stream = match connection.accept_uni().await {
@@ -153,29 +342,36 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     Err(_) => break,
                 };
 
-                // 反序列化
-                if let Ok(packet) = PacketType::from_bytes(&payload) {
+                // 反序列化 (未知标签来自更新版本的对端：整包跳过，不影响后续流量)
+                if let Ok(Some(packet)) = PacketType::decode(&payload) {
                     // 1. 拦截 Discovery 包 (Gossip)
-                    if let PacketType::PeerDiscovery { sender_id, peers } = &packet {
-                        // 更新路由表
-                        // 这里需要把 PeerBrief 转回 PeerInfo，并记录来源 IP
-                        // 简化处理: 直接交给 DiscoveryService
-                        debug!("🗣️ Received Gossip from {}", sender_id);
-                        // disc_ref.handle_gossip(...).await; 
+                    // 名片转回 PeerInfo，按本地的 GossipMergePolicy 合并进路由表
+                    if let PacketType::PeerDiscovery { sender_id, peers } = packet {
+                        debug!(sender_id = %sender_id, peers = peers.len(), "🗣️ Received Gossip");
+                        disc_ref.handle_gossip(peers.into_iter().map(PeerInfo::from).collect()).await;
                         continue;
                     }
 
-                    // 2. 交给大脑处理 (Inference / Gradient)
-                    if let Some(response) = node_ref.process_packet(packet).await {
-                        // 3. 如果有回执，发回去 (例如 InferenceResponse)
-                        // 注意：这里我们收的是 Uni stream，如果要回复，需要建立反向流或双向流
-                        // 为了简化，这里假设对方监听地址在 Packet payload 里或通过 discovery 查找
-                        // 真实实现中 QUIC 通常用 Bi-stream (双向流)
-                        // 这里仅演示逻辑: 查路由表 -> 发送
+                    // 2. 拦截下线通知 (Leave): 立即移除，不等心跳超时
+                    if let PacketType::Leave { node_id } = &packet {
+                        disc_ref.handle_leave(node_id).await;
+                        continue;
+                    }
+
+                    // 2.5 拦截轻量心跳: 只刷新存活状态，不触碰拓扑
+                    if let PacketType::Heartbeat { node_id, epoch, load } = &packet {
+                        disc_ref.handle_heartbeat(node_id, *epoch, *load).await;
+                        continue;
+                    }
+
+                    // 3. 交给大脑处理 (Inference / Gradient)
+                    // 4. 单向流没有回执通道：需要即时回执的请求 (InferenceRequest) 应走上面的 Bi-stream
+                    if let Some(response) = node_ref.process_packet_from(&remote, packet).await {
+                        debug!(reply_kind = response.kind(), "Dropping reply to a uni-stream packet");
                     }
                 }
             }
-        });
+        }.instrument(conn_span));
     }
 
     Ok(())
@@ -185,33 +381,39 @@ async fn main() -> Result<(), Box<dyn Error>> {
 // 🛠️ Network Utilities (QUIC Boilerplate)
 // ==================================================================
 
-/// 创建 QUIC 服务端 Endpoint
-fn make_server_endpoint(bind_addr: SocketAddr) -> Result<(quinn::Endpoint, quinn::Incoming), Box<dyn Error>> {
-    // 1. 生成自签名证书 (Ephemeral)
-    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()])?;
-    let cert_der = cert.serialize_der()?;
-    let priv_key = cert.serialize_private_key_der();
-    let priv_key = rustls::PrivateKey(priv_key);
-    let cert_chain = vec![rustls::Certificate(cert_der)];
-
-    // 2. 配置 Server TLS
-    let mut server_config = rustls::ServerConfig::builder()
-        .with_safe_defaults()
-        .with_no_client_auth()
-        .with_single_cert(cert_chain, priv_key)?;
-    server_config.alpn_protocols = vec![b"htp-v1".to_vec()]; // Application Layer Protocol Negotiation
-
-    // 3. 构建 Quinn Server Config
-    let server_config = quinn::ServerConfig::with_crypto(Arc::new(server_config));
-    
-    // 4. 绑定端口
-    let endpoint = quinn::Endpoint::server(server_config, bind_addr)?;
-    
-    Ok((endpoint, incoming)) // 注意: quinn 0.10 API 略有不同，这里是概念代码
+/// 网络辅助函数的错误类型 (Send + Sync，可以跨越后台任务中的 await)
+type NetError = Box<dyn Error + Send + Sync>;
+
+/// 🚇 将流水线请求转发给持有 `next_layer` 的节点，并在同一条双向流上等待其回执
+/// (下游节点会继续转发，因此这里拿到的是整条流水线的最终结果)
+async fn forward_pipeline(endpoint: &quinn::Endpoint, discovery: &DiscoveryService, packet: PacketType) -> Result<PacketType, NetError> {
+    let PacketType::InferencePipelineRequest { next_layer, .. } = &packet else {
+        return Err("Not a pipeline request".into());
+    };
+    let owner = discovery.layer_owner(*next_layer).await
+        .ok_or_else(|| format!("No peer owns layer {}", next_layer))?;
+    debug!(next_layer, owner = %owner.id, "🚇 Forwarding pipeline request");
+    round_trip(endpoint, &owner.address, &packet).await?
+        .ok_or_else(|| format!("Layer {} owner [{}] sent no reply", next_layer, owner.id).into())
+}
+
+/// 发送一个包并在同一条双向流上等待回执 (对端关闭流而未写入任何内容时为 None)
+async fn round_trip(endpoint: &quinn::Endpoint, target_addr: &str, packet: &PacketType) -> Result<Option<PacketType>, NetError> {
+    let remote: SocketAddr = target_addr.parse()?;
+    let connection = endpoint.connect(remote, "localhost")?.await?;
+    let (mut send, mut recv) = connection.open_bi().await?;
+    send.write_all(&packet.to_bytes()?).await?;
+    send.finish().await?;
+
+    let payload = recv.read_to_end(16 * 1024 * 1024).await?;
+    if payload.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(PacketType::from_bytes(&payload)?))
 }
 
 /// 发送 UDP/QUIC 包的辅助函数
-async fn send_packet(endpoint: &quinn::Endpoint, target_addr: &str, packet: &PacketType) -> Result<(), Box<dyn Error>> {
+async fn send_packet(endpoint: &quinn::Endpoint, target_addr: &str, packet: &PacketType) -> Result<(), NetError> {
     // 解析地址
     let remote: SocketAddr = target_addr.parse()?;
     
@@ -222,7 +424,7 @@ async fn send_packet(endpoint: &quinn::Endpoint, target_addr: &str, packet: &Pac
     let mut send_stream = connection.open_uni().await?;
     
     // 序列化并发送
-    let bytes = packet.to_bytes().map_err(|s| s.to_string())?; // Convert String error to Box<dyn Error>
+    let bytes = packet.to_bytes()?;
     send_stream.write_all(&bytes).await?;
     send_stream.finish().await?;
 
@@ -1,7 +1,9 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
-use super::algebra::{Matrix, Vector, Float};
+use super::algebra::{Matrix, Vector, Float, MANIFOLD_DIM, SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED};
+use super::primes::WeightInitializer;
 use serde::{Serialize, Deserialize};
+use tracing::trace;
 
 /// ⚠️ [Safety Limit]: Lipschitz Continuity Constraint (K)
 /// 边界定义: 谱范数约束 (Spectral Norm Constraint)
@@ -10,6 +12,11 @@ use serde::{Serialize, Deserialize};
 /// 这违背了白盒系统的 "Traceable" (可追踪) 原则。
 const MAX_LIPSCHITZ_CONSTANT: Float = 1.01;
 
+/// 🧯 random_stable 的裁剪目标: 低于 K 并留出 1% 余量
+/// 幂迭代给出的是 σ 的下界，余量吸收估算误差，保证裁剪后的真实 σ ≤ 1：
+/// 任意多个这样的元组复合 (σ 次乘) 都不会超过 K，可以直接经过带检查的 `compose`。
+const STABLE_NORM_TARGET: Float = 0.99;
+
 /// 🏛️ AffineTuple: 逻辑流形上的基本变换单元
 /// 表示一个仿射变换 A(x) = Wx + b
 /// * W (Linear): 逻辑推演矩阵 (Logic Matrix)
@@ -31,6 +38,17 @@ impl AffineTuple {
         }
     }
     
+    /// 🔍 是否精确为单位元 (W = I 且 b = 0)
+    pub fn is_identity(&self) -> bool {
+        self.linear.is_identity() && self.translation.data.iter().all(|&x| x == 0.0)
+    }
+
+    /// 📍 作为 "点" 读取：返回平移部分
+    /// 与 `Vector::to_affine_leaf` 互逆；点叶子 (线性部分为 I) 折叠后的 Root 也按此约定读出状态。
+    pub fn as_point(&self) -> &Vector {
+        &self.translation
+    }
+
     /// 构造零元 (Zero Transformation)
     /// 用于累加器的初始状态
     pub fn zeros() -> Self {
@@ -52,6 +70,32 @@ impl AffineTuple {
         AffineTuple { linear, translation }
     }
 
+    /// 🎲 随机仿射元组 (Xavier 初始化的 D x D 矩阵 + 零偏置)
+    /// 测试与原型的快捷构造，等价于 `new(init_matrix(D, D, seed), init_bias(D))`。
+    pub fn random(seed: u64) -> Self {
+        AffineTuple {
+            linear: WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, seed),
+            translation: WeightInitializer::init_bias(MANIFOLD_DIM),
+        }
+    }
+
+    /// 🎲 稳定的随机仿射元组
+    /// 在 `random` 的基础上把线性部分的谱范数裁剪到 `STABLE_NORM_TARGET` (低于 K = 1.01 并留有余量)，
+    /// Xavier 方阵的 σ_max 约为 2，直接长链折叠会指数放大；裁剪后可安全折叠 (包括带检查的 `compose`)。
+    pub fn random_stable(seed: u64) -> Self {
+        Self::random(seed).clamp_to_stable(STABLE_NORM_TARGET)
+    }
+
+    /// 🛡️ Stability Projection (稳定性投影)
+    /// 把线性部分的谱范数裁剪到 `max_norm` 以内 (`Matrix::clip_spectral_norm`)，偏置保持不变。
+    /// 求解器或梯度更新之后可作为 post-step 投影，保证该元组满足 Lipschitz 约束。
+    pub fn clamp_to_stable(&self, max_norm: Float) -> Self {
+        AffineTuple {
+            linear: self.linear.clip_spectral_norm(max_norm),
+            translation: self.translation.clone(),
+        }
+    }
+
     /// ⏳ [Time Operator]: Non-Commutative Composition (时间演化 - 非交换)
     /// 
     /// 数学定义: $\mathcal{A}_2 \oplus \mathcal{A}_1$
@@ -65,28 +109,101 @@ impl AffineTuple {
     /// Result:
     /// * W_new = W2 * W1
     /// * b_new = W2 * b1 + b2
+    ///
+    /// 🛡️ Lipschitz Guard: 复合后的谱范数超过 `MAX_LIPSCHITZ_CONSTANT` 时返回 Err (消息中带有实测范数)。
+    /// 范数由 `estimate_spectral_norm_seeded` 估算 (随机探测向量，不会落入均匀向量的盲区)，
+    /// 实际迭代次数记录在 trace 日志中。需要允许 "稳定但逐步扩张" 的长链时 (热路径折叠)，
+    /// 使用不做检查的 `compose_unchecked`。
     pub fn compose(&self, prev: &Self) -> Result<Self, String> {
-        // 1. Compute Logic Composition (Non-Commutative)
-        // Order matters: self is the "Next" step, prev is the "Previous" step.
-        let new_linear = self.linear.matmul(&prev.linear);
+        let composed = self.compose_unchecked(prev);
 
         // [FALSIFIABILITY CHECK]: Lipschitz Stability
-        // 检查复合后的矩阵范数是否过大。
-        if new_linear.spectral_norm() > MAX_LIPSCHITZ_CONSTANT.powi(2) { // 粗略估算积累
-             // 注意：在实际训练中这里通常是 soft constraint (Loss penalty)，
-             // 但在严格推理模式下，我们可以将其视为硬边界。
-             // return Err(format!("❌ Stability Violation: Gradient explosion detected (Norm > {}).", MAX_LIPSCHITZ_CONSTANT));
+        // 幂迭代给出的是下界估计：通过检查不代表绝对安全，但超限一定是真实的扩张。
+        let (norm, iterations) = composed.linear
+            .estimate_spectral_norm_seeded(SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED);
+        trace!(norm, iterations, converged = iterations < SPECTRAL_PROBE_ITERATIONS, "🛡️ Lipschitz guard power iteration");
+        if norm > MAX_LIPSCHITZ_CONSTANT {
+            return Err(format!(
+                "❌ Stability Violation: composed operator norm {:.4} exceeds Lipschitz bound {}",
+                norm, MAX_LIPSCHITZ_CONSTANT
+            ));
         }
+        Ok(composed)
+    }
+
+    /// ⏳ [Time Operator]: Unchecked Composition (无 Lipschitz 检查的复合)
+    /// 与 `compose` 相同的代数，但不检查范数。用于 `HyperFolder` / `HyperTensor` 的折叠热路径：
+    /// 深层折叠的稳定性由 `NormGuard` 或 `verify_integrity` 负责，而不是每一步的硬边界。
+    ///
+    /// ⚡ Fast Path: 任一操作数的 W 为单位矩阵时 (剪枝后常见的 No-Op 步骤)，
+    /// 跳过 D³ 的 matmul，结果与完整路径逐位一致：
+    /// * W2 = I: W_new = W1,  b_new = b1 + b2
+    /// * W1 = I: W_new = W2,  b_new = W2 * b1 + b2
+    pub fn compose_unchecked(&self, prev: &Self) -> Self {
+        if self.linear.is_identity() {
+            return AffineTuple {
+                linear: prev.linear.clone(),
+                translation: prev.translation.add(&self.translation),
+            };
+        }
+        if prev.linear.is_identity() {
+            return AffineTuple {
+                linear: self.linear.clone(),
+                translation: self.linear.matmul_vec(&prev.translation).add(&self.translation),
+            };
+        }
+
+        // 1. Compute Logic Composition (Non-Commutative)
+        // Order matters: self is the "Next" step, prev is the "Previous" step.
+        let new_linear = self.linear.matmul(&prev.linear);
 
         // 2. Compute Bias Propagation
         // The bias of the previous step is transformed by the current logic.
         let propagated_bias = self.linear.matmul_vec(&prev.translation);
         let new_translation = propagated_bias.add(&self.translation);
 
-        Ok(AffineTuple {
+        AffineTuple {
             linear: new_linear,
             translation: new_translation,
-        })
+        }
+    }
+
+    /// 📈 [Monitoring]: Composition with Norm Growth (带范数增长观测的复合)
+    /// 与 `compose_unchecked` 结果完全相同，额外返回本次复合的范数增长比:
+    /// $ratio = \sigma(W_2 W_1) / (\sigma(W_2) \cdot \sigma(W_1))$
+    ///
+    /// 由次乘性 ratio ≤ 1 (幂迭代估算允许微小误差)：
+    /// * ratio ≈ 1: 两个门的主放大方向对齐，范数按最坏情况累积 (长链折叠的不稳定来源)
+    /// * ratio ≪ 1: 放大方向互相错开，复合后的实际增长远小于上界
+    ///
+    /// 不做 Lipschitz 硬检查 (基于 `compose_unchecked`)，只供折叠代码记录或聚合；任一输入范数接近 0 时 ratio 记为 1。
+    /// 范数由 `estimate_spectral_norm_seeded` 估算 (与 `compose` 的检查同一探测种子)。
+    /// 形状不可复合，或任一范数为 NaN / ∞ (权重已被污染) 时返回 Err。
+    pub fn compose_checked(&self, prev: &Self) -> Result<(Self, Float), String> {
+        if self.linear.cols != prev.linear.rows || self.linear.cols != prev.translation.data.len()
+            || self.linear.rows != self.translation.data.len()
+        {
+            return Err(format!(
+                "❌ Shape Mismatch: cannot compose {}x{} (bias {}) after {}x{} (bias {})",
+                self.linear.rows, self.linear.cols, self.translation.data.len(),
+                prev.linear.rows, prev.linear.cols, prev.translation.data.len()
+            ));
+        }
+        let composed = self.compose_unchecked(prev);
+
+        let norm = |m: &Matrix| m.estimate_spectral_norm_seeded(SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED).0;
+        let (next_norm, prev_norm, composed_norm) = (norm(&self.linear), norm(&prev.linear), norm(&composed.linear));
+        if !(next_norm.is_finite() && prev_norm.is_finite() && composed_norm.is_finite()) {
+            return Err(format!(
+                "❌ Non-finite operator norm (next {}, prev {}, composed {})",
+                next_norm, prev_norm, composed_norm
+            ));
+        }
+
+        let input_norms = next_norm * prev_norm;
+        let ratio = if input_norms < 1e-12 { 1.0 } else { composed_norm / input_norms };
+
+        Ok((composed, ratio))
     }
 
     /// ➕ [Primitive]: Pure Addition (纯加法)
@@ -111,6 +228,54 @@ impl AffineTuple {
         }
     }
 
+    /// 🧱 [Representation]: Homogeneous Coordinates (齐次坐标)
+    ///
+    /// 将仿射变换嵌入为 (rows+1) x (cols+1) 的分块矩阵:
+    /// [[W, b],
+    ///  [0, 1]]
+    /// 在齐次坐标下，时间复合退化为普通矩阵乘法: H(A2 ⊕ A1) = H(A2) · H(A1)。
+    pub fn to_homogeneous(&self) -> Matrix {
+        let (rows, cols) = (self.linear.rows, self.linear.cols);
+        let width = cols + 1;
+        let mut data = vec![0.0; (rows + 1) * width];
+        for i in 0..rows {
+            data[i * width..i * width + cols].copy_from_slice(&self.linear.data[i * cols..(i + 1) * cols]);
+            data[i * width + cols] = self.translation.data[i];
+        }
+        data[rows * width + cols] = 1.0;
+        Matrix::new(rows + 1, width, data)
+    }
+
+    /// 🧱 从齐次坐标矩阵还原仿射变换
+    /// 最后一行必须为 [0, ..., 0, 1] (容差 1e-6)，否则不是仿射变换 (例如投影变换)。
+    pub fn from_homogeneous(m: &Matrix) -> Result<Self, String> {
+        if m.rows < 2 || m.cols < 2 {
+            return Err(format!("Homogeneous matrix must be at least 2x2, got {}x{}", m.rows, m.cols));
+        }
+        let (rows, cols) = (m.rows - 1, m.cols - 1);
+        let last_row = &m.data[rows * m.cols..];
+        let is_affine = last_row[..cols].iter().all(|x| x.abs() < 1e-6) && (last_row[cols] - 1.0).abs() < 1e-6;
+        if !is_affine {
+            return Err("Last row of a homogeneous matrix must be [0, ..., 0, 1]".to_string());
+        }
+
+        let mut linear = Vec::with_capacity(rows * cols);
+        let mut translation = Vec::with_capacity(rows);
+        for i in 0..rows {
+            let row = &m.data[i * m.cols..(i + 1) * m.cols];
+            linear.extend_from_slice(&row[..cols]);
+            translation.push(row[cols]);
+        }
+        Ok(AffineTuple::new(Matrix::new(rows, cols, linear), Vector { data: translation }))
+    }
+
+    /// 🎚️ [Primitive]: Linear Interpolation (参数插值)
+    /// Math: (1-t)·self + t·other，同时作用于 W 与 b。
+    /// 用于权重 EMA (指数滑动平均) 与 Model Soup 等模型平均技术。
+    pub fn lerp(&self, other: &Self, t: Float) -> Self {
+        self.scale(1.0 - t).add_components(&other.scale(t))
+    }
+
     /// 🌌 [Space Operator]: Commutative Aggregation (空间聚合 - 交换)
     /// 
     /// 数学定义: $\mathcal{A}_1 \otimes \mathcal{A}_2$
@@ -125,6 +290,18 @@ impl AffineTuple {
         Ok(sum.scale(0.5))
     }
     
+    /// 🔄 Inverse Transformation (逆变换)
+    /// A(x) = Wx + b  =>  A⁻¹(y) = W⁻¹y - W⁻¹b
+    /// 满足 A⁻¹ ∘ A = I。W 奇异 (信息已丢失) 时返回错误。
+    pub fn inverse(&self) -> Result<Self, String> {
+        let inv_linear = self.linear.inverse()?;
+        let inv_translation = inv_linear.matmul_vec(&self.translation).scale(-1.0);
+        Ok(AffineTuple {
+            linear: inv_linear,
+            translation: inv_translation,
+        })
+    }
+
     /// 🔧 Inverse Solver (代数逆解)
     /// 给定输入状态 S_in 和目标状态 S_target，求解需要的变换 A (假设 A 是单纯的 W 或 b 更新)
     /// 这是 White-Box 架构的核心能力。
@@ -1,6 +1,10 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
+use std::path::Path;
+
 use serde::{Serialize, Deserialize};
+use super::affine::AffineTuple;
+use super::primes::ConceptEmbedder;
 
 // ==================================================================
 // 1. 基础类型定义 (The Manifold Substrate)
@@ -13,6 +17,13 @@ pub type Float = f32;
 /// 逻辑流形的维度。
 pub const MANIFOLD_DIM: usize = 512;
 
+/// 🎯 幂迭代的收敛容差 (相邻两次 σ 估计的相对变化)
+pub const SPECTRAL_NORM_TOLERANCE: Float = 1e-5;
+
+/// 🎲 稳定性相关的谱范数估算 (Lipschitz 检查 / 裁剪) 共用的探测种子与迭代上限
+pub const SPECTRAL_PROBE_SEED: u64 = 0x5EED_1F5C;
+pub const SPECTRAL_PROBE_ITERATIONS: usize = 20;
+
 /// 🏛️ Vector: 逻辑流形上的点或位移向量
 /// Represents a point $v \in \mathbb{R}^D$
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -88,10 +99,92 @@ impl Vector {
         Vector { data: new_data }
     }
 
+    /// ⚫ 内积: $\langle v, u \rangle = \sum_i v_i u_i$
+    /// 维度不一致时返回错误，而不是让 `zip` 静默截断到较短的一方。
+    pub fn dot(&self, other: &Self) -> Result<Float, String> {
+        if self.data.len() != other.data.len() {
+            return Err(format!("Dot product dimension mismatch: {} vs {}", self.data.len(), other.data.len()));
+        }
+        Ok(self.data.iter().zip(&other.data).map(|(a, b)| a * b).sum())
+    }
+
+    /// 📐 余弦相似度: $\frac{\langle v, u \rangle}{\|v\| \|u\|} \in [-1, 1]$
+    /// 度量两个流形点的夹角 (与 `LogicOracle::calculate_loss` 的平方距离互补)。
+    /// 任一向量的模长小于 1e-9 (与 `normalize` 的阈值一致) 时返回 0.0。
+    ///
+    /// ⚠️ 维度不一致时 panic (见 `dot`)。
+    pub fn cosine_similarity(&self, other: &Self) -> Float {
+        let (na, nb) = (self.norm(), other.norm());
+        if na < 1e-9 || nb < 1e-9 {
+            return 0.0;
+        }
+        let dot = self.dot(other).unwrap_or_else(|e| panic!("cosine_similarity: {}", e));
+        (dot / (na * nb)).clamp(-1.0, 1.0)
+    }
+
+    /// 🧭 正交投影: $\frac{\langle v, d \rangle}{\|d\|^2} \cdot d$
+    /// 度量状态在某个概念方向上的分量 (可解释性)。
+    /// 方向向量近乎为零时返回零向量。
+    pub fn project_onto(&self, dir: &Vector) -> Self {
+        let norm_sq: Float = dir.data.iter().map(|x| x * x).sum();
+        if norm_sq < 1e-9 {
+            return Vector { data: vec![0.0; self.data.len()] };
+        }
+        let dot: Float = self.data.iter().zip(&dir.data).map(|(a, b)| a * b).sum();
+        dir.scale(dot / norm_sq)
+    }
+
+    /// ✂️ 正交补: $v - \mathrm{proj}_d(v)$
+    /// 从状态中剔除某个概念方向的分量。
+    pub fn reject_from(&self, dir: &Vector) -> Self {
+        self.sub(&self.project_onto(dir))
+    }
+
+    /// ➕ 分量和: $\sum_i v_i$ (空向量返回 0)
+    pub fn sum(&self) -> Float {
+        self.data.iter().sum()
+    }
+
+    /// 📊 均值: $\mu = \frac{1}{n} \sum_i v_i$ (空向量返回 0)
+    pub fn mean(&self) -> Float {
+        if self.data.is_empty() {
+            return 0.0;
+        }
+        self.sum() / self.data.len() as Float
+    }
+
+    /// 📊 总体方差: $\sigma^2 = \frac{1}{n} \sum_i (v_i - \mu)^2$ (空向量返回 0)
+    pub fn variance(&self) -> Float {
+        if self.data.is_empty() {
+            return 0.0;
+        }
+        let mu = self.mean();
+        self.data.iter().map(|x| (x - mu) * (x - mu)).sum::<Float>() / self.data.len() as Float
+    }
+
+    /// 📊 标准差: $\sqrt{\sigma^2 + \epsilon}$
+    /// 加入 ε 保证常数向量的标准差严格为正，可直接用作 LayerNorm / 白化的分母。
+    pub fn std(&self) -> Float {
+        const EPS: Float = 1e-6;
+        (self.variance() + EPS).sqrt()
+    }
+
+    /// 🔍 所有分量均为有限值 (无 NaN / Inf)
+    pub fn is_finite(&self) -> bool {
+        self.data.iter().all(|x| x.is_finite())
+    }
+
     /// 原始数据访问
     pub fn as_slice(&self) -> &[Float] {
         &self.data
     }
+
+    /// 🍃 包装为折叠叶子 (点算子): I·x + v
+    /// 约定：表示 "点" 的叶子线性部分恒为单位阵，平移部分即该点本身；
+    /// 用 `AffineTuple::as_point` 取回。
+    pub fn to_affine_leaf(self) -> AffineTuple {
+        AffineTuple::new(Matrix::identity(), self)
+    }
 }
 
 impl Matrix {
@@ -115,6 +208,16 @@ impl Matrix {
         }
     }
 
+    /// 🔍 是否精确等于单位矩阵 (方阵，对角为 1，其余为 0)
+    /// 逐元素精确比较 (不做容差)：仅用于跳过 "确定为 No-Op" 的计算。
+    pub fn is_identity(&self) -> bool {
+        self.rows == self.cols
+            && self.data.iter().enumerate().all(|(k, &x)| {
+                let expected = if k / self.cols == k % self.cols { 1.0 } else { 0.0 };
+                x == expected
+            })
+    }
+
     /// 矩阵乘法 (Matrix Multiplication): $C = A \cdot B$
     pub fn matmul(&self, other: &Self) -> Self {
         assert_eq!(self.cols, other.rows, "Matrix dimension mismatch for multiplication");
@@ -139,6 +242,32 @@ impl Matrix {
         Matrix { rows: n, cols: p, data: result }
     }
 
+    /// 🔀 GEMM 风格的乘法: $C = op(A) \cdot op(B)$，`op` 由标志决定是否转置
+    /// 直接按转置后的下标读取原数据，不构造 $A^T$ / $B^T$ (例如 Gram 矩阵 $A^T A$ = `a.gemm(true, &a, false)`)。
+    /// 累加顺序与 `matmul` 相同，`a.gemm(true, &b, false)` 与 `a.transpose().matmul(&b)` 逐位一致。
+    pub fn gemm(&self, a_transposed: bool, other: &Self, b_transposed: bool) -> Self {
+        let (n, m) = if a_transposed { (self.cols, self.rows) } else { (self.rows, self.cols) };
+        let (m_b, p) = if b_transposed { (other.cols, other.rows) } else { (other.rows, other.cols) };
+        assert_eq!(m, m_b, "Matrix dimension mismatch for multiplication");
+
+        let a_at = |i: usize, k: usize| if a_transposed { self.data[k * self.cols + i] } else { self.data[i * self.cols + k] };
+        let b_at = |k: usize, j: usize| if b_transposed { other.data[j * other.cols + k] } else { other.data[k * other.cols + j] };
+
+        let mut result = vec![0.0; n * p];
+        for i in 0..n {
+            for k in 0..m {
+                let r = a_at(i, k);
+                if r.abs() > 1e-9 {
+                    for j in 0..p {
+                        result[i * p + j] += r * b_at(k, j);
+                    }
+                }
+            }
+        }
+
+        Matrix { rows: n, cols: p, data: result }
+    }
+
     /// 矩阵-向量乘法 (Matrix-Vector Product): $y = A \cdot x$
     pub fn matmul_vec(&self, vec: &Vector) -> Vector {
         assert_eq!(self.cols, vec.data.len(), "Matrix-Vector dimension mismatch");
@@ -182,6 +311,15 @@ impl Matrix {
         Matrix { rows: self.rows, cols: self.cols, data: new_data }
     }
 
+    /// ➕ 原地融合加法: $A \leftarrow A + k \cdot B$
+    /// 与 `add(&other.scale(k))` 逐位相同，但不分配中间矩阵 (优化器热路径)。
+    pub fn add_scaled(&mut self, other: &Matrix, factor: Float) {
+        assert_eq!(self.data.len(), other.data.len(), "Matrix addition shape mismatch");
+        for (a, b) in self.data.iter_mut().zip(&other.data) {
+            *a += b * factor;
+        }
+    }
+
     /// 矩阵缩放 (Scalar Multiplication): $k \cdot A$
     pub fn scale(&self, scalar: Float) -> Self {
         let new_data = self.data.iter()
@@ -190,6 +328,11 @@ impl Matrix {
         Matrix { rows: self.rows, cols: self.cols, data: new_data }
     }
 
+    /// 🔍 所有元素均为有限值 (无 NaN / Inf)
+    pub fn is_finite(&self) -> bool {
+        self.data.iter().all(|x| x.is_finite())
+    }
+
     /// 📊 Frobenius Norm (原 spectral_norm)
     /// $\|A\|_F = \sqrt{\sum a_{ij}^2}$
     /// 这不是 Lipschitz 常数，只是矩阵元素的能量总和。
@@ -201,26 +344,409 @@ impl Matrix {
             .sqrt()
     }
 
+    /// 📐 Trace (迹): $\mathrm{tr}(A) = \sum_i a_{ii}$
+    /// 对于单位矩阵，此值为 D。与 `determinant` 一样仅对方阵有定义 (非方阵 panic)。
+    pub fn trace(&self) -> Float {
+        assert_eq!(self.rows, self.cols, "Trace is only defined for square matrices");
+        (0..self.rows)
+            .map(|i| self.data[i * self.cols + i])
+            .sum()
+    }
+
+    /// 🧊 Determinant (行列式): LU 分解 (部分选主元)
+    /// $\det(A) = (-1)^{swaps} \prod_i u_{ii}$，即线性部分的体积缩放因子；为 0 时不可逆。
+    /// 内部以 f64 计算，仅对方阵有定义 (非方阵 panic)。远离等体积的 512 维矩阵可能上溢为 ∞ 或下溢为 0。
+    pub fn determinant(&self) -> Float {
+        assert_eq!(self.rows, self.cols, "Determinant is only defined for square matrices");
+        let n = self.rows;
+        let mut lu: Vec<f64> = self.data.iter().map(|&x| x as f64).collect();
+        let mut det = 1.0f64;
+
+        for col in 0..n {
+            let pivot_row = (col..n)
+                .max_by(|&a, &b| lu[a * n + col].abs().total_cmp(&lu[b * n + col].abs()))
+                .unwrap_or(col);
+            let pivot = lu[pivot_row * n + col];
+            if pivot == 0.0 {
+                return 0.0;
+            }
+            if pivot_row != col {
+                for j in 0..n {
+                    lu.swap(col * n + j, pivot_row * n + j);
+                }
+                det = -det;
+            }
+            det *= pivot;
+
+            for row in (col + 1)..n {
+                let factor = lu[row * n + col] / pivot;
+                if factor == 0.0 {
+                    continue;
+                }
+                for j in col..n {
+                    lu[row * n + j] -= factor * lu[col * n + j];
+                }
+            }
+        }
+        det as Float
+    }
+
     /// 🛡️ Estimated Spectral Norm (Power Iteration)
     /// 估算矩阵的最大奇异值 $\sigma_{max}$，即真实的 Lipschitz 常数。
-    /// 算法：幂迭代法 (Power Method) 作用于 $A^T A$。
-    /// Iterations: 通常 3 次即可得到对于稳定性检查足够精确的下界估计。
+    /// 算法：幂迭代法 (Power Method) 作用于 $A^T A$，从固定种子 `SPECTRAL_PROBE_SEED` 的随机探测向量出发，
+    /// 收敛后提前终止 (见 `estimate_spectral_norm_seeded`)。`iterations` 为迭代上限。
     pub fn estimate_spectral_norm(&self, iterations: usize) -> Float {
-        // 1. 初始化探测向量 (Deterministically)
-        // 使用均匀分布的向量而不是随机向量，确保确定性。
+        self.estimate_spectral_norm_seeded(iterations, SPECTRAL_PROBE_SEED).0
+    }
+
+    /// 📏 Uniform-Probe Spectral Norm (均匀探测向量的幂迭代)
+    /// 旧版估算: 从均匀向量 $(1, \dots, 1)/\sqrt{n}$ 出发、固定迭代 `iterations` 次。
+    /// ⚠️ 主奇异方向与均匀向量正交时会严重低估 σ；仅用于对照与回归测试，稳定性检查请用 `estimate_spectral_norm`。
+    pub fn estimate_spectral_norm_uniform(&self, iterations: usize) -> Float {
         let init_val = 1.0 / (self.cols as Float).sqrt();
         let mut v = Vector::new(vec![init_val; self.cols]);
-
-        // 2. Power Iteration: v_k = A^T * A * v_{k-1}
         for _ in 0..iterations {
-            let av = self.matmul_vec(&v);         // Apply A
-            let at_av = self.transpose_matmul_vec(&av); // Apply A^T
-            v = at_av.normalize();                // Re-normalize
+            v = self.transpose_matmul_vec(&self.matmul_vec(&v)).normalize();
+        }
+        self.matmul_vec(&v).norm()
+    }
+
+    /// 🎲 Seeded Spectral Norm (随机起点 + 收敛检测的幂迭代)
+    /// 均匀探测向量可能恰好与主奇异子空间正交 (例如某些对称结构)，导致严重低估 Lipschitz 常数；
+    /// 这里的探测向量取自 `ConceptEmbedder` 同款的 SplitMix64 序列，几乎必然含有主方向分量。
+    /// 相邻两次迭代的 Rayleigh 估计 $\|A v\|$ 相对变化小于 `SPECTRAL_NORM_TOLERANCE` 时提前终止。
+    /// 返回 (σ_max 估计, 实际迭代次数)，调用方可据此记录收敛情况。
+    pub fn estimate_spectral_norm_seeded(&self, iterations: usize, seed: u64) -> (Float, usize) {
+        let (sigma, _, used) = self.power_iteration(iterations, seed);
+        (sigma, used)
+    }
+
+    /// 🧭 Dominant Singular Vector (主奇异方向)
+    /// 与 `estimate_spectral_norm` 相同的幂迭代 (种子探测 + 收敛检测)，但同时返回收敛的右奇异向量 v (单位长度)：
+    /// 逻辑门对输入空间中 v 方向的放大最强，$\|A v\| = \sigma_{max}$。
+    /// 用于可解释性分析 (这个门主要在 "推" 哪个方向)。
+    pub fn dominant_singular_vector(&self, iterations: usize) -> (Float, Vector) {
+        let (sigma, v, _) = self.power_iteration(iterations, SPECTRAL_PROBE_SEED);
+        (sigma, v)
+    }
+
+    /// 🔁 幂迭代内核: 返回 (σ 估计, 右奇异向量估计, 实际迭代次数)
+    fn power_iteration(&self, iterations: usize, seed: u64) -> (Float, Vector, usize) {
+        let mut v = Vector { data: ConceptEmbedder::splitmix_sequence(seed, self.cols) }.normalize();
+        let mut av = self.matmul_vec(&v);
+        let mut sigma = av.norm();
+
+        for k in 1..=iterations {
+            v = self.transpose_matmul_vec(&av).normalize();
+            av = self.matmul_vec(&v);
+            let next = av.norm();
+            if (next - sigma).abs() <= SPECTRAL_NORM_TOLERANCE * next {
+                return (next, v, k);
+            }
+            sigma = next;
+        }
+        (sigma, v, iterations)
+    }
+
+    /// ✂️ Spectral Norm Clipping (谱范数裁剪)
+    /// 若 $\sigma_{max}(A) > $ `max_norm`，整体缩放 $A \cdot \frac{max\_norm}{\sigma_{max}}$，否则原样返回。
+    /// 只改变尺度、不改变方向，保留矩阵编码的逻辑结构。
+    /// σ 由 `estimate_spectral_norm_seeded` 估算 (与 `AffineTuple::compose` 的 Lipschitz 检查读数一致)。
+    pub fn clip_spectral_norm(&self, max_norm: Float) -> Matrix {
+        let (sigma, _) = self.estimate_spectral_norm_seeded(SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED);
+        if sigma <= max_norm || sigma < 1e-12 {
+            return self.clone();
+        }
+        self.scale(max_norm / sigma)
+    }
+
+    /// 🔄 Exact Inverse (Gauss-Jordan 消元，部分选主元)
+    /// 仅适用于方阵；内部以 f64 计算。主元相对于矩阵最大元素小于 1e-7 时视为奇异并返回错误，
+    /// 而不是输出被舍入误差主导的 "逆"。
+    pub fn inverse(&self) -> Result<Matrix, String> {
+        if self.rows != self.cols {
+            return Err(format!("Cannot invert a non-square {}x{} matrix", self.rows, self.cols));
+        }
+        let n = self.rows;
+        let scale = self.data.iter().fold(0.0f64, |m, &x| m.max((x as f64).abs()));
+        if scale == 0.0 || !scale.is_finite() {
+            return Err("Cannot invert a zero or non-finite matrix".to_string());
+        }
+
+        // 增广矩阵 [A | I]
+        let width = 2 * n;
+        let mut aug = vec![0.0f64; n * width];
+        for i in 0..n {
+            for j in 0..n {
+                aug[i * width + j] = self.data[i * n + j] as f64;
+            }
+            aug[i * width + n + i] = 1.0;
+        }
+
+        for col in 0..n {
+            // 1. 选主元: 当前列绝对值最大的行
+            let pivot_row = (col..n)
+                .max_by(|&a, &b| aug[a * width + col].abs().total_cmp(&aug[b * width + col].abs()))
+                .unwrap_or(col);
+            let pivot = aug[pivot_row * width + col];
+            if pivot.abs() < 1e-7 * scale {
+                return Err(format!("Matrix is singular (pivot {:.3e} at column {})", pivot, col));
+            }
+            if pivot_row != col {
+                for j in 0..width {
+                    aug.swap(col * width + j, pivot_row * width + j);
+                }
+            }
+
+            // 2. 归一化主元行，并消去其余各行的该列
+            for j in 0..width {
+                aug[col * width + j] /= pivot;
+            }
+            for row in 0..n {
+                let factor = aug[row * width + col];
+                if row == col || factor == 0.0 {
+                    continue;
+                }
+                for j in 0..width {
+                    aug[row * width + j] -= factor * aug[col * width + j];
+                }
+            }
+        }
+
+        let data = (0..n)
+            .flat_map(|i| aug[i * width + n..(i + 1) * width].iter().map(|&x| x as Float).collect::<Vec<_>>())
+            .collect();
+        Ok(Matrix { rows: n, cols: n, data })
+    }
+
+    /// 🌡️ Condition Number Estimate (条件数估算)
+    /// $\kappa(A) = \sigma_{max} / \sigma_{min}$，其中 $\sigma_{min} = 1 / \|A^{-1}\|_2$。
+    /// 两个谱范数均由 `estimate_spectral_norm` 估算 (种子探测，最多 `SPECTRAL_PROBE_ITERATIONS` 次；分别作用于 A 与 `inverse()` 的结果)。
+    /// κ 很大时 `inverse` / 一次性求解的结果会被舍入误差主导，调用方应改用更强正则化的
+    /// `pseudo_inverse`。非方阵或奇异矩阵返回 `Float::INFINITY`。
+    pub fn condition_number(&self) -> Float {
+        let Ok(inv) = self.inverse() else {
+            return Float::INFINITY;
+        };
+        let kappa = self.estimate_spectral_norm(SPECTRAL_PROBE_ITERATIONS) * inv.estimate_spectral_norm(SPECTRAL_PROBE_ITERATIONS);
+        if kappa.is_finite() { kappa.max(1.0) } else { Float::INFINITY }
+    }
+
+    /// 🧮 Tikhonov-Regularized Pseudo-Inverse (Moore-Penrose)
+    /// 适用于任意形状的矩阵 (矩形映射 / 批量最小二乘)。
+    ///
+    /// * Tall (rows >= cols): $A^+ = (A^T A + \lambda I)^{-1} A^T$
+    /// * Wide (rows < cols):  $A^+ = A^T (A A^T + \lambda I)^{-1}$
+    ///
+    /// 两种形式都只需要对较小的 Gram 矩阵 (SPD) 做 Cholesky 分解。
+    /// λ > 0 保证 Gram 矩阵正定；λ = 0 时要求 A 满秩。
+    pub fn pseudo_inverse(&self, lambda: Float) -> Matrix {
+        if self.rows >= self.cols {
+            // (A^T A + λI) X = A^T  =>  X = A^+   (cols x rows)，右端直接按转置读取 A
+            let gram = self.gemm(true, self, false).add_diagonal(lambda);
+            cholesky_solve(&gram, self, true, false)
+        } else {
+            // (A A^T + λI) Y = A  =>  A^+ = Y^T   (cols x rows)，解直接按转置写出
+            let gram = self.gemm(false, self, true).add_diagonal(lambda);
+            cholesky_solve(&gram, self, false, true)
+        }
+    }
+
+    /// 📦 从 (rows, cols, 行优先数据) 构造矩阵
+    /// 与 `new` 不同，形状不匹配时返回错误而非 panic，适合加载外部数据。
+    pub fn from_parts(rows: usize, cols: usize, data: Vec<Float>) -> Result<Self, String> {
+        if data.len() != rows * cols {
+            return Err(format!("Matrix data size {} does not match {}x{}", data.len(), rows, cols));
+        }
+        Ok(Matrix { rows, cols, data })
+    }
+
+    /// 📦 取出行优先的原始数据 (形状信息随之丢弃)
+    pub fn into_vec(self) -> Vec<Float> {
+        self.data
+    }
+
+    /// 🔃 转置: $A^T$ (rows x cols -> cols x rows)，`A^T[j][i] = A[i][j]`
+    /// 显式构造转置矩阵；只需要 $A^T x$ 时用 `transpose_matmul_vec`、只需要转置乘积时用 `gemm`，可以省去这次分配。
+    pub fn transpose(&self) -> Matrix {
+        let mut data = vec![0.0; self.rows * self.cols];
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                data[j * self.rows + i] = self.data[i * self.cols + j];
+            }
+        }
+        Matrix { rows: self.cols, cols: self.rows, data }
+    }
+
+    /// $A + \lambda I$ (方阵，内部辅助)
+    fn add_diagonal(mut self, lambda: Float) -> Matrix {
+        for i in 0..self.rows.min(self.cols) {
+            self.data[i * self.cols + i] += lambda;
+        }
+        self
+    }
+}
+
+// ==================================================================
+// 3. 互操作 (Interop with plain Vec<Float> / NumPy)
+// ==================================================================
+
+/// 📦 `Vec<Float>` -> Vector (不做维度检查，与外部数值库对接时保持静默)
+impl From<Vec<Float>> for Vector {
+    fn from(data: Vec<Float>) -> Self {
+        Vector { data }
+    }
+}
+
+/// 📦 Vector -> `Vec<Float>`
+impl From<Vector> for Vec<Float> {
+    fn from(v: Vector) -> Self {
+        v.data
+    }
+}
+
+/// 🐍 NumPy `.npy` 魔数
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+impl Matrix {
+    /// 🐍 导出为 NumPy `.npy` (v1.0，行优先 little-endian f32)
+    ///
+    /// 文件布局：`\x93NUMPY` + 版本 `\x01\x00` + 头长度 (u16 LE) + 头字符串 + 数据。
+    /// 对 3 x 4 矩阵，头字符串为
+    /// `{'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }`，
+    /// 以空格填充并以 `\n` 结尾，使数据起始偏移为 64 的整数倍。
+    /// Python 侧直接 `np.load(path)` 即得到 `(rows, cols)` 的 float32 数组。
+    pub fn save_npy(&self, path: &Path) -> Result<(), String> {
+        let dict = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.rows, self.cols
+        );
+        // 前缀 10 字节 (魔数 6 + 版本 2 + 长度 2)，头部 (含结尾 \n) 填充到 64 字节对齐
+        let unpadded = NPY_MAGIC.len() + 4 + dict.len() + 1;
+        let header_len = dict.len() + 1 + (64 - unpadded % 64) % 64;
+        let header_len_u16 = u16::try_from(header_len)
+            .map_err(|_| format!("NPY header too long ({} bytes)", header_len))?;
+
+        let mut bytes = Vec::with_capacity(NPY_MAGIC.len() + 4 + header_len + self.data.len() * 4);
+        bytes.extend_from_slice(NPY_MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&header_len_u16.to_le_bytes());
+        bytes.extend_from_slice(dict.as_bytes());
+        bytes.resize(NPY_MAGIC.len() + 4 + header_len - 1, b' ');
+        bytes.push(b'\n');
+        for x in &self.data {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+
+        std::fs::write(path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// 🐍 读取 NumPy `.npy` (v1.x / v2.x)
+    /// 只接受 2-D、`'<f4'`、C 顺序 (fortran_order = False) 的数组。
+    pub fn load_npy(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+            return Err(format!("{} is not a .npy file", path.display()));
         }
 
-        // 3. Compute Rayleigh Quotient Approximation
-        // sigma ~ ||A v||
-        let av = self.matmul_vec(&v);
-        av.norm()
+        // 1. 头长度: v1 为 u16，v2/v3 为 u32
+        let (header_start, header_len) = match bytes[6] {
+            1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+            2 | 3 if bytes.len() >= 12 => (12, u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize),
+            v => return Err(format!("Unsupported .npy version {}", v)),
+        };
+        let data_start = header_start + header_len;
+        let header = bytes.get(header_start..data_start)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .ok_or("Truncated or non-UTF8 .npy header")?;
+
+        // 2. 解析头字典
+        if !header.contains("'descr': '<f4'") {
+            return Err(format!("Unsupported dtype (expected '<f4'): {}", header.trim()));
+        }
+        if !header.contains("'fortran_order': False") {
+            return Err("Fortran-ordered arrays are not supported".to_string());
+        }
+        let shape = header.split("'shape': (").nth(1)
+            .and_then(|rest| rest.split(')').next())
+            .ok_or("Missing shape in .npy header")?;
+        let dims: Vec<usize> = shape.split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(|d| d.parse::<usize>().map_err(|e| format!("Invalid shape '{}': {}", shape, e)))
+            .collect::<Result<_, _>>()?;
+        let (rows, cols) = match dims.as_slice() {
+            [rows, cols] => (*rows, *cols),
+            _ => return Err(format!("Expected a 2-D array, got shape ({})", shape)),
+        };
+
+        // 3. 数据
+        let payload = &bytes[data_start..];
+        if payload.len() != rows * cols * 4 {
+            return Err(format!("Expected {} bytes of data, found {}", rows * cols * 4, payload.len()));
+        }
+        let data = payload.chunks_exact(4)
+            .map(|c| Float::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        Ok(Matrix { rows, cols, data })
+    }
+}
+
+/// 🧮 Cholesky Solve: 求解 $G X = B$，其中 G 为对称正定矩阵
+/// 内部以 f64 计算以降低舍入误差；极小的主元被钳制，保证输出有限。
+/// 与 `gemm` 一样用标志代替显式转置：`rhs_transposed` 时 $B = $ `rhs`$^T$，
+/// `out_transposed` 时返回 $X^T$。
+fn cholesky_solve(gram: &Matrix, rhs: &Matrix, rhs_transposed: bool, out_transposed: bool) -> Matrix {
+    let (b_rows, m) = if rhs_transposed { (rhs.cols, rhs.rows) } else { (rhs.rows, rhs.cols) };
+    assert_eq!(gram.rows, gram.cols, "Cholesky requires a square matrix");
+    assert_eq!(gram.rows, b_rows, "Cholesky right-hand side shape mismatch");
+    let n = gram.rows;
+    let b_at = |i: usize, col: usize| if rhs_transposed { rhs.data[col * rhs.cols + i] } else { rhs.data[i * rhs.cols + col] };
+
+    // 1. Factorize: G = L L^T
+    let mut l = vec![0.0f64; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = gram.data[i * n + j] as f64;
+            for k in 0..j {
+                sum -= l[i * n + k] * l[j * n + k];
+            }
+            if i == j {
+                l[i * n + i] = sum.max(1e-12).sqrt();
+            } else {
+                l[i * n + j] = sum / l[j * n + j];
+            }
+        }
+    }
+
+    // 2. 对 B 的每一列做前向/后向代入
+    let mut out = vec![0.0; n * m];
+    let mut y = vec![0.0f64; n];
+    let mut x = vec![0.0f64; n];
+    for col in 0..m {
+        // L y = b
+        for i in 0..n {
+            let mut sum = b_at(i, col) as f64;
+            for k in 0..i {
+                sum -= l[i * n + k] * y[k];
+            }
+            y[i] = sum / l[i * n + i];
+        }
+        // L^T x = y
+        for i in (0..n).rev() {
+            let mut sum = y[i];
+            for k in (i + 1)..n {
+                sum -= l[k * n + i] * x[k];
+            }
+            x[i] = sum / l[i * n + i];
+            let idx = if out_transposed { col * n + i } else { i * m + col };
+            out[idx] = x[i] as Float;
+        }
+    }
+
+    if out_transposed {
+        Matrix { rows: m, cols: n, data: out }
+    } else {
+        Matrix { rows: n, cols: m, data: out }
     }
 }
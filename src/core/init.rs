@@ -1,12 +1,25 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
 use super::algebra::{Vector, Matrix, Float, MANIFOLD_DIM};
+use super::rng::{splitmix64, HtpRng};
 
 // ⚠️ [REFACTOR NOTICE]:
 // This file formerly handled "Prime Generation" for cryptographic hardness.
 // In White-Box Evolver, it is repurposed for "Manifold Initialization".
 // Recommended Rename: `src/core/init.rs`
 
+/// 🧩 CombineMode: n-gram 中各 Token 嵌入的组合方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CombineMode {
+    /// ➕ 逐元素求和
+    Sum,
+    /// ➗ 逐元素平均
+    Mean,
+    /// 📉 指数衰减加权平均：第 i 个 Token (共 n 个) 的权重为 γ^(n-1-i)，
+    /// 越靠后的 Token 权重越大 (最后一个为 1)，权重和归一化为 1。
+    WeightedDecay(Float),
+}
+
 /// 🧬 ConceptEmbedder: 将离散 Token 映射到连续流形
 ///
 /// 替代了原本的 "Hash-to-Prime" 机制。
@@ -23,27 +36,55 @@ impl ConceptEmbedder {
     pub fn embed_token(token_id: u32) -> Vector {
         // 使用简单的哈希算法生成确定性的伪随机向量
         // (避免引入庞大的依赖，仅作演示)
-        let mut data = Vec::with_capacity(MANIFOLD_DIM);
-        let mut state = token_id as u64;
+        let data = Self::splitmix_sequence(token_id as u64, MANIFOLD_DIM);
+
+        // 归一化向量长度 (Unit Norm)，确保初始状态在单位球面上
+        let norm: Float = data.iter().map(|x| x*x).sum::<Float>().sqrt();
+        let normalized_data = data.iter().map(|x| x / norm).collect();
+
+        Vector::new(normalized_data)
+    }
 
-        // SplitMix64 风格的简单的混合器
-        for _ in 0..MANIFOLD_DIM {
+    /// 🎲 SplitMix64 序列: 以 `seed` 为起点的 `len` 个 [-1.0, 1.0] 伪随机值 (未归一化)
+    /// `embed_token` 的底层生成器；也用作幂迭代等数值算法的确定性随机探测向量。
+    pub fn splitmix_sequence(seed: u64, len: usize) -> Vec<Float> {
+        let mut data = Vec::with_capacity(len);
+        let mut state = seed;
+
+        // SplitMix64: 状态按黄金比例常数递增，每一步经终混器输出
+        for _ in 0..len {
+            let z = splitmix64(state);
             state = state.wrapping_add(0x9e3779b97f4a7c15);
-            let mut z = state;
-            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
-            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
-            z = z ^ (z >> 31);
-            
+
             // 归一化到 [-1.0, 1.0] 区间，符合神经网络输入分布
             let val = (z as Float / u64::MAX as Float) * 2.0 - 1.0;
             data.push(val);
         }
+        data
+    }
 
-        // 归一化向量长度 (Unit Norm)，确保初始状态在单位球面上
-        let norm: Float = data.iter().map(|x| x*x).sum::<Float>().sqrt();
-        let normalized_data = data.iter().map(|x| x / norm).collect();
+    /// 🧩 N-Gram Projection (多 Token 组合嵌入)
+    /// 将一小段 Token (如子词片段) 的嵌入按 `mode` 组合为单个向量，作为更丰富的叶子嵌入。
+    /// 空序列返回零向量。
+    pub fn embed_ngram(tokens: &[u32], mode: CombineMode) -> Vector {
+        if tokens.is_empty() {
+            return Vector::zeros();
+        }
 
-        Vector::new(normalized_data)
+        let n = tokens.len();
+        let weights: Vec<Float> = match mode {
+            CombineMode::Sum => vec![1.0; n],
+            CombineMode::Mean => vec![1.0 / n as Float; n],
+            CombineMode::WeightedDecay(gamma) => {
+                let raw: Vec<Float> = (0..n).map(|i| gamma.powi((n - 1 - i) as i32)).collect();
+                let total: Float = raw.iter().sum();
+                raw.iter().map(|w| w / total).collect()
+            }
+        };
+
+        tokens.iter().zip(&weights).fold(Vector::zeros(), |acc, (&token, &w)| {
+            acc.add(&Self::embed_token(token).scale(w))
+        })
     }
 }
 
@@ -58,25 +99,48 @@ impl WeightInitializer {
     /// 适用于 Tanh 或 Linear 激活函数
     /// Range: [-limit, limit] where limit = sqrt(6 / (fan_in + fan_out))
     pub fn init_matrix(rows: usize, cols: usize, seed: u64) -> Matrix {
-        let mut data = Vec::with_capacity(rows * cols);
-        let mut rng_state = seed;
+        let mut rng = HtpRng::new(seed);
 
         // Xavier Limit
         let limit = (6.0 / (rows as Float + cols as Float)).sqrt();
 
-        for _ in 0..(rows * cols) {
-            // Simple LCG PRNG
-            rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1);
-            let rand_01 = rng_state as Float / u64::MAX as Float;
-            
-            // Map [0, 1] to [-limit, limit]
-            let val = (rand_01 * 2.0 - 1.0) * limit;
-            data.push(val);
-        }
+        // U[-1, 1] 映射到 [-limit, limit]
+        let data = (0..rows * cols).map(|_| rng.next_uniform() * limit).collect();
 
         Matrix::new(rows, cols, data)
     }
 
+    /// 🔔 Xavier Normal Initialization
+    /// W_ij ~ N(0, σ²)，σ = sqrt(2 / (fan_in + fan_out))。
+    /// 与 Xavier Uniform 的方差相同 (limit² / 3 = 2 / (fan_in + fan_out))，但分布为高斯分布，
+    /// 符合 Glorot 方差分析中的正态假设。
+    pub fn init_matrix_gaussian(rows: usize, cols: usize, seed: u64) -> Matrix {
+        let std = (2.0 / (rows as Float + cols as Float)).sqrt();
+        Matrix::new(rows, cols, HtpRng::new(seed).gaussian_vec(rows * cols, std))
+    }
+
+    /// 🎗️ Banded Initialization (局部性偏置)
+    /// 只在主对角线 `bandwidth` 范围内 (|i - j| <= bandwidth) 生成非零权重，带外严格为 0。
+    /// 适用于序列局部逻辑：参数更少、更易解释，且 matmul 会跳过零元，复合更快。
+    /// Xavier Limit 按每行的实际非零个数 (有效 fan) 计算。
+    pub fn init_banded(dim: usize, bandwidth: usize, seed: u64) -> Matrix {
+        let mut data = vec![0.0; dim * dim];
+        let mut rng = HtpRng::new(seed);
+
+        let fan = (2 * bandwidth + 1).min(dim) as Float;
+        let limit = (6.0 / (2.0 * fan)).sqrt();
+
+        for i in 0..dim {
+            let lo = i.saturating_sub(bandwidth);
+            let hi = (i + bandwidth).min(dim.saturating_sub(1));
+            for j in lo..=hi {
+                data[i * dim + j] = rng.next_uniform() * limit;
+            }
+        }
+
+        Matrix::new(dim, dim, data)
+    }
+
     /// 📍 Bias Initialization
     /// 通常初始化为 0 或很小的常数
     pub fn init_bias(dim: usize) -> Vector {
@@ -13,7 +13,8 @@ pub mod affine;
 pub mod param;
 
 // 4. Primes (Refactored to Init): 初始化与嵌入 (ConceptEmbedder)
-// 虽然文件名叫 primes (历史遗留)，但现在负责 Xavier 初始化和 Token 嵌入。
+// 虽然模块名叫 primes (历史遗留)，但源文件已更名为 init.rs，负责 Xavier 初始化和 Token 嵌入。
+#[path = "init.rs"]
 pub mod primes;
 
 // 5. Neuron: 神经单元 (HTPNeuron)
@@ -23,3 +24,7 @@ pub mod neuron;
 // 6. Oracle: 逻辑导师 (LogicOracle)
 // 负责计算 Loss、验证几何一致性和提供代数逆解。
 pub mod oracle;
+
+// 7. Rng: 确定性伪随机数 (HtpRng)
+// 合成数据、权重初始化与扰动共用的 LCG，提供均匀与高斯 (Box-Muller) 采样。
+pub mod rng;
@@ -1,9 +1,269 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use super::affine::AffineTuple;
-use super::algebra::{Vector, Matrix};
+use super::algebra::{Vector, Matrix, Float};
+use super::param::HyperParams;
+use super::rng::HtpRng;
 use serde::{Serialize, Deserialize};
 
+/// 🗃️ OutputCache: 有界 LRU 推理缓存
+///
+/// 以输入向量的哈希为键，缓存 absorb 的输出。
+/// 适用于重复输入的推理场景 (Retrieval / RAG 复用)。
+/// ⚠️ 缓存只对当前逻辑门有效：条目带有计算时的权重版本号，
+/// 与神经元当前版本不一致时整体作废 (见 `HTPNeuron::invalidate_cache`)。
+/// 条目保存原始输入并按位比较，64 位哈希碰撞只会造成未命中，不会返回别的输入的输出。
+/// 状态位于互斥锁之后，只读的模型 (如 Worker 的 ArcSwap 快照) 也能通过 `HTPNeuron::infer` 复用缓存。
+#[derive(Debug, Default)]
+pub struct OutputCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct CacheState {
+    /// 条目计算时所对应的权重版本
+    version: u64,
+    entries: HashMap<u64, CacheEntry>,
+    /// LRU 顺序：访问序号 → 键，最小的序号最久未使用
+    order: BTreeMap<u64, u64>,
+    /// 单调递增的访问序号
+    tick: u64,
+    /// 命中次数
+    hits: u64,
+    /// 未命中次数 (即真实计算次数)
+    misses: u64,
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    input: Vector,
+    output: Vector,
+    /// 最近一次访问的序号 (即在 `order` 中的位置)
+    tick: u64,
+}
+
+impl Clone for OutputCache {
+    fn clone(&self) -> Self {
+        OutputCache {
+            capacity: self.capacity,
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl CacheState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick = self.tick.wrapping_add(1);
+        self.tick
+    }
+}
+
+impl OutputCache {
+    pub fn new(capacity: usize) -> Self {
+        OutputCache {
+            capacity: capacity.max(1),
+            ..Default::default()
+        }
+    }
+
+    /// 🔑 输入指纹：对每个分量的位模式做哈希 (精确匹配，不做容差)
+    pub(crate) fn key_of(input: &Vector) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        input.data.len().hash(&mut hasher);
+        for x in &input.data {
+            x.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn same_bits(a: &Vector, b: &Vector) -> bool {
+        a.data.len() == b.data.len() && a.data.iter().zip(&b.data).all(|(x, y)| x.to_bits() == y.to_bits())
+    }
+
+    /// 🔍 查找 (计入命中 / 未命中)；`version` 与条目版本不一致时先清空全部条目
+    /// 键相同但输入不同 (哈希碰撞) 视为未命中。
+    pub(crate) fn lookup(&self, key: u64, version: u64, input: &Vector) -> Option<Vector> {
+        let mut state = self.state.lock().unwrap();
+        if state.version != version {
+            state.entries.clear();
+            state.order.clear();
+            state.version = version;
+        }
+        let tick = state.next_tick();
+        let hit = match state.entries.get_mut(&key) {
+            Some(entry) if Self::same_bits(&entry.input, input) => {
+                // 刷新 LRU 位置
+                let previous = std::mem::replace(&mut entry.tick, tick);
+                Some((previous, entry.output.clone()))
+            }
+            _ => None,
+        };
+        match hit {
+            Some((previous, output)) => {
+                state.order.remove(&previous);
+                state.order.insert(tick, key);
+                state.hits += 1;
+                Some(output)
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// 💾 写入 `version` 下计算出的输出；满员时淘汰最久未使用的条目，同键条目 (含碰撞) 被覆盖
+    pub(crate) fn store(&self, key: u64, version: u64, input: &Vector, output: Vector) {
+        let mut state = self.state.lock().unwrap();
+        if state.version != version {
+            // 计算期间权重已变化：结果不属于当前版本
+            return;
+        }
+        if let Some(replaced) = state.entries.remove(&key) {
+            state.order.remove(&replaced.tick);
+        } else if state.entries.len() >= self.capacity {
+            if let Some((_, evicted)) = state.order.pop_first() {
+                state.entries.remove(&evicted);
+            }
+        }
+        let tick = state.next_tick();
+        state.order.insert(tick, key);
+        state.entries.insert(key, CacheEntry { input: input.clone(), output, tick });
+    }
+
+    /// 🧹 清空所有缓存条目 (保留统计)
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.order.clear();
+    }
+
+    /// 📊 (hits, misses)
+    pub fn stats(&self) -> (u64, u64) {
+        let state = self.state.lock().unwrap();
+        (state.hits, state.misses)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 🔗 SharedGate: 多层共享 (绑定) 的逻辑门
+///
+/// 参数高效模型 (Universal Transformer) 让多层复用同一组 (W, b)。
+/// 各绑定层仍持有一份本地副本 (`HTPNeuron::logic_gate()` 读取的就是它)，
+/// 写入 (`set_logic_gate` / `gate_mut`) 发布到共享门，读取前按版本号廉价地判断是否需要拉取。
+#[derive(Clone, Debug)]
+pub struct SharedGate {
+    gate: Arc<RwLock<AffineTuple>>,
+    /// 每次发布递增
+    version: Arc<AtomicU64>,
+}
+
+impl SharedGate {
+    pub fn new(gate: AffineTuple) -> Self {
+        SharedGate {
+            gate: Arc::new(RwLock::new(gate)),
+            version: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 📖 当前共享逻辑门的快照
+    pub fn read(&self) -> AffineTuple {
+        self.gate.read().unwrap().clone()
+    }
+
+    /// ✍️ 替换共享逻辑门，返回新版本号
+    pub fn write(&self, gate: AffineTuple) -> u64 {
+        let mut guard = self.gate.write().unwrap();
+        *guard = gate;
+        self.version.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+}
+
+/// 🔭 GateRegime: 逻辑门对距离的作用 (由谱范数 σ_max 判定)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateRegime {
+    /// σ_max < 1: 压缩，迭代作用会收敛到不动点
+    Contractive,
+    /// σ_max ≈ 1 (相对误差 1e-3 以内): 近似保距
+    Isometric,
+    /// σ_max > 1: 至少有一个方向被放大
+    Expansive,
+}
+
+/// 🔬 NeuronExplanation: 神经元的白盒解释 (见 `HTPNeuron::explain`)
+#[derive(Clone, Debug)]
+pub struct NeuronExplanation {
+    /// 🛡️ 逻辑门线性部分的谱范数 σ_max (20 次幂迭代估算)
+    pub spectral_norm: Float,
+    /// 🔭 压缩 / 保距 / 扩张
+    pub regime: GateRegime,
+    /// 🧭 被放大最多的输入方向 (主右奇异向量，单位长度)
+    pub dominant_direction: Vector,
+    /// 📍 不动点 x* = W x* + b，即 (I - W) x* = b 的解；I - W 奇异 (例如 W = I) 时为 None
+    pub fixed_point: Option<Vector>,
+    /// 📏 偏置 (平移) 的模长 ||b||
+    pub bias_norm: Float,
+}
+
+impl fmt::Display for NeuronExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "σ_max = {:.4} ({:?}), ||b|| = {:.4}, ", self.spectral_norm, self.regime, self.bias_norm)?;
+        match &self.fixed_point {
+            Some(x) => write!(f, "fixed point at ||x*|| = {:.4}", x.norm()),
+            None => write!(f, "no unique fixed point"),
+        }
+    }
+}
+
+/// ✍️ GateMut: 逻辑门的可写借用 (见 `HTPNeuron::gate_mut`)
+/// Drop 时使缓存失效，绑定层同时把写入发布到共享门。
+pub struct GateMut<'a> {
+    neuron: &'a mut HTPNeuron,
+}
+
+impl Deref for GateMut<'_> {
+    type Target = AffineTuple;
+
+    fn deref(&self) -> &AffineTuple {
+        &self.neuron.logic_gate
+    }
+}
+
+impl DerefMut for GateMut<'_> {
+    fn deref_mut(&mut self) -> &mut AffineTuple {
+        &mut self.neuron.logic_gate
+    }
+}
+
+impl Drop for GateMut<'_> {
+    fn drop(&mut self) {
+        self.neuron.invalidate_cache();
+    }
+}
+
+fn default_lr_scale() -> Float {
+    1.0
+}
+
 /// 🧠 HTPNeuron: 逻辑流形上的基本神经单元
 ///
 /// 与输出标量激活值的传统神经元不同，HTP 神经元维护着一个高维坐标 (Vector)。
@@ -18,7 +278,29 @@ pub struct HTPNeuron {
 
     /// ⚙️ Intrinsic Logic Gate (内在逻辑门 / 权重)
     /// 定义了该神经元如何处理输入信息：(W, b)
-    pub logic_gate: AffineTuple,
+    /// 私有：写入必须经过 `set_logic_gate` / `gate_mut`，才能保证缓存失效与共享门发布。
+    logic_gate: AffineTuple,
+
+    /// 🎚️ Learning-Rate Multiplier (该层的学习率倍率，默认 1.0)
+    /// 优化器的有效学习率 = lr × lr_scale，用于分层 (Discriminative) 微调。
+    #[serde(default = "default_lr_scale")]
+    pub lr_scale: Float,
+
+    /// 🗃️ Optional Inference Cache (可选的输出缓存，不参与序列化)
+    #[serde(skip)]
+    cache: Option<OutputCache>,
+
+    /// 🎲 扰动前的原始逻辑门 (仅在 perturb 之后存在，不参与序列化)
+    #[serde(skip)]
+    perturb_backup: Option<AffineTuple>,
+
+    /// 🔢 权重版本号：每次写入逻辑门都递增，缓存按此判定是否过期 (不参与序列化)
+    #[serde(skip)]
+    weights_version: u64,
+
+    /// 🔗 绑定的共享逻辑门及本地副本对应的共享版本 (不参与序列化：反序列化后各层独立持有权重)
+    #[serde(skip)]
+    tied: Option<(SharedGate, u64)>,
 }
 
 impl HTPNeuron {
@@ -28,6 +310,11 @@ impl HTPNeuron {
         HTPNeuron {
             state: Vector::zeros(),
             logic_gate: AffineTuple::identity(),
+            lr_scale: 1.0,
+            cache: None,
+            perturb_backup: None,
+            weights_version: 0,
+            tied: None,
         }
     }
 
@@ -36,7 +323,144 @@ impl HTPNeuron {
         HTPNeuron {
             state: Vector::zeros(),
             logic_gate: AffineTuple::new(linear, bias),
+            lr_scale: 1.0,
+            cache: None,
+            perturb_backup: None,
+            weights_version: 0,
+            tied: None,
+        }
+    }
+
+    /// 🎚️ 设置该层的学习率倍率
+    pub fn with_lr_scale(mut self, lr_scale: Float) -> Self {
+        self.lr_scale = lr_scale;
+        self
+    }
+
+    /// 🗃️ 启用有界 LRU 输出缓存
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(OutputCache::new(capacity));
+        self
+    }
+
+    /// 🧹 使缓存失效 (权重变化后必须调用)
+    /// 递增权重版本号；旧版本下计算的缓存条目在下一次 absorb 时整体丢弃。
+    /// 绑定层同时把本地逻辑门发布到共享门，使所有绑定层看到这次写入。
+    pub fn invalidate_cache(&mut self) {
+        self.weights_version = self.weights_version.wrapping_add(1);
+        if let Some((shared, seen)) = &mut self.tied {
+            *seen = shared.write(self.logic_gate.clone());
+        }
+    }
+
+    /// 🔗 绑定到共享逻辑门 (本地逻辑门被替换为共享门的当前值)
+    pub fn tie_to(mut self, shared: &SharedGate) -> Self {
+        self.logic_gate = shared.read();
+        self.tied = Some((shared.clone(), shared.version()));
+        self.weights_version = self.weights_version.wrapping_add(1);
+        self
+    }
+
+    /// 🔗 取得本层的共享逻辑门 (尚未绑定时以当前逻辑门创建并绑定)，用于把其他层绑定到本层
+    pub fn share_gate(&mut self) -> SharedGate {
+        self.sync_tied_gate();
+        let gate = self.logic_gate.clone();
+        let (shared, _) = self.tied.get_or_insert_with(|| (SharedGate::new(gate), 0));
+        shared.clone()
+    }
+
+    /// 🔗 是否与其他层共享逻辑门
+    pub fn is_tied(&self) -> bool {
+        self.tied.is_some()
+    }
+
+    /// 🔄 若共享门已被其他绑定层更新，拉取到本地副本；返回是否发生了拉取
+    /// absorb 与优化器在读写前自动调用。
+    pub fn sync_tied_gate(&mut self) -> bool {
+        let Some((shared, seen)) = &mut self.tied else {
+            return false;
+        };
+        let latest = shared.version();
+        if latest == *seen {
+            return false;
+        }
+        self.logic_gate = shared.read();
+        *seen = latest;
+        self.weights_version = self.weights_version.wrapping_add(1);
+        true
+    }
+
+    /// 🔢 当前权重版本号 (每次写入逻辑门递增)
+    pub fn weights_version(&self) -> u64 {
+        self.weights_version
+    }
+
+    /// 📊 缓存统计 (hits, misses)，未启用缓存时返回 None
+    pub fn cache_stats(&self) -> Option<(u64, u64)> {
+        self.cache.as_ref().map(OutputCache::stats)
+    }
+
+    /// ⚙️ 当前逻辑门 (W, b)
+    pub fn logic_gate(&self) -> &AffineTuple {
+        &self.logic_gate
+    }
+
+    /// ✍️ 原地修改逻辑门：返回的守卫在离开作用域时自动调用 `invalidate_cache`
+    /// 绑定层先拉取最新的共享逻辑门，避免覆盖其他绑定层刚写入的更新。
+    pub fn gate_mut(&mut self) -> GateMut<'_> {
+        self.sync_tied_gate();
+        GateMut { neuron: self }
+    }
+
+    /// ⚙️ 替换逻辑门，并自动使缓存失效
+    pub fn set_logic_gate(&mut self, gate: AffineTuple) {
+        self.logic_gate = gate;
+        self.invalidate_cache();
+    }
+
+    /// ⚙️ 在给定配置下替换逻辑门 (证明模式下拒绝不可逆的门，原逻辑门保持不变)
+    pub fn set_logic_gate_checked(&mut self, gate: AffineTuple, params: &HyperParams) -> Result<(), String> {
+        params.admit_gate(&gate)?;
+        self.set_logic_gate(gate);
+        Ok(())
+    }
+
+    /// 🎲 Controlled Perturbation (受控扰动)
+    ///
+    /// 向逻辑门 (W 与 b) 的每个元素加入确定性的均匀噪声 `scale * U(-1, 1)`，
+    /// 用于敏感度分析 (Loss 随扰动的变化) 与 SAM (Sharpness-Aware Minimization)。
+    /// 原始逻辑门会被保存，`undo_perturb` 可精确还原；连续多次扰动时保留最早的原始值。
+    pub fn perturb(&mut self, scale: Float, seed: u64) {
+        if self.perturb_backup.is_none() {
+            self.perturb_backup = Some(self.logic_gate.clone());
+        }
+
+        let mut rng = HtpRng::new(seed);
+        for w in self.logic_gate.linear.data.iter_mut() {
+            *w += rng.next_uniform() * scale;
+        }
+        for b in self.logic_gate.translation.data.iter_mut() {
+            *b += rng.next_uniform() * scale;
         }
+        self.invalidate_cache();
+    }
+
+    /// ↩️ 撤销扰动，逐位还原为 perturb 之前的逻辑门
+    /// 返回是否确实存在需要撤销的扰动。
+    pub fn undo_perturb(&mut self) -> bool {
+        match self.perturb_backup.take() {
+            Some(original) => {
+                self.logic_gate = original;
+                self.invalidate_cache();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 🎲 当前是否处于扰动状态
+    pub fn is_perturbed(&self) -> bool {
+        self.perturb_backup.is_some()
     }
 
     /// 🔄 Time Evolution / Forward Pass (时间演化)
@@ -44,18 +468,68 @@ impl HTPNeuron {
     /// 物理含义: 神经元 "吸收" 输入状态，应用自己的逻辑规则，推导出新的状态。
     /// 公式: S_new = W * S_input + b
     pub fn absorb(&mut self, input: &Vector) -> Vector {
+        // Tied Weights: 其他绑定层可能已更新共享逻辑门
+        self.sync_tied_gate();
+
+        let new_state = self.forward_cached(input);
+
+        // Update Internal Memory
+        self.state = new_state.clone();
+        new_state
+    }
+
+    /// 🔎 Stateless Inference (只读推理)
+    ///
+    /// 与 absorb 计算相同的 W * x + b，但不写入 `state`，因此可以直接在共享的只读模型上调用
+    /// (无需克隆神经元)，并复用该神经元的输出缓存。
+    /// 绑定层的共享门若已被其他层更新，则按共享门的最新值计算且不经过缓存 (本地版本号已过期)。
+    pub fn infer(&self, input: &Vector) -> Vector {
+        if let Some((shared, seen)) = &self.tied {
+            if shared.version() != *seen {
+                let gate = shared.read();
+                return gate.linear.matmul_vec(input).add(&gate.translation);
+            }
+        }
+        self.forward_cached(input)
+    }
+
+    /// W * x + b，启用缓存时先查缓存、未命中则计算并写回
+    fn forward_cached(&self, input: &Vector) -> Vector {
+        // 0. Cache Lookup (仅在启用缓存时)
+        let cache_key = self.cache.as_ref().map(|_| OutputCache::key_of(input));
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            if let Some(cached) = cache.lookup(key, self.weights_version, input) {
+                return cached;
+            }
+        }
+
         // 1. Apply Linear Logic (W * x)
         // 这一步代表 "推理" (Deduction)
         let linear_part = self.logic_gate.linear.matmul_vec(input);
 
         // 2. Apply Bias/Correction (+ b)
         // 这一步代表 "修正" (Adjustment)
-        let new_state = linear_part.add(&self.logic_gate.translation);
+        let output = linear_part.add(&self.logic_gate.translation);
 
-        // 3. Update Internal Memory
-        self.state = new_state.clone();
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            cache.store(key, self.weights_version, input, output.clone());
+        }
+        output
+    }
 
-        new_state
+    /// 🔁 Recurrent Processing (局部循环推演)
+    ///
+    /// 把神经元当作 RNN 单元依次吸收一段序列，每一步的输出作为下一步的状态上下文：
+    /// S_t = W * (x_t + S_{t-1}) + b，其中 S_{-1} 为调用前的 `self.state`。
+    /// 返回每一步的中间状态 (与 inputs 一一对应)；结束后 `self.state` 为最后一步的输出。
+    /// 要求逻辑门为方阵 (输出可回灌为输入)。
+    pub fn absorb_sequence(&mut self, inputs: &[Vector]) -> Vec<Vector> {
+        inputs.iter()
+            .map(|x| {
+                let context = x.add(&self.state);
+                self.absorb(&context)
+            })
+            .collect()
     }
 
     /// 🧬 Algebraic One-Shot Learning (代数逆解 / 瞬间学习)
@@ -74,17 +548,55 @@ impl HTPNeuron {
         
         // 瞬间更新权重，无需迭代
         self.logic_gate.translation = new_bias;
+        self.invalidate_cache();
     }
     
     /// 🔍 Manifold Integrity Check (流形完整性检查)
     /// 防止 NaN (Not a Number) 或 Inf (无穷大) 污染网络。
     /// 這是 "Zero Hallucination" 的物理基础之一。
+    /// 同时检查状态与逻辑门 (W 与 b)：损坏的权重在产生坏状态之前就会被发现。
     pub fn verify_integrity(&self) -> Result<(), String> {
-        for val in self.state.as_slice() {
-            if !val.is_finite() {
-                return Err("🔥 Neuron Meltdown: State contains NaN or Infinity. Logic manifold collapsed.".to_string());
-            }
+        if !self.state.is_finite() {
+            return Err("🔥 Neuron Meltdown: State contains NaN or Infinity. Logic manifold collapsed.".to_string());
+        }
+        if !self.logic_gate.linear.is_finite() {
+            return Err("🔥 Neuron Meltdown: Logic gate matrix contains NaN or Infinity.".to_string());
+        }
+        if !self.logic_gate.translation.is_finite() {
+            return Err("🔥 Neuron Meltdown: Logic gate bias contains NaN or Infinity.".to_string());
         }
         Ok(())
     }
+
+    /// 🔬 White-Box Explanation (白盒解释)
+    /// 一次性汇总逻辑门 (W, b) 的几何含义：谱范数与压缩/扩张判定、主放大方向、不动点与偏置大小。
+    pub fn explain(&self) -> NeuronExplanation {
+        let linear = &self.logic_gate.linear;
+        let (spectral_norm, dominant_direction) = linear.dominant_singular_vector(20);
+        let regime = if (spectral_norm - 1.0).abs() <= 1e-3 {
+            GateRegime::Isometric
+        } else if spectral_norm < 1.0 {
+            GateRegime::Contractive
+        } else {
+            GateRegime::Expansive
+        };
+
+        // 不动点: (I - W) x* = b
+        let n = linear.rows;
+        let i_minus_w: Vec<Float> = (0..n * linear.cols)
+            .map(|k| if k / linear.cols == k % linear.cols { 1.0 } else { 0.0 } - linear.data[k])
+            .collect();
+        let fixed_point = Matrix::new(n, linear.cols, i_minus_w)
+            .inverse()
+            .ok()
+            .map(|inv| inv.matmul_vec(&self.logic_gate.translation));
+
+        NeuronExplanation {
+            spectral_norm,
+            regime,
+            dominant_direction,
+            fixed_point,
+            bias_norm: self.logic_gate.translation.norm(),
+        }
+    }
 }
@@ -2,6 +2,61 @@
 
 use super::algebra::{Vector, Matrix, Float, MANIFOLD_DIM};
 use super::affine::AffineTuple;
+use super::rng::HtpRng;
+
+/// 🛡️ One-Shot Solver 的数值阻尼 λ₀ (仅防止 ||x||² → 0 时除零，不影响强信号下的精确拟合)
+pub const SOLVER_DAMPING: Float = 1e-6;
+
+/// 🧲 恒等先验启用时接近项 ||ΔW||² 的默认权重 (单位权重，pull = μ / (1 + μ))
+pub const SOLVER_PROXIMITY_WEIGHT: Float = 1.0;
+
+/// 📊 Reduction: Batch Loss 的归约方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduction {
+    /// 平均: Sum / n (与样本数无关，适合日志与学习率调参)
+    Mean,
+    /// 求和: 与聚合器按 `batch_size` 加权的方式一致
+    Sum,
+    /// 不归约: 返回每个样本各自的 Loss
+    None,
+}
+
+/// 📊 BatchLoss: Batch Loss 的计算结果
+#[derive(Clone, Debug, PartialEq)]
+pub enum BatchLoss {
+    /// Mean / Sum 归约后的标量
+    Reduced(Float),
+    /// `Reduction::None` 下的逐样本 Loss
+    PerExample(Vec<Float>),
+}
+
+impl BatchLoss {
+    /// 取标量值 (逐样本结果按 Sum 归约)
+    pub fn value(&self) -> Float {
+        match self {
+            BatchLoss::Reduced(v) => *v,
+            BatchLoss::PerExample(losses) => losses.iter().sum(),
+        }
+    }
+}
+
+/// 🛡️ VerifyReport: 批量零幻觉校验的汇总
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerifyReport {
+    /// 通过校验 (Loss < ε) 的样本数
+    pub passed: usize,
+    /// 未通过校验的样本数
+    pub failed: usize,
+    /// 未通过的样本: (下标, Loss)，按下标升序
+    pub failures: Vec<(usize, Float)>,
+}
+
+impl VerifyReport {
+    /// ✅ 全部通过 (空 Batch 视为通过)
+    pub fn all_passed(&self) -> bool {
+        self.failed == 0
+    }
+}
 
 /// 🔮 LogicOracle: 逻辑导师与真理裁决者
 ///
@@ -21,6 +76,62 @@ impl LogicOracle {
         diff.data.iter().map(|x| x * x).sum()
     }
 
+    /// ⚖️ [Loss Function]: Huber (Robust) Error
+    /// 逐分量的 Huber 损失，对离群目标 (错误标注的事实) 不敏感：
+    ///
+    /// * |r| <= δ: ½ r²            (二次区，梯度 r)
+    /// * |r| >  δ: δ (|r| - ½ δ)   (线性区，梯度 δ · sign(r))
+    ///
+    /// 其中 r = S_pred - S_target。返回 (总损失, dL/dS_pred)。
+    /// 注意二次区带 ½ 系数 (标准 Huber 约定)，与 `calculate_loss` 的 ||r||² 相差 2 倍。
+    pub fn huber_loss(predicted: &Vector, target: &Vector, delta: Float) -> (Float, Vector) {
+        let diff = predicted.sub(target);
+        let mut loss = 0.0;
+        let grad = diff.data.iter()
+            .map(|&r| {
+                if r.abs() <= delta {
+                    loss += 0.5 * r * r;
+                    r
+                } else {
+                    loss += delta * (r.abs() - 0.5 * delta);
+                    delta * r.signum()
+                }
+            })
+            .collect();
+        (loss, Vector { data: grad })
+    }
+
+    /// ⚖️ [Loss Function]: Affine Transform Error
+    /// 同时度量矩阵部分与平移部分的误差，用于监督完整的变换 (而不仅仅是一个点)。
+    ///
+    /// L = || W_pred - W_target ||_F^2 + || b_pred - b_target ||^2
+    pub fn affine_loss(predicted: &AffineTuple, target: &AffineTuple) -> Float {
+        let matrix_err: Float = predicted.linear.data.iter()
+            .zip(&target.linear.data)
+            .map(|(p, t)| (p - t) * (p - t))
+            .sum();
+        matrix_err + Self::calculate_loss(&predicted.translation, &target.translation)
+    }
+
+    /// ⚖️ [Loss Function]: Batch Loss
+    /// 对一批 (预测, 目标) 对逐个计算 `calculate_loss`，再按 `reduction` 归约。
+    /// 训练循环与分布式路径统一使用此函数上报 Loss。空 Batch 的 Mean 为 0。
+    pub fn batch_loss(preds: &[Vector], targets: &[Vector], reduction: Reduction) -> BatchLoss {
+        assert_eq!(preds.len(), targets.len(), "Batch size mismatch between predictions and targets");
+
+        let losses: Vec<Float> = preds.iter()
+            .zip(targets)
+            .map(|(p, t)| Self::calculate_loss(p, t))
+            .collect();
+
+        match reduction {
+            Reduction::None => BatchLoss::PerExample(losses),
+            Reduction::Sum => BatchLoss::Reduced(losses.iter().sum()),
+            Reduction::Mean if losses.is_empty() => BatchLoss::Reduced(0.0),
+            Reduction::Mean => BatchLoss::Reduced(losses.iter().sum::<Float>() / losses.len() as Float),
+        }
+    }
+
     /// 🛡️ [Verification]: Geometric Consistency Check
     /// 验证推理结果是否在允许的误差范围内 (Epsilon Ball)。
     /// 这是 "Zero Hallucination" 的判定标准。
@@ -29,6 +140,27 @@ impl LogicOracle {
         loss < epsilon
     }
 
+    /// 🛡️ [Verification]: Batch Consistency Check
+    /// 对一批 (预测, 目标) 对逐个执行 `verify_logic` 的判定 (Loss < ε)，
+    /// 汇总通过 / 失败数，并记录每个失败样本的下标与 Loss，用作批量推理的质量闸门。
+    pub fn batch_verify(preds: &[Vector], targets: &[Vector], epsilon: Float) -> VerifyReport {
+        assert_eq!(preds.len(), targets.len(), "Batch size mismatch between predictions and targets");
+
+        let failures: Vec<(usize, Float)> = preds.iter()
+            .zip(targets)
+            .map(|(p, t)| Self::calculate_loss(p, t))
+            .enumerate()
+            // NaN Loss 一律判为失败
+            .filter(|&(_, loss)| loss >= epsilon || loss.is_nan())
+            .collect();
+
+        VerifyReport {
+            passed: preds.len() - failures.len(),
+            failed: failures.len(),
+            failures,
+        }
+    }
+
     /// 🎓 [The Solver]: One-Shot Regularized Estimator (自适应阻尼求解器)
     /// 
     /// ⚠️ 修正 (Fix): 原先的 "One-Shot Solver" 在输入向量模长接近 0 时存在奇点。
@@ -39,11 +171,93 @@ impl LogicOracle {
     /// - 弱信号区 (Low Norm): 退化为梯度下降 (Gradient Descent)，安全更新。
     /// 
     /// Formula: ΔW = (E * S_in^T) / (||S_in||^2 + λ)
+    ///
+    /// 维度由输入本身决定 (不依赖 `MANIFOLD_DIM`)：ΔW 的形状为 `target.len() x input.len()`，
+    /// 因此同样适用于矩形门 (跨维度投影)。`current_gate` 的形状必须与之一致。
     pub fn compute_ideal_update(
         input: &Vector, 
         target: &Vector, 
         current_gate: &AffineTuple
     ) -> Matrix {
+        Self::compute_ideal_update_regularized(input, target, current_gate, 0.0, 0.0)
+    }
+
+    /// 🎓 [The Solver]: Proximal Estimator (近端求解器)
+    ///
+    /// 先验的锚点不是单位矩阵，而是当前权重 W 本身 (例如刚被 SGD 更新过的权重)：
+    ///
+    /// min ||E - ΔW·x||² + λ||ΔW||²
+    ///
+    /// 闭式解 ΔW = E·x^T / (λ + ||x||²) 是沿 x 的秩一更新，与 x 正交的输入完全不受影响。
+    /// λ 越大，对事实的吸收越保守 (残差比例 λ / (λ + ||x||²))；λ = 0 时只保留数值阻尼，
+    /// 与 `compute_ideal_update` 一致。只修改线性部分，偏置由调用方决定是否变动。
+    pub fn compute_ideal_update_proximal(
+        input: &Vector,
+        target: &Vector,
+        current_gate: &AffineTuple,
+        proximity_weight: Float,
+    ) -> Matrix {
+        let rows = target.data.len();
+        let cols = input.data.len();
+        assert_eq!(
+            (current_gate.linear.rows, current_gate.linear.cols),
+            (rows, cols),
+            "Gate shape does not match target x input"
+        );
+        assert_eq!(current_gate.translation.data.len(), rows, "Gate bias does not match target dimension");
+
+        let error = target.sub(&current_gate.linear.matmul_vec(input).add(&current_gate.translation));
+        let input_norm_sq: Float = input.data.iter().map(|x| x*x).sum();
+        let denominator = input_norm_sq + proximity_weight.max(SOLVER_DAMPING);
+
+        let mut delta_data = vec![0.0; rows * cols];
+        for i in 0..rows {
+            let factor = error.data[i] / denominator;
+            for j in 0..cols {
+                delta_data[i * cols + j] = factor * input.data[j];
+            }
+        }
+
+        Matrix {
+            rows,
+            cols,
+            data: delta_data,
+        }
+    }
+
+    /// 🎓 [The Solver]: Identity-Regularized Estimator (恒等先验求解器)
+    ///
+    /// 无先验的求解器只保证 "把 input 映射到 target"，可能产生巨大的非对角项，
+    /// 在下一次 compose 时就违反 Lipschitz 约束。这里额外惩罚新权重偏离单位矩阵的程度：
+    ///
+    /// min ||E - ΔW·x||² + λ||ΔW||² + μ||W + ΔW - I||²
+    ///
+    /// 令 D = I - W, κ = λ + μ, s = ||x||²，由 Sherman-Morrison 得闭式解：
+    /// ΔW = E·x^T / (κ + s) + (μ/κ) · (D - (D·x)·x^T / (κ + s))
+    ///
+    /// 接近项权重 λ = `proximity_weight` (下限 `SOLVER_DAMPING`) 决定了与 x 正交方向上的拉回比例 μ/κ：
+    /// λ ≈ 0 时 μ/κ ≈ 1，任何 μ > 0 都会把 W 在 x 的正交补上整块重置为 I；
+    /// 取 λ = `SOLVER_PROXIMITY_WEIGHT` (1) 时拉回比例 μ/(1 + μ) 随 μ 连续变化。
+    ///
+    /// μ = 0 时接近项随先验一起关闭，退化为 `compute_ideal_update` (精确拟合)；
+    /// μ 越大，W 越被拉回 I (留在稳定流形内)，代价是对目标的拟合变松：残差 y - (W'·x + b) = (λ·E + μ·(E - D·x)) / (κ + s)。
+    /// 矩形门使用矩形单位阵 (I_ij = [i == j])。
+    pub fn compute_ideal_update_regularized(
+        input: &Vector,
+        target: &Vector,
+        current_gate: &AffineTuple,
+        identity_weight: Float,
+        proximity_weight: Float,
+    ) -> Matrix {
+        let rows = target.data.len();
+        let cols = input.data.len();
+        assert_eq!(
+            (current_gate.linear.rows, current_gate.linear.cols),
+            (rows, cols),
+            "Gate shape does not match target x input"
+        );
+        assert_eq!(current_gate.translation.data.len(), rows, "Gate bias does not match target dimension");
+
         // 1. Calculate Prediction Error: E = Target - (W * Input + b)
         let current_pred = current_gate.linear.matmul_vec(input);
         let current_pos = current_pred.add(&current_gate.translation);
@@ -54,39 +268,121 @@ impl LogicOracle {
         
         // 🛡️ Damping Factor (Lambda)
         // 物理意义：信噪比阈值。当 ||x||^2 << lambda 时，我们不信任该信号作为分母。
-        let lambda = 1e-6; 
+        // 无先验时只保留数值阻尼；启用先验时接近项按 `proximity_weight` 计权。
+        let mu = identity_weight.max(0.0);
+        let lambda = if mu > 0.0 { proximity_weight.max(SOLVER_DAMPING) } else { SOLVER_DAMPING };
+        let kappa = lambda + mu;
         
         // 分母不再可能为 0，保证 Lipschitz 连续性
-        let denominator = input_norm_sq + lambda;
+        let denominator = input_norm_sq + kappa;
 
-        // 3. Compute Outer Product with Damping: (E * x^T) / (||x||^2 + λ)
-        let mut delta_data = vec![0.0; MANIFOLD_DIM * MANIFOLD_DIM];
-        for i in 0..MANIFOLD_DIM {
+        // 3. Compute Outer Product with Damping: (E * x^T) / (||x||^2 + κ)
+        let mut delta_data = vec![0.0; rows * cols];
+        for i in 0..rows {
             // 预计算缩放因子，减少重复除法
             let factor = error.data[i] / denominator;
-            for j in 0..MANIFOLD_DIM {
-                delta_data[i * MANIFOLD_DIM + j] = factor * input.data[j];
+            for j in 0..cols {
+                delta_data[i * cols + j] = factor * input.data[j];
+            }
+        }
+
+        // 4. Identity Prior: + (μ/κ) · (D - (D·x)·x^T / (κ + s)),  D = I - W
+        if mu > 0.0 {
+            let pull = mu / kappa;
+            let w = &current_gate.linear;
+            let d_x = {
+                let mut v = current_pred.scale(-1.0);
+                for (i, d) in v.data.iter_mut().enumerate().take(cols) {
+                    *d += input.data[i];
+                }
+                v
+            };
+            for i in 0..rows {
+                let factor = d_x.data[i] / denominator;
+                for j in 0..cols {
+                    let eye = if i == j { 1.0 } else { 0.0 };
+                    let d_ij = eye - w.data[i * cols + j];
+                    delta_data[i * cols + j] += pull * (d_ij - factor * input.data[j]);
+                }
             }
         }
 
         Matrix {
-            rows: MANIFOLD_DIM,
-            cols: MANIFOLD_DIM,
+            rows,
+            cols,
             data: delta_data,
         }
     }
 
     /// 🎲 [Synthetic Data]: Generate Random Premise
     /// 生成一个随机的单位向量作为逻辑前提。
+    /// 与 `ConceptEmbedder::embed_token` 一致：先在 [-1, 1]^D 中采样，再归一化到单位球面。
     pub fn genesis_premise(seed: u64) -> Vector {
-        // Simple LCG based generation to avoid external 'rand' crate dependency for now
-        let mut data = Vec::with_capacity(MANIFOLD_DIM);
-        let mut state = seed;
-        for _ in 0..MANIFOLD_DIM {
-            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
-            let val = (state as f64 / u64::MAX as f64) as Float; // 0.0 to 1.0
-            data.push(val * 2.0 - 1.0); // -1.0 to 1.0
+        let mut rng = HtpRng::new(seed);
+        let data = (0..MANIFOLD_DIM).map(|_| rng.next_uniform()).collect();
+        Vector::new(data).normalize()
+    }
+
+    /// 🔔 [Synthetic Data]: Gaussian Premise
+    /// 各分量独立采样自 N(0, 1) 后归一化。高斯向量的方向在球面上严格均匀分布，
+    /// 而 [-1, 1]^D 均匀采样再归一化会偏向立方体的角 (对角方向)。
+    pub fn genesis_premise_gaussian(seed: u64) -> Vector {
+        let mut rng = HtpRng::new(seed);
+        Vector::new(rng.gaussian_vec(MANIFOLD_DIM, 1.0)).normalize()
+    }
+
+    /// 🎯 [Synthetic Data]: Generate Nearby Target
+    /// 为前提生成一个确定性的邻近目标: target = premise + 0.1 · u(seed)，u 为单位方向，
+    /// 因此 ||target - premise|| ≈ 0.1。同一 (premise, seed) 总是得到同一目标，适合求解器的可复现测试。
+    pub fn genesis_target(premise: &Vector, seed: u64) -> Vector {
+        const TARGET_RADIUS: Float = 0.1;
+        // 与前提的种子空间错开，避免 seed 相同时方向与前提重合
+        let direction = Self::genesis_premise(seed ^ 0xa5a5_a5a5_a5a5_a5a5);
+        premise.add(&direction.scale(TARGET_RADIUS))
+    }
+
+    /// 🔔 [Synthetic Data]: Gaussian Target
+    /// target = premise + ε，ε ~ N(0, σ² I)，σ = 0.1 / √D，因此 E||ε||² = 0.01 (距离约 0.1)。
+    /// 与 `genesis_target` 不同，偏移的长度也是随机的 (各向同性高斯噪声)。
+    pub fn genesis_target_gaussian(premise: &Vector, seed: u64) -> Vector {
+        let sigma = 0.1 / (premise.data.len() as Float).sqrt();
+        let mut rng = HtpRng::new(seed ^ 0xa5a5_a5a5_a5a5_a5a5);
+        premise.add(&Vector::new(rng.gaussian_vec(premise.data.len(), sigma)))
+    }
+
+    /// 🎲 [Synthetic Data]: Orthogonal Premise Batch
+    /// 生成 count 个两两正交的单位向量作为逻辑前提 (count ≤ D)。
+    ///
+    /// 随机前提之间存在相关性，会让最小二乘求解器病态 (Ill-conditioned)。
+    /// 这里对 genesis_premise 的输出做 Modified Gram-Schmidt 正交化，
+    /// 并重复一轮 ("Twice is enough") 以抵消 f32 的舍入误差。
+    pub fn genesis_premise_batch(seed: u64, count: usize) -> Vec<Vector> {
+        assert!(count <= MANIFOLD_DIM, "Cannot generate more than D orthogonal premises");
+
+        let dot = |a: &Vector, b: &Vector| -> Float {
+            a.data.iter().zip(&b.data).map(|(x, y)| x * y).sum()
+        };
+
+        let mut basis: Vec<Vector> = Vec::with_capacity(count);
+        let mut attempt: u64 = 0;
+        while basis.len() < count {
+            // 为每个候选向量派生一个去相关的种子
+            let sub_seed = seed.wrapping_add(attempt).wrapping_mul(0x9e3779b97f4a7c15);
+            attempt += 1;
+
+            let mut v = Self::genesis_premise(sub_seed);
+            for _ in 0..2 {
+                for q in &basis {
+                    v = v.sub(&q.scale(dot(&v, q)));
+                }
+            }
+
+            // 线性相关的候选 (残差过小) 直接丢弃，换下一个种子
+            if v.norm() < 1e-3 {
+                continue;
+            }
+            basis.push(v.normalize());
         }
-        Vector::new(data)
+        basis
     }
 }
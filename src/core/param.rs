@@ -1,10 +1,12 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
+use super::affine::AffineTuple;
 use super::algebra::{Float, MANIFOLD_DIM};
 use serde::{Serialize, Deserialize};
+use tracing::warn;
 
 /// ⚙️ HyperParams: 逻辑流形的物理法则配置
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct HyperParams {
     /// 📏 Manifold Dimension
     pub dimension: usize,
@@ -23,8 +25,49 @@ pub struct HyperParams {
     /// 但必须小于混沌阈值。
     pub lipschitz_bound: Float,
 
+    /// 🧮 End-to-End Lipschitz Target (全网络稳定性预算)
+    /// 复合会累积范数：深度为 d、每层上界为 K 的网络，端到端上界为 K^d。
+    /// 此字段是整条时间线允许的总放大倍率。
+    #[serde(default = "default_end_to_end_bound")]
+    pub end_to_end_bound: Float,
+
     /// 🎯 Zero-Hallucination Tolerance (Epsilon)
     pub tolerance_epsilon: Float,
+
+    /// 📜 Linear Proof Mode (线性证明模式)
+    /// 形式推理场景要求每一次折叠都能被精确逆推回输入。
+    /// 本架构的神经元是纯仿射的 (没有非线性激活)，因此唯一会破坏可逆性的是奇异的逻辑门：
+    /// 开启后，所有写入逻辑门的路径都必须保持其可逆，否则写入被拒绝 (原逻辑门保持不变)：
+    /// `HTPNeuron::set_logic_gate_checked`、优化器的 `step_neuron` (需 `with_linear_proof_mode`)、
+    /// `TrainingLoop` 的 One-Shot Solver / 事实注入，以及 `HTPNode` 的参数同步。
+    #[serde(default)]
+    pub linear_proof_mode: bool,
+}
+
+/// 🧩 PartialHyperParams: 部分覆盖配置
+/// 每个字段都是可选的，只有 `Some` 的字段会覆盖基础配置 (见 `HyperParams::merge`)。
+/// 用于 "基础配置 + 环境覆盖" 的分层配置；序列化时缺省字段即为 None。
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PartialHyperParams {
+    #[serde(default)]
+    pub dimension: Option<usize>,
+    #[serde(default)]
+    pub depth: Option<usize>,
+    #[serde(default)]
+    pub learning_rate: Option<Float>,
+    #[serde(default)]
+    pub lipschitz_bound: Option<Float>,
+    #[serde(default)]
+    pub end_to_end_bound: Option<Float>,
+    #[serde(default)]
+    pub tolerance_epsilon: Option<Float>,
+    #[serde(default)]
+    pub linear_proof_mode: Option<bool>,
+}
+
+/// 旧配置文件没有 end_to_end_bound 字段时的默认值 (与 validate 的混沌阈值一致)
+fn default_end_to_end_bound() -> Float {
+    2.0
 }
 
 impl Default for HyperParams {
@@ -34,7 +77,9 @@ impl Default for HyperParams {
             depth: 12,
             learning_rate: 1e-3,
             lipschitz_bound: 1.05, // 修正后的安全阈值
+            end_to_end_bound: default_end_to_end_bound(),
             tolerance_epsilon: 1e-4,
+            linear_proof_mode: false,
         }
     }
 }
@@ -46,7 +91,9 @@ impl HyperParams {
             depth: 24,
             learning_rate: 5e-4,
             lipschitz_bound: 1.01, // 接近等距映射
+            end_to_end_bound: default_end_to_end_bound(),
             tolerance_epsilon: 1e-6,
+            linear_proof_mode: false,
         }
     }
 
@@ -56,7 +103,71 @@ impl HyperParams {
             depth: 6,
             learning_rate: 1e-2,
             lipschitz_bound: 1.10, 
+            end_to_end_bound: default_end_to_end_bound(),
             tolerance_epsilon: 1e-3,
+            linear_proof_mode: false,
+        }
+    }
+
+    /// 🧮 当前配置下的端到端 Lipschitz 上界: K^depth
+    pub fn effective_lipschitz_budget(&self) -> Float {
+        self.lipschitz_bound.powi(self.depth as i32)
+    }
+
+    /// 🧮 为使端到端上界恰好等于 `end_to_end_bound`，每层允许的上界: K = target^(1/depth)
+    /// depth 为 0 时按单层处理。
+    pub fn per_layer_lipschitz_bound(&self) -> Float {
+        self.end_to_end_bound.powf(1.0 / self.depth.max(1) as Float)
+    }
+
+    /// 📜 证明模式下的逻辑门准入检查
+    /// 关闭时任何逻辑门都被接受；开启时逻辑门必须可逆 (AffineTuple::inverse 成功)。
+    /// 可逆门的任意复合仍可逆，因此整条时间线的折叠结果都能被精确逆推。
+    pub fn admit_gate(&self, gate: &AffineTuple) -> Result<(), String> {
+        if !self.linear_proof_mode {
+            return Ok(());
+        }
+        Self::require_invertible(gate)
+    }
+
+    /// 📜 证明模式的核心检查 (与开关无关)：逻辑门必须可逆
+    /// 供只持有开关、不持有完整配置的组件 (例如优化器) 复用。
+    pub fn require_invertible(gate: &AffineTuple) -> Result<(), String> {
+        gate.inverse()
+            .map(|_| ())
+            .map_err(|e| format!("📜 Linear proof mode rejects a non-invertible logic gate: {}", e))
+    }
+
+    /// 🧩 分层合并：以 self 为基础，仅应用覆盖中为 `Some` 的字段
+    /// 合并结果会重新执行 `validate`，非法组合 (例如覆盖了错误的维度) 返回 Err。
+    pub fn merge(&self, override_partial: PartialHyperParams) -> Result<HyperParams, String> {
+        let merged = HyperParams {
+            dimension: override_partial.dimension.unwrap_or(self.dimension),
+            depth: override_partial.depth.unwrap_or(self.depth),
+            learning_rate: override_partial.learning_rate.unwrap_or(self.learning_rate),
+            lipschitz_bound: override_partial.lipschitz_bound.unwrap_or(self.lipschitz_bound),
+            end_to_end_bound: override_partial.end_to_end_bound.unwrap_or(self.end_to_end_bound),
+            tolerance_epsilon: override_partial.tolerance_epsilon.unwrap_or(self.tolerance_epsilon),
+            linear_proof_mode: override_partial.linear_proof_mode.unwrap_or(self.linear_proof_mode),
+        };
+        merged.validate().map_err(|e| format!("Merged HyperParams are invalid: {}", e))?;
+        Ok(merged)
+    }
+
+    /// 🔍 差异：返回把 self 变为 `other` 所需的最小覆盖 (只包含不同的字段)
+    /// 满足 `self.merge(self.diff(other)) == other` (当 other 合法时)。
+    pub fn diff(&self, other: &HyperParams) -> PartialHyperParams {
+        fn changed<T: PartialEq + Copy>(base: T, other: T) -> Option<T> {
+            (base != other).then_some(other)
+        }
+        PartialHyperParams {
+            dimension: changed(self.dimension, other.dimension),
+            depth: changed(self.depth, other.depth),
+            learning_rate: changed(self.learning_rate, other.learning_rate),
+            lipschitz_bound: changed(self.lipschitz_bound, other.lipschitz_bound),
+            end_to_end_bound: changed(self.end_to_end_bound, other.end_to_end_bound),
+            tolerance_epsilon: changed(self.tolerance_epsilon, other.tolerance_epsilon),
+            linear_proof_mode: changed(self.linear_proof_mode, other.linear_proof_mode),
         }
     }
 
@@ -70,6 +181,17 @@ impl HyperParams {
         if self.lipschitz_bound > 2.0 {
             return Err("Lipschitz constant too high: Will cause Exploding Gradient / Chaos.".to_string());
         }
+        // 单层合法，但叠加 depth 层后可能爆炸：仅警告，不拒绝
+        let budget = self.effective_lipschitz_budget();
+        if budget > self.end_to_end_bound {
+            warn!(
+                lipschitz_bound = self.lipschitz_bound,
+                depth = self.depth,
+                budget,
+                per_layer = self.per_layer_lipschitz_bound(),
+                "⚠️ Per-layer Lipschitz bound explodes over depth (K^d exceeds end-to-end target)"
+            );
+        }
         Ok(())
     }
 }
@@ -0,0 +1,78 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+use std::f64::consts::PI;
+
+use super::algebra::Float;
+
+/// 🎲 HtpRng: 确定性伪随机数发生器 (合成数据、权重初始化与扰动共用)
+///
+/// 64 位 LCG (Knuth MMIX 常数)，不依赖外部 `rand` crate，同一种子在任何平台上都产生同一序列。
+/// `WeightInitializer` 的各种初始化与 `HTPNeuron::perturb` 都从这里取随机数。
+///
+/// * `next_unit`: [0, 1] 上的均匀分布
+/// * `next_uniform`: [-1, 1] 上的均匀分布
+/// * `next_gaussian`: 标准正态分布 N(0, 1) (Box-Muller 变换，成对生成，第二个样本缓存到下次调用)
+#[derive(Clone, Debug)]
+pub struct HtpRng {
+    state: u64,
+    /// Box-Muller 每次产生两个独立样本，多出的一个留给下一次调用
+    spare: Option<f64>,
+}
+
+impl HtpRng {
+    pub fn new(seed: u64) -> Self {
+        HtpRng { state: seed, spare: None }
+    }
+
+    /// 🔢 下一个原始 64 位状态
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1);
+        self.state
+    }
+
+    /// 📏 U[0, 1]
+    pub fn next_unit(&mut self) -> f64 {
+        self.next_u64() as f64 / u64::MAX as f64
+    }
+
+    /// 📏 U[-1, 1]
+    pub fn next_uniform(&mut self) -> Float {
+        (self.next_unit() as Float) * 2.0 - 1.0
+    }
+
+    /// 🔔 N(0, 1): Box-Muller
+    /// $z_0 = \sqrt{-2 \ln u_1} \cos(2\pi u_2)$, $z_1 = \sqrt{-2 \ln u_1} \sin(2\pi u_2)$
+    /// u1 取自 (0, 1]，避免 ln(0)。
+    pub fn next_gaussian(&mut self) -> Float {
+        if let Some(z) = self.spare.take() {
+            return z as Float;
+        }
+        let u1 = 1.0 - self.next_unit().min(1.0 - f64::EPSILON);
+        let u2 = self.next_unit();
+        let radius = (-2.0 * u1.ln()).sqrt();
+        let angle = 2.0 * PI * u2;
+        self.spare = Some(radius * angle.sin());
+        (radius * angle.cos()) as Float
+    }
+
+    /// 🔔 `len` 个 N(0, std²) 样本
+    pub fn gaussian_vec(&mut self, len: usize, std: Float) -> Vec<Float> {
+        (0..len).map(|_| self.next_gaussian() * std).collect()
+    }
+}
+
+/// 🌀 SplitMix64 终混器 (Finalizer)
+/// 把任意 64 位输入雪崩式地打散：相邻的输入得到互不相关的输出。
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// 🌱 子种子派生: 由 (主种子, 流编号) 得到独立的子种子 (例如每个 Epoch 的打乱种子)
+/// `seed ^ stream` 会让 (1, 0) 与 (0, 1) 撞车；先把主种子乘上奇数常数拉开间距再加流编号，
+/// 最后经 SplitMix64 混合。
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    splitmix64(seed.wrapping_mul(0x9e3779b97f4a7c15).wrapping_add(stream))
+}
@@ -42,6 +42,18 @@ pub mod net;
 #[cfg(test)]
 mod tests {
     pub mod streaming_test;
+    pub mod tensor_test;
+    pub mod affine_test;
+    pub mod algebra_test;
+    pub mod discovery_test;
+    pub mod fold_prop_test;
+    pub mod neuron_test;
+    pub mod node_test;
+    pub mod oracle_test;
+    pub mod param_test;
+    pub mod rng_test;
+    pub mod sim_test;
+    pub mod training_test;
 }
 
 // ==================================================================
@@ -56,15 +68,16 @@ pub mod prelude {
     
     // 2. Core Units
     pub use crate::core::neuron::HTPNeuron;
-    pub use crate::core::param::HyperParams;
+    pub use crate::core::param::{HyperParams, PartialHyperParams};
     pub use crate::core::oracle::LogicOracle;
     
     // 3. Initialization (Mapping "Primes" to "Embeddings")
-    pub use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+    pub use crate::core::primes::{CombineMode, ConceptEmbedder, WeightInitializer};
+    pub use crate::core::rng::HtpRng;
 
     // 4. Topology
-    pub use crate::topology::tensor::HyperTensor;
+    pub use crate::topology::tensor::{HyperTensor, MergeMode};
 
     // 5. Training
-    pub use crate::train_loop::{TrainingLoop, SimpleOptimizer};
+    pub use crate::train_loop::{TrainingLoop, SimpleOptimizer, AdamOptimizer, Dataset, EpochStats, OneShotFact, MixedStepStats};
 }
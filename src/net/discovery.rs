@@ -1,18 +1,33 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::ops::Range;
 use std::time::{Duration, SystemTime};
-use tokio::sync::RwLock;
-use log::{info, debug, warn};
+use tokio::sync::{RwLock, broadcast, watch};
+use tracing::{info, debug, warn, instrument};
 use rand::seq::SliceRandom;
 
 use crate::net::node::NodeRole;
+use crate::net::wire::{PacketType, PeerBrief};
 
 /// ⏱️ Peer Configuration
-const PEER_TTL_SECS: u64 = 60;   // 超过 60秒 没心跳视为下线
-const GOSSIP_INTERVAL_MS: u64 = 2000; // 每 2秒 八卦一次
+pub const HEARTBEAT_INTERVAL_MS: u64 = 500; // 每 0.5秒 发送一次轻量心跳 (只用于存活判定)
+const PEER_TTL_HEARTBEATS: u64 = 6;         // 连续错过 6 次心跳 (3秒) 视为下线
+pub const PEER_TTL_MS: u64 = HEARTBEAT_INTERVAL_MS * PEER_TTL_HEARTBEATS;
+pub const GOSSIP_INTERVAL_MS: u64 = 10_000; // 每 10秒 八卦一次 (交换完整的拓扑视图)
 const FANOUT: usize = 3;         // 每次随机告诉 3 个邻居
+/// 💓 每次心跳最多发送给多少个节点 (拓扑邻居优先，其余轮转)
+pub const MAX_HEARTBEAT_TARGETS: usize = 8;
+
+/// 🩺 Reliability Configuration
+const RELIABILITY_DECAY: f64 = 0.5;     // 每次超时，可靠度减半
+const RELIABILITY_RECOVERY: f64 = 0.1;  // 每次直接心跳，向 1.0 恢复 10%
+const FLAKY_THRESHOLD: f64 = 0.3;       // 低于此值的节点不作为 Parent 候选
+
+/// 🧠 Split-Brain 事件通道容量 (慢订阅者只会丢失最旧的事件)
+const SPLIT_BRAIN_CHANNEL_CAPACITY: usize = 16;
 
 /// 🏷️ PeerInfo: 邻居节点的身份卡片
 #[derive(Debug, Clone)]
@@ -21,7 +36,71 @@ pub struct PeerInfo {
     pub address: String, // IP:Port
     pub role: NodeRole,
     pub last_seen: SystemTime,
-    // 💡 Future: 加入 latency 或 load 指标用于更优的路由选择
+    /// 🩺 可靠度评分 [0, 1]：超时掉线时衰减，持续心跳时恢复
+    /// 这是本地观测值，不随 Gossip 传播。
+    pub reliability: f64,
+    /// 📊 负载 (如排队中的推理请求数，0 = 空闲)，本地观测值
+    pub load: f64,
+    /// ⏱️ 最近一次测得的往返延迟 (未测量时为 None)，本地观测值
+    pub latency: Option<Duration>,
+    /// 🚇 该节点持有的全局层区间 (模型并行)，None 表示未声明。随 Gossip 传播。
+    pub layers: Option<Range<usize>>,
+    /// 🕰️ 最近一次心跳报告的模型纪元 (尚未收到心跳时为 None)，本地观测值
+    pub epoch: Option<u64>,
+}
+
+impl PeerInfo {
+    /// 🏷️ 本记录的 Gossip 名片 (只含节点自述的配置)
+    pub fn brief(&self) -> PeerBrief {
+        PeerBrief {
+            id: self.id.clone(),
+            address: self.address.clone(),
+            role: self.role.clone(),
+            layers: self.layers.clone(),
+        }
+    }
+}
+
+/// 🏷️ 收到的名片视为此刻存活的新记录 (本地观测值取初始值，由合并策略决定是否采用)
+impl From<PeerBrief> for PeerInfo {
+    fn from(brief: PeerBrief) -> Self {
+        PeerInfo {
+            id: brief.id,
+            address: brief.address,
+            role: brief.role,
+            last_seen: SystemTime::now(),
+            reliability: 1.0,
+            load: 0.0,
+            latency: None,
+            layers: brief.layers,
+            epoch: None,
+        }
+    }
+}
+
+/// 🧭 RoutingStrategy: 推理请求的 Worker 选择策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    /// 🔁 按 ID 顺序轮询
+    RoundRobin,
+    /// 📊 选择负载最低的 Worker
+    LeastLoaded,
+    /// ⏱️ 选择延迟最低的 Worker (未测量的排在最后)
+    LowestLatency,
+}
+
+/// 🔀 GossipMergePolicy: 八卦记录与本地记录冲突时的合并方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GossipMergePolicy {
+    /// 🤝 并集 (默认): 收到八卦即视为存活 (last_seen 刷新为本地当前时间)，
+    /// 节点自述的层区间随之更新，地址与角色保留本地值。
+    #[default]
+    Union,
+    /// 🕰️ 时钟优先: 仅当对方记录的 last_seen 比本地更新时，整体采用对方的地址 / 角色 / 层区间与时钟；
+    /// 陈旧的八卦 (例如分区愈合后的过期洪泛) 被忽略，新节点也沿用对方的时钟 (不会被 "复活")。
+    PreferNewerClock,
+    /// 🏠 本地优先: 已知节点的记录完全不受八卦影响 (存活只由直接心跳判定)，只接纳全新的节点。
+    PreferLocal,
 }
 
 /// 🌳 Topology: 我在网络中的位置
@@ -35,61 +114,406 @@ pub struct Topology {
     pub is_root: bool,               // 我是否是最终的 Parameter Server
 }
 
+/// 🧠 SplitBrainDetected: 分区愈合后发现两个各自独立运行过的 PS
+///
+/// `rejoined_ps` 曾因心跳超时被移出路由表 (从本节点看与 `known_ps` 分属两个分区)，
+/// 如今在 `known_ps` 仍存活时重新出现。两者可能各自推进过模型，
+/// 上层逻辑应据此对账 (例如保留 Epoch 更高的一方)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitBrainDetected {
+    /// 分区期间一直可达的 PS (可能是本节点自己)
+    pub known_ps: String,
+    /// 超时后重新出现的 PS
+    pub rejoined_ps: String,
+}
+
 /// 📡 DiscoveryService: 负责节点发现与拓扑维护
 pub struct DiscoveryService {
     local_id: String,
     local_role: NodeRole,
     local_addr: String,
+    /// 🚇 本节点持有的全局层区间 (随 Gossip 名片传播，供 layer_owner 路由)
+    local_layers: Option<Range<usize>>,
     
     /// 📖 Routing Table: 这是一个线程安全的动态邻居表
     peers: Arc<RwLock<HashMap<String, PeerInfo>>>,
+
+    /// 🪦 Departed Ledger: 已掉线节点的可靠度记录
+    /// 节点重新加入时继承这里的评分，反复掉线的节点不会 "洗白"。
+    departed: Arc<RwLock<HashMap<String, f64>>>,
+
+    /// ⏱️ 心跳超时阈值 (默认 PEER_TTL_MS)
+    peer_ttl: Duration,
+
+    /// 📣 Topology Watch: 成员变化时推送最新拓扑 (Push-based)
+    /// 订阅者无需轮询 build_topology，PS 掉线后 Worker 可以立即重新挂载。
+    topology_tx: watch::Sender<Topology>,
+
+    /// 🔁 Round-Robin 游标
+    rr_cursor: AtomicUsize,
+
+    /// 💓 心跳目标的轮转游标 (非邻居节点分批轮流接收心跳)
+    heartbeat_cursor: AtomicUsize,
+
+    /// ✂️ 因心跳超时 (而非主动下线) 被移除的 PS，疑似处于另一个网络分区
+    partitioned_ps: Arc<RwLock<HashSet<String>>>,
+
+    /// 🧠 Split-Brain 事件广播
+    split_brain_tx: broadcast::Sender<SplitBrainDetected>,
+
+    /// 🔀 八卦合并策略 (默认 Union)
+    merge_policy: GossipMergePolicy,
+
+    /// 📦 路由表容量上限 (None = 不限)
+    /// 超出时淘汰最久未见的非 PS 节点，使大集群中的每个节点只维护有界的局部视图 (Partial View)。
+    max_peers: Option<usize>,
 }
 
 impl DiscoveryService {
     pub fn new(id: String, role: NodeRole, addr: String) -> Self {
+        // 初始拓扑：路由表为空，PS 即 Root，Worker 暂为孤儿
+        let initial = Topology {
+            parent: None,
+            children: Vec::new(),
+            is_root: role == NodeRole::ParameterServer,
+        };
         DiscoveryService {
             local_id: id,
             local_role: role,
             local_addr: addr,
+            local_layers: None,
             peers: Arc::new(RwLock::new(HashMap::new())),
+            departed: Arc::new(RwLock::new(HashMap::new())),
+            peer_ttl: Duration::from_millis(PEER_TTL_MS),
+            topology_tx: watch::channel(initial).0,
+            rr_cursor: AtomicUsize::new(0),
+            heartbeat_cursor: AtomicUsize::new(0),
+            partitioned_ps: Arc::new(RwLock::new(HashSet::new())),
+            split_brain_tx: broadcast::channel(SPLIT_BRAIN_CHANNEL_CAPACITY).0,
+            merge_policy: GossipMergePolicy::default(),
+            max_peers: None,
+        }
+    }
+
+    /// 🚇 声明本节点持有的全局层区间 (模型并行)
+    pub fn with_local_layers(mut self, layers: Range<usize>) -> Self {
+        self.local_layers = Some(layers);
+        self
+    }
+
+    /// 🆔 本节点 ID
+    pub fn local_id(&self) -> &str {
+        &self.local_id
+    }
+
+    /// ⏱️ 自定义心跳超时 (默认 PEER_TTL_MS，即 PEER_TTL_HEARTBEATS 个心跳周期)
+    pub fn with_peer_ttl(mut self, ttl: Duration) -> Self {
+        self.peer_ttl = ttl;
+        self
+    }
+
+    /// 🔀 设置八卦合并策略 (默认 Union)
+    pub fn with_merge_policy(mut self, policy: GossipMergePolicy) -> Self {
+        self.merge_policy = policy;
+        self
+    }
+
+    /// 📦 设置路由表容量上限 (默认不限)
+    /// PS 节点永不被淘汰：若 PS 数量本身超过上限，路由表只保留全部 PS。
+    pub fn with_max_peers(mut self, max_peers: usize) -> Self {
+        self.max_peers = Some(max_peers);
+        self
+    }
+
+    /// 📦 Helper: 路由表超出容量上限时，按 (last_seen, id) 从旧到新淘汰非 PS 节点
+    /// 淘汰不是故障，也不记入 Departed Ledger (否则账本本身会无界增长)。返回被淘汰的节点 ID。
+    fn evict_over_capacity(&self, peers: &mut HashMap<String, PeerInfo>) -> Vec<String> {
+        let Some(max_peers) = self.max_peers else {
+            return Vec::new();
+        };
+        let excess = peers.len().saturating_sub(max_peers);
+        if excess == 0 {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<&PeerInfo> = peers.values()
+            .filter(|p| p.role != NodeRole::ParameterServer)
+            .collect();
+        candidates.sort_by(|a, b| a.last_seen.cmp(&b.last_seen).then_with(|| a.id.cmp(&b.id)));
+        let evicted: Vec<String> = candidates.iter()
+            .take(excess)
+            .map(|p| p.id.clone())
+            .collect();
+
+        for id in &evicted {
+            peers.remove(id);
+            debug!(node_id = %self.local_id, peer_id = %id, "📦 Peer table full. Evicting least-recently-seen peer.");
         }
+        evicted
+    }
+
+    /// 📣 订阅拓扑变化事件
+    /// 每当可达的 PS/Worker 集合发生变化，Receiver 会收到重新构建的 Topology。
+    pub fn topology_changed(&self) -> watch::Receiver<Topology> {
+        self.topology_tx.subscribe()
+    }
+
+    /// 🧠 订阅 Split-Brain 事件
+    pub fn split_brain_events(&self) -> broadcast::Receiver<SplitBrainDetected> {
+        self.split_brain_tx.subscribe()
+    }
+
+    /// 🧠 Helper: 超时掉线的 PS 重新出现时，若还有其他 PS 存活 (含本节点)，广播 Split-Brain 事件
+    async fn check_split_brain(&self, peers: &HashMap<String, PeerInfo>, rejoined: &[String]) {
+        let mut partitioned = self.partitioned_ps.write().await;
+        for rejoined_ps in rejoined {
+            if !partitioned.remove(rejoined_ps) {
+                continue;
+            }
+            let mut known: Vec<&str> = peers.values()
+                .filter(|p| p.role == NodeRole::ParameterServer && &p.id != rejoined_ps)
+                .map(|p| p.id.as_str())
+                .collect();
+            if self.local_role == NodeRole::ParameterServer {
+                known.push(&self.local_id);
+            }
+            known.sort();
+
+            for known_ps in known {
+                warn!(known_ps, rejoined_ps = %rejoined_ps, "🧠 Split-brain detected: partitioned PS rejoined");
+                // 没有订阅者时发送失败，忽略即可
+                let _ = self.split_brain_tx.send(SplitBrainDetected {
+                    known_ps: known_ps.to_string(),
+                    rejoined_ps: rejoined_ps.clone(),
+                });
+            }
+        }
+    }
+
+    /// 📣 Helper: 成员集合变化时重建拓扑并推送给所有订阅者
+    fn notify_topology(&self, peers: &HashMap<String, PeerInfo>) {
+        let topology = self.derive_topology(peers);
+        debug!(node_id = %self.local_id, parent = ?topology.parent.as_ref().map(|p| &p.id),
+            "📣 Membership changed. Broadcasting new topology");
+        // send_replace 即使当前没有订阅者也会更新值
+        self.topology_tx.send_replace(topology);
     }
 
     /// 🌱 Seeding: 注入初始种子节点 (Bootstrapping)
     pub async fn add_seed_peer(&self, id: String, addr: String, role: NodeRole) {
-        let mut peers = self.peers.write().await;
-        peers.insert(id.clone(), PeerInfo {
-            id,
-            address: addr,
-            role,
-            last_seen: SystemTime::now(),
-        });
+        self.register_heartbeat(id, addr, role).await;
+    }
+
+    /// 🌱 Multi-Seed: 注入多个种子节点 (消除单一种子的单点故障)
+    /// 每个条目格式为 "id@ip:port"。先整体校验，任一条目格式错误时不注册任何种子并返回 Err；
+    /// 成功时按原顺序返回 (id, addr)，供调用方依次尝试握手直到某个种子响应。
+    pub async fn add_seed_peers(&self, seeds: &[String], role: NodeRole) -> Result<Vec<(String, String)>, String> {
+        let parsed = seeds.iter()
+            .map(|spec| match spec.trim().split_once('@') {
+                Some((id, addr)) if !id.is_empty() && !addr.is_empty() => Ok((id.to_string(), addr.to_string())),
+                _ => Err(format!("Invalid seed '{}': expected format id@ip:port", spec)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        for (id, addr) in &parsed {
+            self.add_seed_peer(id.clone(), addr.clone(), role.clone()).await;
+        }
+        Ok(parsed)
     }
 
     /// 💓 Heartbeat: 更新某个节点的状态 (“我听到它的心跳了”)
+    /// 持续的直接心跳会逐步恢复该节点的可靠度。已知节点的角色改变时 (例如 Worker 升级为 PS) 重新推送拓扑。
     pub async fn register_heartbeat(&self, id: String, addr: String, role: NodeRole) {
         let mut peers = self.peers.write().await;
+        if let Some(peer) = peers.get_mut(&id) {
+            let role_changed = peer.role != role;
+            peer.address = addr;
+            peer.role = role;
+            peer.last_seen = SystemTime::now();
+            peer.reliability += (1.0 - peer.reliability) * RELIABILITY_RECOVERY;
+            if role_changed {
+                self.notify_topology(&peers);
+            }
+            return;
+        }
+
+        // 新节点 (或重新加入的节点)：继承掉线前的评分
+        let reliability = self.departed.write().await.remove(&id).unwrap_or(1.0);
+        let rejoined_ps = (role == NodeRole::ParameterServer).then(|| id.clone());
         peers.insert(id.clone(), PeerInfo {
             id,
             address: addr,
             role,
             last_seen: SystemTime::now(),
+            reliability,
+            load: 0.0,
+            latency: None,
+            layers: None,
+            epoch: None,
         });
+        self.evict_over_capacity(&mut peers);
+        if let Some(rejoined_ps) = rejoined_ps {
+            self.check_split_brain(&peers, &[rejoined_ps]).await;
+        }
+        self.notify_topology(&peers);
+    }
+
+    /// 💓 Lightweight Heartbeat: 处理邻居的轻量心跳包 (与 Gossip 解耦)
+    /// 只刷新存活时钟与可靠度，并记录对方自报的纪元与负载；不携带地址与路由表，因此不会改变拓扑。
+    /// 未知节点的心跳被忽略 (新节点只能经握手 / Gossip 加入)，返回该节点是否在路由表中。
+    pub async fn handle_heartbeat(&self, node_id: &str, epoch: u64, load: f64) -> bool {
+        let mut peers = self.peers.write().await;
+        let Some(peer) = peers.get_mut(node_id) else {
+            debug!(peer_id = node_id, "💓 Heartbeat from unknown peer ignored");
+            return false;
+        };
+        peer.last_seen = SystemTime::now();
+        peer.reliability += (1.0 - peer.reliability) * RELIABILITY_RECOVERY;
+        peer.epoch = Some(epoch);
+        peer.load = load;
+        true
+    }
+
+    /// 🩺 查询某个邻居的当前信息 (包括可靠度)
+    pub async fn get_peer(&self, id: &str) -> Option<PeerInfo> {
+        self.peers.read().await.get(id).cloned()
+    }
+
+    /// 📊 更新某个邻居的负载与延迟观测值 (未知节点忽略)
+    pub async fn report_metrics(&self, id: &str, load: f64, latency: Option<Duration>) {
+        if let Some(peer) = self.peers.write().await.get_mut(id) {
+            peer.load = load;
+            if latency.is_some() {
+                peer.latency = latency;
+            }
+        }
+    }
+
+    /// 🚇 记录某个邻居持有的层区间 (未知节点忽略)
+    pub async fn register_layers(&self, id: &str, layers: Range<usize>) {
+        if let Some(peer) = self.peers.write().await.get_mut(id) {
+            peer.layers = Some(layers);
+        }
+    }
+
+    /// 🚇 Pipeline Routing: 查找持有第 `layer` 层的 Worker
+    /// 多个副本时与 select_worker 一致地避开不稳定节点，并按 ID 取第一个 (确定性)。
+    pub async fn layer_owner(&self, layer: usize) -> Option<PeerInfo> {
+        let peers = self.peers.read().await;
+        let mut owners: Vec<&PeerInfo> = peers.values()
+            .filter(|p| p.role == NodeRole::Worker)
+            .filter(|p| p.layers.as_ref().is_some_and(|r| r.contains(&layer)))
+            .collect();
+        owners.sort_by_key(|p| &p.id);
+        owners.iter()
+            .find(|p| p.reliability >= FLAKY_THRESHOLD)
+            .or_else(|| owners.first())
+            .map(|p| (*p).clone())
+    }
+
+    /// 🧭 Inference Routing: 为推理请求挑选一个 Worker
+    ///
+    /// 候选集为所有 Worker (按 ID 排序，保证轮询顺序确定)，
+    /// 与 Parent 选择一致，不稳定的 Worker 会被避开 (除非全部不稳定)。
+    pub async fn select_worker(&self, strategy: RoutingStrategy) -> Option<PeerInfo> {
+        let peers = self.peers.read().await;
+        let mut workers: Vec<&PeerInfo> = peers.values()
+            .filter(|p| p.role == NodeRole::Worker)
+            .collect();
+        workers.sort_by_key(|p| &p.id);
+        if workers.iter().any(|p| p.reliability >= FLAKY_THRESHOLD) {
+            workers.retain(|p| p.reliability >= FLAKY_THRESHOLD);
+        }
+        if workers.is_empty() {
+            return None;
+        }
+
+        let chosen = match strategy {
+            RoutingStrategy::RoundRobin => {
+                let cursor = self.rr_cursor.fetch_add(1, Ordering::Relaxed);
+                workers[cursor % workers.len()]
+            }
+            RoutingStrategy::LeastLoaded => workers.iter()
+                .min_by(|a, b| a.load.total_cmp(&b.load))
+                .copied()?,
+            RoutingStrategy::LowestLatency => workers.iter()
+                .min_by_key(|p| p.latency.unwrap_or(Duration::MAX))
+                .copied()?,
+        };
+        Some(chosen.clone())
     }
 
     /// 🗑️ GC: 清理掉线的节点
+    /// 超时节点的可靠度衰减后记入 Departed Ledger；超时的 PS 标记为疑似分区 (见 SplitBrainDetected)。
+    #[instrument(name = "discovery.purge", skip(self), fields(node_id = %self.local_id))]
     pub async fn purge_dead_peers(&self) {
         let mut peers = self.peers.write().await;
         let now = SystemTime::now();
-        peers.retain(|id, info| {
-            if let Ok(duration) = now.duration_since(info.last_seen) {
-                if duration.as_secs() < PEER_TTL_SECS {
-                    return true;
+        let ttl = self.peer_ttl;
+
+        let dead: Vec<String> = peers.values()
+            .filter(|info| now.duration_since(info.last_seen).map_or(true, |d| d >= ttl))
+            .map(|info| info.id.clone())
+            .collect();
+        if dead.is_empty() {
+            return;
+        }
+
+        let mut departed = self.departed.write().await;
+        let mut partitioned = self.partitioned_ps.write().await;
+        for id in dead {
+            if let Some(info) = peers.remove(&id) {
+                info!(peer_id = %id, "💀 Peer timed out. Removing from topology.");
+                if info.role == NodeRole::ParameterServer {
+                    partitioned.insert(id.clone());
                 }
+                departed.insert(id, info.reliability * RELIABILITY_DECAY);
             }
-            info!("💀 Peer [{}] timed out. Removing from topology.", id);
-            false
-        });
+        }
+        self.notify_topology(&peers);
+    }
+
+    /// 👋 Graceful Departure: 处理邻居的主动下线通知
+    /// 立即移除该节点并重建拓扑，无需等待 `peer_ttl` 超时。
+    /// 主动下线不是故障，可靠度原样记入 Departed Ledger (不衰减)。
+    /// 返回该节点是否在路由表中。
+    #[instrument(name = "discovery.leave", skip(self), fields(node_id = %self.local_id))]
+    pub async fn handle_leave(&self, peer_id: &str) -> bool {
+        let mut peers = self.peers.write().await;
+        let Some(info) = peers.remove(peer_id) else {
+            return false;
+        };
+        info!(peer_id, "👋 Peer left gracefully. Removing from topology.");
+        self.departed.write().await.insert(info.id, info.reliability);
+        self.notify_topology(&peers);
+        true
+    }
+
+    /// 💓 Heartbeat Targets: 本轮心跳的接收方 (至多 MAX_HEARTBEAT_TARGETS 个地址)
+    /// 拓扑邻居 (Parent 与 Children，至多占一半名额) 每轮都会收到；剩余名额按 ID 顺序在其他节点间轮转，
+    /// 路由表不超过 MAX_HEARTBEAT_TARGETS x PEER_TTL_HEARTBEATS 个节点时，每个节点在 TTL 内至少收到一次。
+    /// 心跳的开销因此与集群规模无关，而不是每 0.5秒 连接全部节点。
+    pub async fn heartbeat_targets(&self) -> Vec<String> {
+        let peers = self.peers.read().await;
+        let topology = self.topology_tx.borrow().clone();
+
+        let mut targets: Vec<String> = topology.parent.iter()
+            .chain(&topology.children)
+            .filter(|p| peers.contains_key(&p.id))
+            .map(|p| p.address.clone())
+            .take(MAX_HEARTBEAT_TARGETS / 2)
+            .collect();
+
+        let mut others: Vec<&PeerInfo> = peers.values()
+            .filter(|p| !targets.contains(&p.address))
+            .collect();
+        others.sort_by(|a, b| a.id.cmp(&b.id));
+        let slots = (MAX_HEARTBEAT_TARGETS - targets.len()).min(others.len());
+        if slots > 0 {
+            let start = self.heartbeat_cursor.fetch_add(slots, Ordering::Relaxed) % others.len();
+            targets.extend((0..slots).map(|k| others[(start + k) % others.len()].address.clone()));
+        }
+        targets
     }
 
     /// 🗣️ Gossip Protocol: 生成要发送给邻居的“八卦”信息
@@ -100,41 +524,102 @@ impl DiscoveryService {
         // 1. 获取当前所有活着的节点列表
         let all_peers: Vec<PeerInfo> = peers.values().cloned().collect();
         
-        // 2. 随机选择 k 个目标进行传播 (Fan-out)
+        // 2. 按可靠度加权随机选择 k 个目标进行传播 (Fan-out)
+        // 不稳定的节点仍有机会被选中 (权重下限)，但概率显著降低
         let mut rng = rand::thread_rng();
-        let targets: Vec<String> = all_peers
-            .choose_multiple(&mut rng, FANOUT)
-            .map(|p| p.address.clone())
-            .collect();
+        let targets: Vec<String> = match all_peers
+            .choose_multiple_weighted(&mut rng, FANOUT, |p| p.reliability.max(0.01))
+        {
+            Ok(chosen) => chosen.map(|p| p.address.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
             
         // 3. 构建只有 ID/Addr/Role 的轻量级列表用于交换
         // (实际中可能只交换增量，这里为了演示交换全量)
         (targets, all_peers)
     }
 
+    /// 🗣️ 把 `generate_gossip` 的全网视图打包成 PeerDiscovery 包
+    /// 名片列表附上本节点自己，接收方由此认识发送者 (路由表不记录自己，因此视图中原本没有)。
+    pub fn gossip_packet(&self, peers: &[PeerInfo]) -> PacketType {
+        let mut briefs: Vec<PeerBrief> = peers.iter().map(PeerInfo::brief).collect();
+        briefs.push(PeerBrief {
+            id: self.local_id.clone(),
+            address: self.local_addr.clone(),
+            role: self.local_role.clone(),
+            layers: self.local_layers.clone(),
+        });
+        PacketType::PeerDiscovery { sender_id: self.local_id.clone(), peers: briefs }
+    }
+
     /// 🗣️ Gossip Handler: 处理收到的“八卦”
+    /// 已知节点的冲突记录按 `merge_policy` 合并 (见 GossipMergePolicy)；未知节点总是被接纳。
+    #[instrument(name = "discovery.gossip", skip_all, fields(node_id = %self.local_id, incoming = incoming_peers.len()))]
     pub async fn handle_gossip(&self, incoming_peers: Vec<PeerInfo>) {
         let mut local_peers = self.peers.write().await;
+        let mut departed = self.departed.write().await;
+        let before = local_peers.len();
+        let mut role_changed = false;
+        let mut new_ps = Vec::new();
         for p in incoming_peers {
             // 不记录自己
             if p.id == self.local_id { continue; }
 
-            // 简单的 LWW (Last-Write-Wins) 策略
-            // 如果对方发来的节点我们没见过，或者比我们要新，就更新
-            // 注意：这里用 SystemTime 其实有分布式时钟问题，
+            // 注意：SystemTime 存在分布式时钟问题，
             // 严谨做法应使用 Logical Clock (Lamport Clock) 或 Vector Clock。
-            // 但对于 Peer Discovery 的“存活”判定，本地时间收到消息的时间点即可。
-            
-            // 这里我们简化为：只要收到八卦，就认为该节点还活着
-            local_peers.entry(p.id.clone())
-                .and_modify(|local| local.last_seen = SystemTime::now())
-                .or_insert_with(|| {
-                    info!("✨ Discovered new peer via Gossip: [{}]", p.id);
-                    PeerInfo {
-                        last_seen: SystemTime::now(),
-                        ..p
+            // 但对于 Peer Discovery 的“存活”判定，本地时间收到消息的时间点即可 (Union)。
+            if let Some(local) = local_peers.get_mut(&p.id) {
+                match self.merge_policy {
+                    GossipMergePolicy::Union => {
+                        // 只要收到八卦，就认为该节点还活着
+                        local.last_seen = SystemTime::now();
+                        // 层区间是节点自述的配置 (非本地观测值)，随 Gossip 更新
+                        if p.layers.is_some() {
+                            local.layers = p.layers.clone();
+                        }
                     }
-                });
+                    GossipMergePolicy::PreferNewerClock => {
+                        if p.last_seen > local.last_seen {
+                            role_changed |= local.role != p.role;
+                            local.address = p.address.clone();
+                            local.role = p.role.clone();
+                            local.last_seen = p.last_seen;
+                            if p.layers.is_some() {
+                                local.layers = p.layers.clone();
+                            }
+                        }
+                    }
+                    GossipMergePolicy::PreferLocal => {}
+                }
+                continue;
+            }
+
+            info!(peer_id = %p.id, "✨ Discovered new peer via Gossip");
+            if p.role == NodeRole::ParameterServer {
+                new_ps.push(p.id.clone());
+            }
+            // 可靠度/负载/延迟都是本地观测值：忽略对方的数值，继承本地的掉线记录
+            let reliability = departed.remove(&p.id).unwrap_or(1.0);
+            let last_seen = match self.merge_policy {
+                GossipMergePolicy::PreferNewerClock => p.last_seen,
+                GossipMergePolicy::Union | GossipMergePolicy::PreferLocal => SystemTime::now(),
+            };
+            local_peers.insert(p.id.clone(), PeerInfo {
+                last_seen,
+                reliability,
+                load: 0.0,
+                latency: None,
+                epoch: None,
+                ..p
+            });
+        }
+
+        let evicted = self.evict_over_capacity(&mut local_peers);
+        self.check_split_brain(&local_peers, &new_ps).await;
+
+        // 只有新节点加入 / 被淘汰 (或角色改变) 才会改变拓扑；单纯的存活刷新不触发事件
+        if local_peers.len() != before || !evicted.is_empty() || role_changed {
+            self.notify_topology(&local_peers);
         }
     }
 
@@ -153,14 +638,24 @@ impl DiscoveryService {
     /// 如果有多个 PS，Worker 会通过取模 (Hash % PS_Count) 自动负载均衡。
     pub async fn build_topology(&self) -> Topology {
         let peers_guard = self.peers.read().await;
-        
+        self.derive_topology(&peers_guard)
+    }
+
+    /// 🧮 纯函数：根据给定的路由表推导拓扑 (供 build_topology 与事件推送共用)
+    fn derive_topology(&self, peers: &HashMap<String, PeerInfo>) -> Topology {
         // 1. 区分角色
-        let mut ps_nodes: Vec<&PeerInfo> = peers_guard.values()
+        let mut ps_nodes: Vec<&PeerInfo> = peers.values()
             .filter(|p| p.role == NodeRole::ParameterServer)
             .collect();
         // 确保 PS 列表顺序确定
         ps_nodes.sort_by_key(|p| &p.id);
 
+        // 🩺 避开不稳定的 PS (除非所有 PS 都不稳定)
+        // 注意：可靠度是本地观测值，这会让不同节点的拓扑略有差异，换取集群稳定性。
+        if ps_nodes.iter().any(|p| p.reliability >= FLAKY_THRESHOLD) {
+            ps_nodes.retain(|p| p.reliability >= FLAKY_THRESHOLD);
+        }
+
         // 如果我是 PS
         if self.local_role == NodeRole::ParameterServer {
             // 简单的逻辑：PS 负责所有连接到它的 Workers
@@ -180,7 +675,7 @@ impl DiscoveryService {
         
         if ps_nodes.is_empty() {
             // 孤儿模式：没有发现 PS
-            warn!("⚠️ No Parameter Server found! Topology is broken.");
+            warn!(node_id = %self.local_id, "⚠️ No Parameter Server found! Topology is broken.");
             return Topology { parent: None, children: vec![], is_root: false };
         }
 
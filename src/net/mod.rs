@@ -9,8 +9,17 @@
 /// 3. Synchronization: 模型参数快照 (Model Snapshots)
 pub mod wire;
 
-// 🔮 Future Roadmap (待实现模块):
-//
-// pub mod node;      // P2P 节点逻辑 (Worker / Parameter Server)
-// pub mod discovery; // 节点发现与拓扑构建
-// pub mod sync;      // 梯度聚合算法 (Ring-AllReduce / Gossip)
+/// 🤖 Node: P2P 节点逻辑 (Worker / Parameter Server)
+pub mod node;
+
+/// 🔭 Discovery: 节点发现、Gossip 与拓扑构建 (支持拓扑变化事件推送)
+pub mod discovery;
+
+/// 🌊 Sync: 梯度聚合 (加权平均，支持多层梯度包的原子聚合) 与按来源限流
+pub mod sync;
+
+/// 🧪 Sim: 单进程多节点仿真 (内存通道代替 QUIC，确定性地驱动完整训练回路)
+pub mod sim;
+
+/// 🛰️ Transport: QUIC Endpoint 与 TLS 配置 (节点与客户端共用)
+pub mod transport;
@@ -1,18 +1,30 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use log::{info, warn, error};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use arc_swap::ArcSwap;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error as _;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{info, warn, instrument};
 
 use crate::core::algebra::{Vector, Matrix};
 use crate::core::affine::AffineTuple;
 use crate::core::neuron::HTPNeuron;
 use crate::core::oracle::LogicOracle;
+use crate::core::param::HyperParams;
 use crate::topology::tensor::HyperTensor;
-use crate::net::wire::{PacketType, GradientUpdate, ModelSnapshot, LayerState};
+use crate::topology::folding::HyperFolder;
+use crate::topology::merkle::{CausalTrace, OpType};
+use crate::net::wire::{PacketType, ErrorCode, FoldMode, GradientUpdate, MultiLayerGradient, ModelSnapshot, LayerState, TraceAssembler};
+use crate::net::sync::GradientRateLimiter;
 use crate::train_loop::SimpleOptimizer;
 
 /// 🎭 NodeRole: 节点身份
+/// 线上 (Gossip 名片) 编码为 `wire_tag()` 的显式标签 (u32)，与变体的声明顺序无关。
 #[derive(Debug, Clone, PartialEq)]
 pub enum NodeRole {
     /// 👷 Worker: 负责执行前向推理和反向传播计算
@@ -21,6 +33,123 @@ pub enum NodeRole {
     ParameterServer,
 }
 
+/// 🔤 "worker" / "ps" (与 CLI 参数一致)
+impl fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeRole::Worker => write!(f, "worker"),
+            NodeRole::ParameterServer => write!(f, "ps"),
+        }
+    }
+}
+
+impl NodeRole {
+    /// 🏷️ 显式线上标签 (一经分配永不改变，见 `PacketType::wire_tag`)
+    pub fn wire_tag(&self) -> u32 {
+        match self {
+            NodeRole::Worker => 0,
+            NodeRole::ParameterServer => 1,
+        }
+    }
+
+    /// 🏷️ 由线上标签还原 (未知标签为 None)
+    pub fn from_wire_tag(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(NodeRole::Worker),
+            1 => Some(NodeRole::ParameterServer),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for NodeRole {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.wire_tag())
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeRole {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tag = u32::deserialize(deserializer)?;
+        Self::from_wire_tag(tag).ok_or_else(|| D::Error::custom(format!("Unknown NodeRole tag {}", tag)))
+    }
+}
+
+impl FromStr for NodeRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "worker" => Ok(NodeRole::Worker),
+            "ps" => Ok(NodeRole::ParameterServer),
+            other => Err(format!("Invalid role '{}'. Use 'worker' or 'ps'.", other)),
+        }
+    }
+}
+
+/// 🔁 重连追赶时允许发送增量快照的最大落后纪元数 (超过则发送完整快照)
+const DEFAULT_MAX_DELTA_EPOCHS: u64 = 16;
+
+/// 📶 TrafficReport: 一个纪元内梯度与快照的流量 (按序列化后的字节数计)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficReport {
+    pub epoch: u64,
+    pub gradient_bytes_received: u64,
+    pub gradient_bytes_sent: u64,
+    pub snapshot_bytes_received: u64,
+    pub snapshot_bytes_sent: u64,
+}
+
+/// 📶 TrafficCounters: 当前纪元的流量计数 (无锁，统计用途，Relaxed 即可)
+#[derive(Default)]
+struct TrafficCounters {
+    /// 计数所属的纪元
+    epoch: AtomicU64,
+    gradient_in: AtomicU64,
+    gradient_out: AtomicU64,
+    snapshot_in: AtomicU64,
+    snapshot_out: AtomicU64,
+}
+
+impl TrafficCounters {
+    /// 只统计梯度与快照包，其余包忽略
+    fn record(&self, packet: &PacketType, outbound: bool) {
+        let counter = match (packet, outbound) {
+            (PacketType::GradientPush(_) | PacketType::MultiGradientPush(_), false) => &self.gradient_in,
+            (PacketType::GradientPush(_) | PacketType::MultiGradientPush(_), true) => &self.gradient_out,
+            (PacketType::ParameterBroadcast(_), false) => &self.snapshot_in,
+            (PacketType::ParameterBroadcast(_), true) => &self.snapshot_out,
+            _ => return,
+        };
+        counter.fetch_add(packet.wire_size() as u64, Ordering::Relaxed);
+    }
+
+    fn report(&self) -> TrafficReport {
+        TrafficReport {
+            epoch: self.epoch.load(Ordering::Relaxed),
+            gradient_bytes_received: self.gradient_in.load(Ordering::Relaxed),
+            gradient_bytes_sent: self.gradient_out.load(Ordering::Relaxed),
+            snapshot_bytes_received: self.snapshot_in.load(Ordering::Relaxed),
+            snapshot_bytes_sent: self.snapshot_out.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 🔄 纪元变化时清零计数，并返回上一纪元的汇总
+    fn roll_over(&self, epoch: u64) -> Option<TrafficReport> {
+        let previous = self.epoch.swap(epoch, Ordering::Relaxed);
+        if previous == epoch {
+            return None;
+        }
+        Some(TrafficReport {
+            epoch: previous,
+            gradient_bytes_received: self.gradient_in.swap(0, Ordering::Relaxed),
+            gradient_bytes_sent: self.gradient_out.swap(0, Ordering::Relaxed),
+            snapshot_bytes_received: self.snapshot_in.swap(0, Ordering::Relaxed),
+            snapshot_bytes_sent: self.snapshot_out.swap(0, Ordering::Relaxed),
+        })
+    }
+}
+
 /// 🤖 HTPNode: 神经节点实体
 pub struct HTPNode {
     pub id: String,
@@ -28,11 +157,51 @@ pub struct HTPNode {
     
     /// 🧠 Local Memory: 本地存储的神经网络模型
     /// Worker 存的是副本 (Cache)，PS 存的是真理 (Master)
-    /// 使用 Arc<RwLock> 实现线程安全的并发访问
-    pub model: Arc<RwLock<Vec<HTPNeuron>>>,
+    /// 双缓冲 (Double-Buffered): 推理无锁读取当前版本的快照，
+    /// 更新在副本上完成后原子替换，训练不会阻塞在线推理。
+    pub model: Arc<ArcSwap<Vec<HTPNeuron>>>,
+
+    /// ✍️ Writer Lock: 串行化所有模型写入 (Copy -> Modify -> Swap)，防止并发更新互相覆盖
+    /// 读者 (推理) 从不获取此锁。
+    model_writer: AsyncMutex<()>,
 
     /// ⚡ Optimizer: 仅 PS 节点持有，用于更新权重
-    pub optimizer: Option<SimpleOptimizer>,
+    /// 带有按层的动量缓冲 (可变状态)，因此以 Mutex 保护
+    pub optimizer: Option<AsyncMutex<SimpleOptimizer>>,
+
+    /// 🕰️ Model Epoch: 当前模型所处的纪元 (用于拒绝过期梯度、标记快照)
+    epoch: AtomicU64,
+
+    /// 🔢 Model Version: 每次替换模型 (梯度更新或参数同步) 都递增
+    model_version: AtomicU64,
+
+    /// ⏭️ 因内容哈希一致而跳过的参数同步次数
+    skipped_syncs: AtomicU64,
+
+    /// 🚦 Rate Limiter: (可选) 按来源节点限制梯度推送速率
+    rate_limiter: Option<Mutex<GradientRateLimiter>>,
+
+    /// 📶 当前纪元的梯度 / 快照流量
+    traffic: TrafficCounters,
+
+    /// 🚇 Layer Shard: 本地模型对应全局的第 [layer_offset, layer_offset + len) 层
+    /// 单机部署时为 0 (持有全部层)。
+    layer_offset: usize,
+
+    /// 🚇 全局模型的总层数 (用于判断流水线是否到达最后一段)
+    total_layers: usize,
+
+    /// 🏷️ 每一层 (本地下标) 最后一次被写入时的纪元，用于重连时计算增量快照
+    layer_epochs: Mutex<Vec<u64>>,
+
+    /// 🔁 落后超过此纪元数的重连节点直接获得完整快照
+    max_delta_epochs: u64,
+
+    /// 🧩 分片到达的 TraceTransfer 的重组缓冲 (有上限与 TTL)
+    trace_assembler: Mutex<TraceAssembler>,
+
+    /// ⚙️ 节点配置 (`from_params` 传入；目前用于证明模式下的逻辑门准入)
+    params: HyperParams,
 }
 
 impl HTPNode {
@@ -45,24 +214,184 @@ impl HTPNode {
         }
 
         let optimizer = match role {
-            NodeRole::ParameterServer => Some(SimpleOptimizer::new(1e-3)), // 默认学习率
+            NodeRole::ParameterServer => Some(AsyncMutex::new(SimpleOptimizer::new(1e-3))), // 默认学习率
             NodeRole::Worker => None,
         };
 
         HTPNode {
             id,
             role,
-            model: Arc::new(RwLock::new(neurons)),
+            model: Arc::new(ArcSwap::from_pointee(neurons)),
+            model_writer: AsyncMutex::new(()),
             optimizer,
+            epoch: AtomicU64::new(0),
+            model_version: AtomicU64::new(0),
+            skipped_syncs: AtomicU64::new(0),
+            rate_limiter: None,
+            traffic: TrafficCounters::default(),
+            layer_offset: 0,
+            total_layers: model_depth,
+            layer_epochs: Mutex::new(vec![0; model_depth]),
+            max_delta_epochs: DEFAULT_MAX_DELTA_EPOCHS,
+            trace_assembler: Mutex::new(TraceAssembler::new()),
+            params: HyperParams::default(),
+        }
+    }
+
+    /// ⚙️ 按配置初始化节点: 深度取 `params.depth`，PS 的学习率取 `params.learning_rate`
+    /// 先执行 `HyperParams::validate`，配置非法 (例如维度与编译时的 MANIFOLD_DIM 不符) 时拒绝启动。
+    pub fn from_params(id: String, role: NodeRole, params: &HyperParams) -> Result<Self, String> {
+        params.validate().map_err(|e| format!("Node [{}] refused to start: invalid HyperParams: {}", id, e))?;
+
+        let mut node = Self::new(id, role, params.depth);
+        if node.optimizer.is_some() {
+            let optimizer = SimpleOptimizer::new(params.learning_rate).with_linear_proof_mode(params.linear_proof_mode);
+            node.optimizer = Some(AsyncMutex::new(optimizer));
+        }
+        node.params = params.clone();
+        Ok(node)
+    }
+
+    /// 🚇 模型并行: 本节点持有 `total_layers` 层模型中的 [offset, offset + model_depth) 段
+    pub fn with_layer_shard(mut self, offset: usize, total_layers: usize) -> Self {
+        self.layer_offset = offset;
+        self.total_layers = total_layers;
+        self
+    }
+
+    /// 🔏 本地模型指纹 (按全局层号计算，与完整快照的 content_hash 一致)
+    pub fn fingerprint(&self) -> u64 {
+        let model_guard = self.model.load();
+        ModelSnapshot::hash_layers(
+            model_guard.iter().enumerate()
+                .map(|(idx, n)| (self.layer_offset + idx, &n.logic_gate().linear, &n.logic_gate().translation))
+        )
+    }
+
+    /// 🔏 生成本节点的指纹比对包 (定期发送给 PS / 邻居)
+    pub fn fingerprint_packet(&self) -> PacketType {
+        PacketType::FingerprintExchange {
+            node_id: self.id.clone(),
+            epoch: self.epoch(),
+            fingerprint: self.fingerprint(),
+        }
+    }
+
+    /// 💓 生成本节点的轻量心跳包 (携带当前纪元与负载)
+    pub fn heartbeat_packet(&self, load: f64) -> PacketType {
+        PacketType::Heartbeat {
+            node_id: self.id.clone(),
+            epoch: self.epoch(),
+            load,
+        }
+    }
+
+    /// 🚇 本节点持有的全局层区间
+    pub fn layer_range(&self) -> Range<usize> {
+        self.layer_offset..self.layer_offset + self.model.load().len()
+    }
+
+    /// 🔁 重连追赶: 落后超过 `max_delta_epochs` 个纪元的节点获得完整快照 (默认 16)
+    pub fn with_max_delta_epochs(mut self, max_delta_epochs: u64) -> Self {
+        self.max_delta_epochs = max_delta_epochs;
+        self
+    }
+
+    /// 🔁 生成重连追赶请求 (携带本节点最后应用的纪元)
+    pub fn sync_request_packet(&self) -> PacketType {
+        PacketType::SyncRequest {
+            node_id: self.id.clone(),
+            last_epoch: self.epoch(),
+        }
+    }
+
+    /// 🚦 开启按来源节点的梯度限流 (每秒 `rate_per_sec` 个，允许突发 `burst` 个)
+    pub fn with_gradient_rate_limit(mut self, rate_per_sec: f64, burst: f64) -> Self {
+        self.rate_limiter = Some(Mutex::new(GradientRateLimiter::new(rate_per_sec, burst)));
+        self
+    }
+
+    /// 🕰️ 当前模型纪元
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// 🔢 当前模型版本 (每次写入递增，跳过的同步不计)
+    pub fn model_version(&self) -> u64 {
+        self.model_version.load(Ordering::SeqCst)
+    }
+
+    /// ⏭️ 因快照与本地一致而跳过写入的同步次数
+    pub fn skipped_syncs(&self) -> u64 {
+        self.skipped_syncs.load(Ordering::Relaxed)
+    }
+
+    /// 📶 当前纪元至今的梯度 / 快照流量
+    pub fn traffic_report(&self) -> TrafficReport {
+        self.traffic.report()
+    }
+
+    /// ⚡ 该节点能否应用梯度 (仅持有 Optimizer 的 PS 可以)
+    pub fn can_apply_gradients(&self) -> bool {
+        self.optimizer.is_some()
+    }
+
+    /// 📨 Packet Processor (带来源): 已知发送方时的入口
+    /// 梯度推送先经过按来源的限流闸门，超速的推送回执 `RateLimited` 错误。
+    pub async fn process_packet_from(&self, source: &str, packet: PacketType) -> Option<PacketType> {
+        let is_gradient = matches!(packet, PacketType::GradientPush(_) | PacketType::MultiGradientPush(_));
+        if is_gradient && self.can_apply_gradients() && !self.admit_gradient(source) {
+            warn!(node_id = %self.id, source, "🚦 Gradient push rate exceeded. Rejecting.");
+            return Some(PacketType::Error {
+                code: ErrorCode::RateLimited,
+                message: format!("Node [{}] is rate limiting gradient pushes from [{}]. Slow down.", self.id, source),
+            });
+        }
+        self.process_packet(packet).await
+    }
+
+    /// 🚦 Helper: 未配置限流时总是放行
+    fn admit_gradient(&self, source: &str) -> bool {
+        match &self.rate_limiter {
+            Some(limiter) => limiter.lock().unwrap().try_acquire(source),
+            None => true,
         }
     }
 
     /// 📨 Packet Processor: 核心消息处理循环
     /// 模拟接收到一个网络包并处理 (实际应配合 Quinn/Tokio Stream 使用)
+    ///
+    /// 🔭 每个包都在一个 `packet` Span 内处理 (携带 node_id 与 epoch)，
+    /// 下游的 inference / gradient / sync Span 嵌套其中，便于跨节点追踪。
+    ///
+    /// 📶 收到的梯度 / 快照包与回执都计入当前纪元的流量。纪元推进后的第一个包到达时，
+    /// 上一纪元的汇总 (含推进纪元的那次更新) 写入日志并清零。
+    #[instrument(name = "packet", skip_all, fields(node_id = %self.id, epoch = self.epoch()))]
     pub async fn process_packet(&self, packet: PacketType) -> Option<PacketType> {
+        if let Some(report) = self.traffic.roll_over(self.epoch()) {
+            info!(
+                traffic_epoch = report.epoch,
+                gradient_bytes_received = report.gradient_bytes_received,
+                gradient_bytes_sent = report.gradient_bytes_sent,
+                snapshot_bytes_received = report.snapshot_bytes_received,
+                snapshot_bytes_sent = report.snapshot_bytes_sent,
+                "📶 Epoch traffic summary"
+            );
+        }
+
+        self.traffic.record(&packet, false);
+        let response = self.dispatch_packet(packet).await;
+        if let Some(reply) = &response {
+            self.traffic.record(reply, true);
+        }
+        response
+    }
+
+    /// 🔀 按包类型分发到具体的处理逻辑
+    async fn dispatch_packet(&self, packet: PacketType) -> Option<PacketType> {
         match packet {
             PacketType::Handshake { node_id, protocol_ver } => {
-                info!("🤝 Handshake received from [{}] (v{})", node_id, protocol_ver);
+                info!(peer_id = %node_id, protocol_ver, "🤝 Handshake received");
                 // 这里可以返回一个 HandshakeAck，暂时略过
                 None
             }
@@ -75,12 +404,50 @@ impl HTPNode {
                 self.handle_inference(request_id, input_state).await
             }
 
+            PacketType::FoldedInferenceRequest { request_id, tokens, mode } => {
+                if self.role != NodeRole::Worker {
+                    warn!("⚠️ PS received FoldedInferenceRequest. Ignoring.");
+                    return None;
+                }
+                self.handle_folded_inference(request_id, tokens, mode).await
+            }
+
+            PacketType::InferencePipelineRequest { request_id, input, next_layer } => {
+                if self.role != NodeRole::Worker {
+                    warn!("⚠️ PS received InferencePipelineRequest. Ignoring.");
+                    return None;
+                }
+                Some(self.handle_pipeline_inference(request_id, input, next_layer))
+            }
+
+            PacketType::FingerprintExchange { node_id, epoch, fingerprint } => {
+                self.handle_fingerprint(&node_id, epoch, fingerprint)
+            }
+
             PacketType::GradientPush(grad) => {
+                if !self.can_apply_gradients() {
+                    return Some(self.reject_gradients("GradientPush"));
+                }
+                self.handle_gradient_update(grad).await
+            }
+
+            PacketType::MultiGradientPush(batch) => {
+                if !self.can_apply_gradients() {
+                    return Some(self.reject_gradients("MultiGradientPush"));
+                }
+                self.handle_multi_gradient_update(batch).await
+            }
+
+            PacketType::TraceTransfer { request_id, part, total_parts, trace, grad_output } => {
+                self.handle_trace_transfer(request_id, part, total_parts, trace, grad_output)
+            }
+
+            PacketType::SyncRequest { node_id, last_epoch } => {
                 if self.role != NodeRole::ParameterServer {
-                    warn!("⚠️ Worker received GradientPush. Ignoring.");
+                    warn!("⚠️ Worker received SyncRequest. Ignoring.");
                     return None;
                 }
-                self.handle_gradient_update(grad).await
+                self.handle_sync_request(&node_id, last_epoch)
             }
 
             PacketType::ParameterBroadcast(snapshot) => {
@@ -90,30 +457,124 @@ impl HTPNode {
                 self.handle_parameter_sync(snapshot).await
             }
 
-            _ => None,
+            // 错误回执不再回执错误，避免两端互相 "乒乓"
+            PacketType::Error { code, message } => {
+                warn!(?code, %message, "⚠️ Peer reported an error");
+                None
+            }
+
+            // 本节点不处理的包 (包括较新版本对端的新变体)：显式回执，让版本偏差可被观测
+            other => {
+                let kind = other.kind();
+                warn!(packet_kind = kind, role = ?self.role, "⚠️ Unsupported packet type. Rejecting.");
+                Some(PacketType::Error {
+                    code: ErrorCode::UnsupportedPacket,
+                    message: format!("Node [{}] ({:?}) does not handle {} packets.", self.id, self.role, kind),
+                })
+            }
+        }
+    }
+
+    /// 🎞️ [Compute Logic]: 远端反向传播
+    /// 分片重组完成、且携带输出梯度的磁带在本地执行 backward，回执本地各层的梯度
+    /// (MultiGradientPush，纪元取本地模型纪元)，由调用方转发给 PS。
+    /// 磁带的时间线布局与 `train_step_sgd` 一致：输入叶子在前，模型各层的叶子在后，
+    /// 因此最后 `depth` 个叶子对应本地的各层。分片未到齐时不回执；不携带输出梯度的磁带只被接收。
+    #[instrument(name = "trace_transfer", skip(self, trace, grad_output), fields(node_id = %self.id))]
+    fn handle_trace_transfer(
+        &self,
+        request_id: u64,
+        part: u32,
+        total_parts: u32,
+        trace: CausalTrace,
+        grad_output: Option<AffineTuple>,
+    ) -> Option<PacketType> {
+        let accepted = self.trace_assembler.lock().unwrap()
+            .accept(request_id, part, total_parts, trace, grad_output);
+        let assembled = match accepted {
+            Ok(Some(assembled)) => assembled,
+            Ok(None) => return None,
+            Err(message) => {
+                warn!(%message, "⚠️ Rejecting TraceTransfer part");
+                return Some(PacketType::Error { code: ErrorCode::InvalidRequest, message });
+            }
+        };
+        let Some(grad_output) = assembled.grad_output else {
+            info!(nodes = assembled.trace.nodes.len(), "🎞️ Trace received without output gradient. Nothing to backpropagate.");
+            return None;
+        };
+
+        let invalid = |message: String| {
+            warn!(%message, "⚠️ Cannot run backward on transferred trace");
+            Some(PacketType::Error { code: ErrorCode::InvalidRequest, message })
+        };
+        let trace = assembled.trace;
+        let Some(root) = trace.nodes.last() else {
+            return invalid(format!("Trace for request {} is empty.", request_id));
+        };
+        let shape = |t: &AffineTuple| (t.linear.rows, t.linear.cols, t.linear.data.len(), t.translation.data.len());
+        if shape(&grad_output) != shape(&root.value) {
+            return invalid(format!("Output gradient for request {} does not match the trace root shape.", request_id));
+        }
+
+        let depth = self.model.load().len();
+        let leaves = trace.nodes.iter().take_while(|n| matches!(n.op, OpType::LeafEmbedding)).count();
+        if leaves < depth {
+            return invalid(format!(
+                "Trace for request {} has {} leaves, fewer than the {} local layers.",
+                request_id, leaves, depth
+            ));
+        }
+
+        let leaf_grads = trace.backward_parallel(&grad_output);
+        let first_layer_leaf = leaves - depth;
+        let updates = (0..depth)
+            .map(|layer| {
+                let grad = &leaf_grads[first_layer_leaf + layer];
+                GradientUpdate {
+                    layer_index: self.layer_offset + layer,
+                    weight_grad: grad.linear.data.clone(),
+                    bias_grad: grad.translation.data.clone(),
+                    batch_size: 1,
+                }
+            })
+            .collect();
+        info!(layers = depth, "🎞️ Backward finished for transferred trace");
+        Some(PacketType::MultiGradientPush(MultiLayerGradient { updates, epoch: self.epoch() }))
+    }
+
+    /// 🚫 Helper: 没有 Optimizer 的节点收到梯度时，回执显式错误而不是静默丢弃
+    fn reject_gradients(&self, packet_kind: &str) -> PacketType {
+        warn!(packet_kind, role = ?self.role, "⚠️ Gradient received by a node without optimizer. Rejecting.");
+        PacketType::Error {
+            code: ErrorCode::RoleMismatch,
+            message: format!(
+                "Node [{}] ({:?}) has no optimizer and cannot apply {}. Send gradients to a ParameterServer.",
+                self.id, self.role, packet_kind
+            ),
         }
     }
 
     /// 🧠 [Worker Logic]: 执行推理
+    #[instrument(name = "inference", skip(self, input), fields(node_id = %self.id))]
     async fn handle_inference(&self, request_id: u64, input: Vector) -> Option<PacketType> {
-        info!("🧠 Worker [{}] processing Request #{}", self.id, request_id);
+        info!("🧠 Worker processing inference request");
 
-        let model_guard = self.model.read().await;
+        // 无锁读取当前模型版本 (即使正在训练也不会阻塞)
+        let model_guard = self.model.load();
         
         // 1. 构建计算图输入
         // 这里简化处理：假设模型是单层或简单的串行结构，将输入包装为 AffineTuple
         // 实际的 Evolver 会构建复杂的 HyperTensor
-        let input_tuple = AffineTuple::new(Matrix::identity(), input);
+        let input_tuple = input.to_affine_leaf();
         
         // 2. 模拟网络前向传播 (Forward Pass)
         // 这里的逻辑是将输入通过所有神经元折叠。
         // 为了演示，我们取第一个神经元进行处理。
         let mut result_vector = Vector::zeros();
         if let Some(first_neuron) = model_guard.first() {
-             // Clone 神经元状态以避免由于借用检查器导致的冲突，
-             // 在实际高性能场景下应使用 Zero-copy。
-             let mut neuron_clone = first_neuron.clone(); 
-             result_vector = neuron_clone.absorb(&input_tuple.translation);
+             // 只读推理：直接在共享模型上计算 (不克隆神经元)，输出缓存跨请求保留
+             result_vector = first_neuron.infer(input_tuple.as_point());
         }
 
         // 3. 返回结果
@@ -123,71 +584,317 @@ impl HTPNode {
         })
     }
 
+    /// 🧬 [Worker Logic]: 服务端折叠 + 推理
+    /// 每个 Token 嵌入被包装为平移算子 (I·x + e_t)，按 mode 折叠后，Root 的平移部分即输入状态。
+    #[instrument(name = "folded_inference", skip(self, tokens), fields(node_id = %self.id, tokens = tokens.len()))]
+    async fn handle_folded_inference(&self, request_id: u64, tokens: Vec<Vector>, mode: FoldMode) -> Option<PacketType> {
+        let timeline: Vec<AffineTuple> = tokens.into_iter()
+            .map(Vector::to_affine_leaf)
+            .collect();
+
+        let root = match mode {
+            FoldMode::Time => HyperFolder::fold_timeline(&timeline),
+            FoldMode::Space => HyperFolder::fold_context(&timeline),
+        };
+        let Some(root) = root else {
+            return Some(PacketType::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("Request #{} carries no tokens to fold.", request_id),
+            });
+        };
+
+        self.handle_inference(request_id, root.as_point().clone()).await
+    }
+
+    /// 🚇 [Worker Logic]: 流水线推理的一段
+    /// 从 `next_layer` 开始依次通过本地持有的层。若已到达模型末尾，回执 InferenceResponse；
+    /// 否则返回新的 InferencePipelineRequest，由调用方转发给持有下一层的节点 (见 DiscoveryService::layer_owner)。
+    #[instrument(name = "pipeline_inference", skip(self, input), fields(node_id = %self.id))]
+    fn handle_pipeline_inference(&self, request_id: u64, input: Vector, next_layer: usize) -> PacketType {
+        let model_guard = self.model.load();
+        let range = self.layer_offset..self.layer_offset + model_guard.len();
+        if !range.contains(&next_layer) {
+            warn!(next_layer, ?range, "⚠️ Pipeline request routed to the wrong shard");
+            return PacketType::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!("Node [{}] holds layers {:?}, not layer {}.", self.id, range, next_layer),
+            };
+        }
+
+        let mut state = input;
+        for neuron in &model_guard[next_layer - self.layer_offset..] {
+            state = neuron.infer(&state);
+        }
+
+        if range.end >= self.total_layers {
+            PacketType::InferenceResponse { request_id, output_state: state }
+        } else {
+            PacketType::InferencePipelineRequest { request_id, input: state, next_layer: range.end }
+        }
+    }
+
+    /// 🔏 [Consensus]: 指纹比对
+    /// 一致时无需回执。不一致时：PS 回执完整快照 (触发对方重新同步)；
+    /// Worker 只记录警告、不回执 (回执指纹会让两个分叉的 Worker 互相来回 Ping-Pong)，
+    /// 由自己下一轮发给 PS 的指纹触发重新同步。
+    #[instrument(name = "fingerprint", skip(self), fields(node_id = %self.id))]
+    fn handle_fingerprint(&self, peer_id: &str, peer_epoch: u64, peer_fingerprint: u64) -> Option<PacketType> {
+        let local = self.fingerprint();
+        if local == peer_fingerprint {
+            return None;
+        }
+
+        warn!(peer_id, peer_epoch, local_epoch = self.epoch(), "🔏 Model fingerprint mismatch. Divergence detected.");
+        match self.role {
+            NodeRole::ParameterServer => Some(self.create_snapshot(&self.model.load())),
+            NodeRole::Worker => None,
+        }
+    }
+
     /// 📉 [PS Logic]: 梯度下降更新
+    #[instrument(name = "gradient", skip_all, fields(node_id = %self.id, layer = grad.layer_index, epoch = self.epoch()))]
     async fn handle_gradient_update(&self, grad: GradientUpdate) -> Option<PacketType> {
-        info!("📉 PS [{}] applying gradients to Layer {}", self.id, grad.layer_index);
+        info!("📉 PS applying gradients");
 
         if let Some(opt) = &self.optimizer {
-            let mut model_guard = self.model.write().await;
-            
-            if let Some(target_neuron) = model_guard.get_mut(grad.layer_index) {
-                // 1. 重构梯度矩阵
-                // GradientUpdate 传输的是扁平化的 Vec<Float>，需要还原为 Matrix
-                let weight_grad_mat = Matrix::new(
-                    target_neuron.logic_gate.linear.rows,
-                    target_neuron.logic_gate.linear.cols,
-                    grad.weight_grad
-                );
-
-                // 2. 执行优化器步骤 (W = W - lr * grad)
-                opt.apply_gradient(&mut target_neuron.logic_gate.linear, &weight_grad_mat);
-                
-                // 3. 更新 Bias (简单相减)
-                // 实际 SimpleOptimizer 也应该支持 Bias，这里手动演示
-                let bias_grad_vec = Vector::new(grad.bias_grad);
-                let lr = 1e-3; // 暂时硬编码，应从 params 读取
-                target_neuron.logic_gate.translation = target_neuron.logic_gate.translation
-                    .sub(&bias_grad_vec.scale(lr));
+            // 锁顺序与多层路径一致: 先 Optimizer，后 Writer
+            let mut opt = opt.lock().await;
+            let _writer = self.model_writer.lock().await;
+
+            // 在副本上更新，完成后原子替换 (推理期间读到的始终是完整版本)
+            let mut next_model = Vec::clone(&self.model.load());
+            if let Err(message) = Self::validate_gradients(&next_model, std::slice::from_ref(&grad)) {
+                warn!(%message, "⚠️ Malformed GradientUpdate. Rejecting.");
+                return Some(PacketType::Error { code: ErrorCode::InvalidRequest, message });
+            }
+            if let Some(target_neuron) = next_model.get_mut(grad.layer_index) {
+                let layer_index = grad.layer_index;
+                Self::apply_layer_gradient(&mut opt, target_neuron, grad);
+                self.stamp_layers(&[layer_index]);
+                let snapshot = self.create_snapshot(&next_model);
+                self.publish_model(next_model);
 
                 info!("✅ Weights updated via Gradient Descent.");
                 
-                // 4. (可选) 触发广播：如果更新累计到一定程度，广播新参数
+                // (可选) 触发广播：如果更新累计到一定程度，广播新参数
                 // 这里为了演示，每次更新都广播（效率极低，仅作逻辑展示）
-                return Some(self.create_snapshot(&model_guard));
+                return Some(snapshot);
             }
         }
         None
     }
 
+    /// 📦 [PS Logic]: 多层梯度原子更新
+    /// 整个包在一次写锁内应用：要么全部层生效，要么全部拒绝。
+    ///
+    /// 纪元由 PS 自己推进：包的 `epoch` 只能是当前纪元 (同一纪元内的追加写入) 或下一个纪元
+    /// (应用后 PS 前进一步)。更旧的包已过期，更新的包来自 "未来"，两者都被拒绝，
+    /// 单个发送方无法把 PS 的纪元一次拨到任意值。
+    #[instrument(name = "gradient_batch", skip_all, fields(node_id = %self.id, epoch = batch.epoch, layers = batch.updates.len()))]
+    async fn handle_multi_gradient_update(&self, batch: MultiLayerGradient) -> Option<PacketType> {
+        info!("📦 PS applying multi-layer gradients");
+
+        let mut opt = self.optimizer.as_ref()?.lock().await;
+        let _writer = self.model_writer.lock().await;
+
+        // 0. 纪元检查在锁内进行，避免并发的包同时通过检查后各自推进纪元
+        let current_epoch = self.epoch();
+        if batch.epoch < current_epoch || batch.epoch > current_epoch + 1 {
+            warn!(current_epoch, "⚠️ MultiLayerGradient epoch out of range. Rejecting.");
+            return Some(PacketType::Error {
+                code: ErrorCode::InvalidRequest,
+                message: format!(
+                    "Batch epoch {} is outside [{}, {}] on PS [{}].",
+                    batch.epoch, current_epoch, current_epoch + 1, self.id
+                ),
+            });
+        }
+
+        // 1. 先整体校验 (层号、唯一性、梯度形状)，任何问题都拒绝整个包 (Atomicity)，
+        //    此时 Optimizer 的状态尚未被触碰
+        let mut next_model = Vec::clone(&self.model.load());
+        if let Err(message) = Self::validate_gradients(&next_model, &batch.updates) {
+            warn!(%message, "⚠️ Malformed MultiLayerGradient. Rejecting whole batch.");
+            return Some(PacketType::Error { code: ErrorCode::InvalidRequest, message });
+        }
+
+        // 2. 一次性应用所有层
+        let touched: Vec<usize> = batch.updates.iter().map(|g| g.layer_index).collect();
+        for grad in batch.updates {
+            let layer_index = grad.layer_index;
+            Self::apply_layer_gradient(&mut opt, &mut next_model[layer_index], grad);
+        }
+        self.epoch.store(batch.epoch, Ordering::SeqCst);
+        self.stamp_layers(&touched);
+
+        // 所有层在副本上完成后一次性替换：读者只会看到全旧或全新的模型
+        let snapshot = self.create_snapshot(&next_model);
+        self.publish_model(next_model);
+
+        info!("✅ All layers updated atomically.");
+        Some(snapshot)
+    }
+
+    /// 🔍 Helper: 校验一组梯度能否安全地应用到模型
+    /// 层号必须在本地范围内且互不重复，∇W / ∇b 的长度必须与该层的 W / b 一致
+    /// (否则重构矩阵时会 panic，或者同一层被 Optimizer 重复更新)。
+    fn validate_gradients(model: &[HTPNeuron], updates: &[GradientUpdate]) -> Result<(), String> {
+        let mut seen = vec![false; model.len()];
+        for grad in updates {
+            let Some(neuron) = model.get(grad.layer_index) else {
+                return Err(format!("Gradient targets unknown layer {} (model depth {}).", grad.layer_index, model.len()));
+            };
+            if std::mem::replace(&mut seen[grad.layer_index], true) {
+                return Err(format!("Layer {} appears more than once in the batch.", grad.layer_index));
+            }
+            let linear = &neuron.logic_gate().linear;
+            if grad.weight_grad.len() != linear.rows * linear.cols {
+                return Err(format!(
+                    "Layer {} weight gradient has {} entries, expected {}x{}.",
+                    grad.layer_index, grad.weight_grad.len(), linear.rows, linear.cols
+                ));
+            }
+            if grad.bias_grad.len() != neuron.logic_gate().translation.data.len() {
+                return Err(format!(
+                    "Layer {} bias gradient has {} entries, expected {}.",
+                    grad.layer_index, grad.bias_grad.len(), neuron.logic_gate().translation.data.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 🔧 Helper: 将单层梯度应用到神经元 (W 与 b)
+    fn apply_layer_gradient(opt: &mut SimpleOptimizer, neuron: &mut HTPNeuron, grad: GradientUpdate) {
+        // 1. 重构梯度矩阵
+        // GradientUpdate 传输的是扁平化的 Vec<Float>，需要还原为 Matrix
+        let weight_grad_mat = Matrix::new(
+            neuron.logic_gate().linear.rows,
+            neuron.logic_gate().linear.cols,
+            grad.weight_grad
+        );
+
+        // 2. 执行优化器步骤 (W/b = W/b - lr * lr_scale * grad)
+        let bias_grad_vec = Vector::new(grad.bias_grad);
+        opt.step_neuron(grad.layer_index, neuron, &weight_grad_mat, &bias_grad_vec);
+    }
+
+    /// 🏷️ Helper: 记录这些层 (本地下标) 在当前纪元被写入
+    fn stamp_layers(&self, layers: &[usize]) {
+        let epoch = self.epoch();
+        let mut stamps = self.layer_epochs.lock().unwrap();
+        for &idx in layers {
+            if let Some(stamp) = stamps.get_mut(idx) {
+                *stamp = epoch;
+            }
+        }
+    }
+
+    /// 🔁 [PS Logic]: 重连追赶
+    /// 对方最后应用的纪元为 `last_epoch`：
+    /// * 落后不超过 `max_delta_epochs`：只回执自 `last_epoch` 起 (含) 被写入过的层 (增量快照)。
+    ///   同一纪元内可能有对方错过的写入，因此边界取闭区间；没有任何层变化时无需回执。
+    /// * 落后太多，或 `last_epoch` 比本地还新 (PS 重启 / 回滚)：回执完整快照。
+    ///
+    /// 增量快照仍是 ParameterBroadcast，Worker 的 `handle_parameter_sync` 只覆盖其中列出的层。
+    #[instrument(name = "sync_request", skip(self), fields(node_id = %self.id, epoch = self.epoch()))]
+    fn handle_sync_request(&self, peer_id: &str, last_epoch: u64) -> Option<PacketType> {
+        let model_guard = self.model.load();
+        let current = self.epoch();
+        if last_epoch > current || current - last_epoch > self.max_delta_epochs {
+            info!(peer_id, last_epoch, "🔁 Peer too far behind. Sending full snapshot");
+            return Some(self.create_snapshot(&model_guard));
+        }
+
+        let stamps = self.layer_epochs.lock().unwrap();
+        let layers: Vec<LayerState> = model_guard.iter().enumerate()
+            .filter(|(idx, _)| stamps.get(*idx).is_some_and(|&stamp| stamp >= last_epoch))
+            .map(|(idx, n)| LayerState {
+                layer_index: self.layer_offset + idx,
+                weights: n.logic_gate().linear.clone(),
+                bias: n.logic_gate().translation.clone(),
+            })
+            .collect();
+        if layers.is_empty() {
+            return None;
+        }
+        info!(peer_id, last_epoch, layers = layers.len(), "🔁 Sending delta snapshot");
+        Some(PacketType::ParameterBroadcast(ModelSnapshot::new(current, layers)))
+    }
+
     /// 🧬 [Worker Logic]: 同步全局参数
+    /// 应用后本地纪元推进到快照的纪元 (重连时据此请求增量)。
+    /// 证明模式下，含有不可逆逻辑门的快照被整体拒绝，本地模型与纪元保持不变。
+    #[instrument(name = "sync", skip_all, fields(node_id = %self.id, epoch = snapshot.epoch))]
     async fn handle_parameter_sync(&self, snapshot: ModelSnapshot) -> Option<PacketType> {
-        info!("🧬 Worker [{}] syncing with Global Truth (Epoch {})", self.id, snapshot.epoch);
-        
-        let mut model_guard = self.model.write().await;
+        info!("🧬 Worker syncing with Global Truth");
+
+        let _writer = self.model_writer.lock().await;
+
+        if self.params.linear_proof_mode {
+            let rejected = snapshot.layers.iter().find_map(|l| {
+                self.params.admit_gate(&AffineTuple::new(l.weights.clone(), l.bias.clone()))
+                    .err()
+                    .map(|e| (l.layer_index, e))
+            });
+            if let Some((layer, e)) = rejected {
+                warn!(layer, error = %e, "📜 Snapshot rejected in linear proof mode");
+                return Some(PacketType::Error {
+                    code: ErrorCode::InvalidRequest,
+                    message: format!("Node [{}] rejects snapshot epoch {}: layer {}: {}", self.id, snapshot.epoch, layer, e),
+                });
+            }
+        }
+        self.epoch.fetch_max(snapshot.epoch, Ordering::SeqCst);
+
+        // 0. 幂等检查: 快照覆盖的层与本地完全一致时，无需复制与替换模型
+        {
+            let model_guard = self.model.load();
+            let local_hash = ModelSnapshot::hash_layers(
+                snapshot.layers.iter()
+                    .filter_map(|l| {
+                        let local = l.layer_index.checked_sub(self.layer_offset)?;
+                        model_guard.get(local).map(|n| (l.layer_index, &n.logic_gate().linear, &n.logic_gate().translation))
+                    })
+            );
+            if local_hash == snapshot.content_hash {
+                self.skipped_syncs.fetch_add(1, Ordering::Relaxed);
+                info!("⏭️ Snapshot identical to local model. Skipping write.");
+                return None;
+            }
+        }
+
+        let mut next_model = Vec::clone(&self.model.load());
         
         for layer_state in snapshot.layers {
-            if layer_state.layer_index < model_guard.len() {
+            // 全局层号 -> 本地下标 (只覆盖本分片持有的层)
+            let Some(local) = layer_state.layer_index.checked_sub(self.layer_offset) else { continue };
+            if let Some(neuron) = next_model.get_mut(local) {
                 // 覆盖本地权重
-                model_guard[layer_state.layer_index].logic_gate.linear = layer_state.weights;
-                model_guard[layer_state.layer_index].logic_gate.bias = layer_state.bias; // 修正: LayerState 定义里是 bias
+                neuron.set_logic_gate(AffineTuple::new(layer_state.weights, layer_state.bias));
             }
         }
+        self.publish_model(next_model);
         None
     }
 
+    /// 🔁 Helper: 原子替换模型并递增模型版本 (调用方须持有 Writer Lock)
+    /// 被覆盖的神经元必须已调用 `invalidate_cache`，否则其输出缓存会继续服务旧权重。
+    fn publish_model(&self, next_model: Vec<HTPNeuron>) {
+        self.model.store(Arc::new(next_model));
+        self.model_version.fetch_add(1, Ordering::SeqCst);
+    }
+
     /// 📸 Helper: 创建模型快照
     fn create_snapshot(&self, neurons: &[HTPNeuron]) -> PacketType {
         let layers = neurons.iter().enumerate().map(|(idx, n)| {
             LayerState {
-                layer_index: idx,
-                weights: n.logic_gate.linear.clone(),
-                bias: n.logic_gate.translation.clone(),
+                layer_index: self.layer_offset + idx,
+                weights: n.logic_gate().linear.clone(),
+                bias: n.logic_gate().translation.clone(),
             }
         }).collect();
 
-        PacketType::ParameterBroadcast(ModelSnapshot {
-            epoch: 0, // 实际应维护全局 Epoch 计数器
-            layers,
-        })
+        PacketType::ParameterBroadcast(ModelSnapshot::new(self.epoch(), layers))
     }
 }
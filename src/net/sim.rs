@@ -0,0 +1,169 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::core::algebra::Float;
+use crate::core::neuron::HTPNeuron;
+use crate::net::node::{HTPNode, NodeRole};
+use crate::net::sync::{GradientAggregator, MultiAggregationResult};
+use crate::net::wire::{GradientUpdate, MultiLayerGradient, PacketType};
+use crate::train_loop::SimpleOptimizer;
+
+/// 📬 Link: 单向内存链路 (发送方 ID, 编码后的包)
+type Link = (UnboundedSender<(String, Vec<u8>)>, UnboundedReceiver<(String, Vec<u8>)>);
+
+/// 📋 RoundReport: 一轮仿真训练的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundReport {
+    /// 本轮使用的 Epoch
+    pub epoch: u64,
+    /// 贡献了梯度的 Worker 数
+    pub contributors: usize,
+    /// 收到并应用了参数广播的 Worker 数
+    pub synced_workers: usize,
+}
+
+/// 🧪 SimCluster: 单进程多节点仿真集群
+///
+/// 用内存通道 (tokio mpsc) 代替 QUIC，把若干 Worker、一个 PS 与 `GradientAggregator` 连成
+/// 完整的 Worker -> Aggregator -> PS -> Broadcast 回路。每个包与真实链路一样经过 bincode 编解码，
+/// 但投递顺序完全确定 (按 Worker 编号)，适合在单元测试中驱动分布式训练逻辑。
+///
+/// PS 本身不计算梯度：它以 batch_size = 0 的空贡献占据聚合器的 "SELF" 位置，不影响加权平均。
+pub struct SimCluster {
+    pub ps: HTPNode,
+    pub workers: Vec<HTPNode>,
+    aggregator: GradientAggregator,
+    /// Worker -> PS 的共享上行链路
+    uplink: Link,
+    /// PS -> 各 Worker 的下行链路
+    downlinks: Vec<Link>,
+    epoch: u64,
+}
+
+impl SimCluster {
+    /// 构造 `num_workers` 个 Worker + 1 个 PS，所有节点的模型深度为 `model_depth`
+    pub fn new(num_workers: usize, model_depth: usize, learning_rate: Float) -> Self {
+        let mut ps = HTPNode::new("sim-ps".to_string(), NodeRole::ParameterServer, model_depth);
+        ps.optimizer = Some(AsyncMutex::new(SimpleOptimizer::new(learning_rate)));
+
+        let workers = (0..num_workers)
+            .map(|i| HTPNode::new(format!("sim-worker-{:02}", i), NodeRole::Worker, model_depth))
+            .collect();
+
+        SimCluster {
+            ps,
+            workers,
+            aggregator: GradientAggregator::new(),
+            uplink: unbounded_channel(),
+            downlinks: (0..num_workers).map(|_| unbounded_channel()).collect(),
+            epoch: 0,
+        }
+    }
+
+    /// 🆔 所有 Worker 的 ID (即聚合器期望的子节点)
+    pub fn worker_ids(&self) -> Vec<String> {
+        self.workers.iter().map(|w| w.id.clone()).collect()
+    }
+
+    /// 🕰️ 已完成的轮数
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// 🔏 所有 Worker 的模型是否与 PS 完全一致
+    pub fn in_sync(&self) -> bool {
+        let truth = self.ps.fingerprint();
+        self.workers.iter().all(|w| w.fingerprint() == truth)
+    }
+
+    /// 🔄 执行一轮同步训练
+    ///
+    /// 1. 每个 Worker 基于本地模型调用 `local_gradient(worker_idx, model)` 计算各层梯度，推送给 PS；
+    /// 2. 聚合器收齐所有 Worker 后输出平均梯度，PS 一次性应用；
+    /// 3. PS 的参数广播经下行链路投递给所有 Worker 并同步。
+    ///
+    /// 聚合未能收齐或 PS 回执错误时返回 Err。
+    pub async fn run_round<F>(&mut self, mut local_gradient: F) -> Result<RoundReport, String>
+    where
+        F: FnMut(usize, &[HTPNeuron]) -> Vec<GradientUpdate>,
+    {
+        self.epoch += 1;
+        let epoch = self.epoch;
+
+        // 1. Workers -> PS
+        for (idx, worker) in self.workers.iter().enumerate() {
+            let updates = local_gradient(idx, &worker.model.load());
+            let packet = PacketType::MultiGradientPush(MultiLayerGradient { updates, epoch });
+            Self::send(&self.uplink.0, &worker.id, &packet)?;
+        }
+
+        // 2. Aggregator (PS 侧): 先吸收所有 Worker，再补上 PS 自身的空贡献
+        let children = self.worker_ids();
+        let mut contributors = 0;
+        let mut layers: Vec<usize> = Vec::new();
+        let mut aggregated = None;
+        while let Ok((from, bytes)) = self.uplink.1.try_recv() {
+            let PacketType::MultiGradientPush(batch) = PacketType::from_bytes(&bytes)? else {
+                return Err(format!("Round {}: unexpected uplink packet from [{}]", epoch, from));
+            };
+            layers.extend(batch.updates.iter().map(|g| g.layer_index));
+            contributors += 1;
+            aggregated = Self::collect(self.aggregator.aggregate_multi(batch, from, &children), aggregated);
+        }
+        layers.sort_unstable();
+        layers.dedup();
+        let own = MultiLayerGradient {
+            updates: layers.into_iter()
+                .map(|layer_index| GradientUpdate { layer_index, weight_grad: Vec::new(), bias_grad: Vec::new(), batch_size: 0 })
+                .collect(),
+            epoch,
+        };
+        aggregated = Self::collect(self.aggregator.aggregate_multi(own, "SELF".to_string(), &children), aggregated);
+        let Some(batch) = aggregated else {
+            return Err(format!(
+                "Round {}: aggregation incomplete, missing {:?}",
+                epoch, self.aggregator.pending_report()
+            ));
+        };
+
+        // 3. PS 应用平均梯度，并向所有 Worker 广播
+        match self.ps.process_packet(PacketType::MultiGradientPush(batch)).await {
+            Some(broadcast @ PacketType::ParameterBroadcast(_)) => {
+                for (tx, _) in &self.downlinks {
+                    Self::send(tx, &self.ps.id, &broadcast)?;
+                }
+            }
+            Some(PacketType::Error { code, message }) => {
+                return Err(format!("Round {}: PS rejected gradients ({:?}): {}", epoch, code, message));
+            }
+            other => return Err(format!("Round {}: PS produced no broadcast ({:?})", epoch, other)),
+        }
+
+        // 4. Workers 同步
+        let mut synced_workers = 0;
+        for (worker, (_, rx)) in self.workers.iter().zip(self.downlinks.iter_mut()) {
+            while let Ok((_, bytes)) = rx.try_recv() {
+                worker.process_packet(PacketType::from_bytes(&bytes)?).await;
+                synced_workers += 1;
+            }
+        }
+
+        Ok(RoundReport { epoch, contributors, synced_workers })
+    }
+
+    /// 📤 Helper: 编码后投递到内存链路
+    fn send(tx: &UnboundedSender<(String, Vec<u8>)>, from: &str, packet: &PacketType) -> Result<(), String> {
+        tx.send((from.to_string(), packet.to_bytes()?))
+            .map_err(|_| format!("Link from [{}] is closed", from))
+    }
+
+    /// 📥 Helper: 保留第一个完整的聚合结果
+    fn collect(result: MultiAggregationResult, acc: Option<MultiLayerGradient>) -> Option<MultiLayerGradient> {
+        match result {
+            MultiAggregationResult::Complete(batch) => acc.or(Some(batch)),
+            MultiAggregationResult::Pending | MultiAggregationResult::PartialSum(_) | MultiAggregationResult::Stale => acc,
+        }
+    }
+}
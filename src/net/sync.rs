@@ -1,8 +1,9 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use crate::core::algebra::{Matrix, Vector, Float};
-use crate::net::wire::GradientUpdate;
+use crate::net::wire::{GradientUpdate, MultiLayerGradient, PartialGradientSum};
 
 /// 📊 AggregationResult: 聚合器的输出
 pub enum AggregationResult {
@@ -10,30 +11,124 @@ pub enum AggregationResult {
     Pending,
     /// ✅ 已收齐，输出聚合后的梯度（准备发给父节点或应用到模型）
     Complete(GradientUpdate),
+    /// ➕ 已收齐 (中间节点，见 `with_carry_up`)：输出未归一化的部分和，由父节点继续聚合
+    PartialSum(PartialGradientSum),
     /// ⚠️ 这是一个过期的梯度（Epoch 落后），已丢弃
     Stale,
 }
 
+/// 📦 MultiAggregationResult: 多层梯度包的聚合输出
+pub enum MultiAggregationResult {
+    /// ⏳ 至少有一层尚未收齐
+    Pending,
+    /// ✅ 包内所有层均已收齐，整体输出
+    Complete(MultiLayerGradient),
+    /// ➕ 包内所有层均已收齐 (中间节点，见 `with_carry_up`)：逐层输出同一纪元的未归一化部分和，
+    /// 由父节点经 `aggregate_multi_partial` 继续原子聚合
+    PartialSum(Vec<PartialGradientSum>),
+    /// ⚠️ 过期的 Epoch，整包丢弃
+    Stale,
+}
+
+/// 🧮 AccumulationPrecision: 聚合器内部累加的精度
+///
+/// 加权和 Σ g·n 随贡献者数量与 batch_size 增长，f32 只有 24 位尾数：
+/// 当累加值远大于单次贡献时 (例如上千个 Worker、每个 batch 上千样本)，每次加法的舍入误差会持续累积，
+/// 平均梯度出现系统性漂移。`F64` 在吸收时提升为 f64 累加、在输出时才降回 f32，
+/// 代价是缓冲内存翻倍；小集群 (几十个贡献者以内) 用默认的 `F32` 即可。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccumulationPrecision {
+    #[default]
+    F32,
+    F64,
+}
+
+/// 🪣 SumBuffer: 按精度存放的加权和缓冲
+enum SumBuffer {
+    F32(Vec<Float>),
+    F64(Vec<f64>),
+}
+
+impl SumBuffer {
+    fn new(precision: AccumulationPrecision) -> Self {
+        match precision {
+            AccumulationPrecision::F32 => SumBuffer::F32(Vec::new()),
+            AccumulationPrecision::F64 => SumBuffer::F64(Vec::new()),
+        }
+    }
+
+    /// 初始化 (首个贡献) 或累加: += g * n；长度以首个贡献为准
+    fn absorb(&mut self, grads: &[Float], n: usize) {
+        match self {
+            SumBuffer::F32(sum) => {
+                let n = n as Float;
+                if sum.is_empty() {
+                    *sum = grads.iter().map(|&g| g * n).collect();
+                } else {
+                    for (s, &g) in sum.iter_mut().zip(grads) {
+                        *s += g * n;
+                    }
+                }
+            }
+            SumBuffer::F64(sum) => {
+                let n = n as f64;
+                if sum.is_empty() {
+                    *sum = grads.iter().map(|&g| g as f64 * n).collect();
+                } else {
+                    for (s, &g) in sum.iter_mut().zip(grads) {
+                        *s += g as f64 * n;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 原样输出加权和 (不归一化)，降回 f32
+    fn raw(&self) -> Vec<Float> {
+        match self {
+            SumBuffer::F32(sum) => sum.clone(),
+            SumBuffer::F64(sum) => sum.iter().map(|&x| x as Float).collect(),
+        }
+    }
+
+    /// 除以总样本数并降回 f32
+    fn finalize(&self, total_batch: usize) -> Vec<Float> {
+        match self {
+            SumBuffer::F32(sum) => {
+                let scale = if total_batch > 0 { 1.0 / (total_batch as Float) } else { 1.0 };
+                sum.iter().map(|&x| x * scale).collect()
+            }
+            SumBuffer::F64(sum) => {
+                let scale = if total_batch > 0 { 1.0 / (total_batch as f64) } else { 1.0 };
+                sum.iter().map(|&x| (x * scale) as Float).collect()
+            }
+        }
+    }
+}
+
 /// 🧠 LayerAccumulator: 单层的累加器
 /// 负责处理 (g1*n1 + g2*n2) / (n1+n2) 的加权逻辑
 struct LayerAccumulator {
     /// 累积的权重梯度和 (Σ g_w * n)
-    weighted_sum_w: Vec<Float>,
+    weighted_sum_w: SumBuffer,
     /// 累积的偏置梯度和 (Σ g_b * n)
-    weighted_sum_b: Vec<Float>,
+    weighted_sum_b: SumBuffer,
     /// 总样本数 (Σ n)
     total_batch: usize,
     /// 已贡献的节点 ID 集合 (防重复提交)
     contributors: HashSet<String>,
+    /// 最近一次聚合时要求的贡献者集合 (子节点 + "SELF")，用于诊断
+    expected: HashSet<String>,
 }
 
 impl LayerAccumulator {
-    fn new() -> Self {
+    fn new(precision: AccumulationPrecision) -> Self {
         LayerAccumulator {
-            weighted_sum_w: Vec::new(),
-            weighted_sum_b: Vec::new(),
+            weighted_sum_w: SumBuffer::new(precision),
+            weighted_sum_b: SumBuffer::new(precision),
             total_batch: 0,
             contributors: HashSet::new(),
+            expected: HashSet::new(),
         }
     }
 
@@ -43,52 +138,53 @@ impl LayerAccumulator {
             return; // 幂等性保护：忽略重复提交
         }
 
-        let n = grad.batch_size as Float;
-
-        // 1. 初始化或累加 Weight 梯度
-        if self.weighted_sum_w.is_empty() {
-            // Init: g * n
-            self.weighted_sum_w = grad.weight_grad.iter().map(|&g| g * n).collect();
-        } else {
-            // Accumulate: += g * n
-            for (i, &g) in grad.weight_grad.iter().enumerate() {
-                if i < self.weighted_sum_w.len() {
-                    self.weighted_sum_w[i] += g * n;
-                }
-            }
-        }
+        // 1. 初始化或累加 Weight 梯度 (Init: g * n, Accumulate: += g * n)
+        self.weighted_sum_w.absorb(&grad.weight_grad, grad.batch_size);
 
         // 2. 初始化或累加 Bias 梯度
-        if self.weighted_sum_b.is_empty() {
-            self.weighted_sum_b = grad.bias_grad.iter().map(|&g| g * n).collect();
-        } else {
-            for (i, &g) in grad.bias_grad.iter().enumerate() {
-                if i < self.weighted_sum_b.len() {
-                    self.weighted_sum_b[i] += g * n;
-                }
-            }
-        }
+        self.weighted_sum_b.absorb(&grad.bias_grad, grad.batch_size);
 
         self.total_batch += grad.batch_size;
         self.contributors.insert(from_node.to_string());
     }
 
+    /// ➕ 吸收子树上报的部分和 (已是 Σ g·n，不再乘以样本数)
+    fn absorb_sum(&mut self, sum: &PartialGradientSum, from_node: &str) {
+        if self.contributors.contains(from_node) {
+            return;
+        }
+        self.weighted_sum_w.absorb(&sum.weight_sum, 1);
+        self.weighted_sum_b.absorb(&sum.bias_sum, 1);
+        self.total_batch += sum.total_batch;
+        self.contributors.insert(from_node.to_string());
+    }
+
+    /// ✅ 所有期望的贡献者均已到齐
+    fn is_complete(&self) -> bool {
+        self.contributors.is_superset(&self.expected)
+    }
+
     /// ➗ 归一化并输出最终梯度
     /// New_Avg = Sum(Weighted_Grads) / Total_Batch
     fn finalize(&self, layer_idx: usize) -> GradientUpdate {
-        let scale = if self.total_batch > 0 {
-            1.0 / (self.total_batch as Float)
-        } else {
-            1.0
-        };
-
         GradientUpdate {
             layer_index: layer_idx,
-            weight_grad: self.weighted_sum_w.iter().map(|&x| x * scale).collect(),
-            bias_grad: self.weighted_sum_b.iter().map(|&x| x * scale).collect(),
+            weight_grad: self.weighted_sum_w.finalize(self.total_batch),
+            bias_grad: self.weighted_sum_b.finalize(self.total_batch),
             batch_size: self.total_batch,
         }
     }
+
+    /// ➕ 输出未归一化的部分和 (Carry-Up)，除法留给 Root
+    fn finalize_partial(&self, layer_idx: usize, epoch: u64) -> PartialGradientSum {
+        PartialGradientSum {
+            layer_index: layer_idx,
+            weight_sum: self.weighted_sum_w.raw(),
+            bias_sum: self.weighted_sum_b.raw(),
+            total_batch: self.total_batch,
+            epoch,
+        }
+    }
 }
 
 /// 🌊 GradientAggregator: 梯度同步聚合器
@@ -99,6 +195,12 @@ pub struct GradientAggregator {
     
     /// 缓冲区: LayerIndex -> Accumulator
     buffers: HashMap<usize, LayerAccumulator>,
+
+    /// 🧮 累加精度 (默认 f32)
+    precision: AccumulationPrecision,
+
+    /// 🌳 中间节点模式：收齐后输出部分和 (PartialSum) 而不是平均值 (Complete)
+    carry_up: bool,
 }
 
 impl GradientAggregator {
@@ -106,9 +208,26 @@ impl GradientAggregator {
         GradientAggregator {
             current_epoch: 0,
             buffers: HashMap::new(),
+            precision: AccumulationPrecision::default(),
+            carry_up: false,
         }
     }
 
+    /// 🧮 设置内部累加精度 (大规模集群建议 F64，见 `AccumulationPrecision`)
+    /// 只影响此后新建的层缓冲。
+    pub fn with_precision(mut self, precision: AccumulationPrecision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// 🌳 作为树形聚合的中间节点：收齐后输出部分和 (子树的加权和 + 总样本数) 交给父节点，
+    /// 只有 Root (默认模式) 执行除法。单层聚合输出 `AggregationResult::PartialSum`，
+    /// 多层聚合输出 `MultiAggregationResult::PartialSum`。
+    pub fn with_carry_up(mut self) -> Self {
+        self.carry_up = true;
+        self
+    }
+
     /// 🔄 设置新纪元 (清空旧缓冲)
     pub fn advance_epoch(&mut self, new_epoch: u64) {
         if new_epoch > self.current_epoch {
@@ -117,6 +236,17 @@ impl GradientAggregator {
         }
     }
 
+    /// 🧹 强制清空所有层的缓冲 (不论 Epoch)
+    /// 用于聚合卡死或检测到模型分叉后的人工恢复；Epoch 计数保持不变。
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+
+    /// 🧹 丢弃单层的缓冲 (含已贡献者记录)，返回该层此前是否有缓冲
+    pub fn reset_layer(&mut self, layer_idx: usize) -> bool {
+        self.buffers.remove(&layer_idx).is_some()
+    }
+
     /// 📥 处理梯度更新
     ///
     /// * `grad`: 收到的梯度包
@@ -134,9 +264,10 @@ impl GradientAggregator {
         let layer_idx = grad.layer_index;
         
         // 1. 获取或创建累加器
+        let precision = self.precision;
         let acc = self.buffers
             .entry(layer_idx)
-            .or_insert_with(LayerAccumulator::new);
+            .or_insert_with(|| LayerAccumulator::new(precision));
 
         // 2. 吸收梯度
         acc.absorb(&grad, &from_node);
@@ -144,19 +275,198 @@ impl GradientAggregator {
         // 3. 检查完整性 (Completeness Check)
         // 我们需要等待：所有子节点 + 我自己 ("SELF")
         // expected_count = children.len() + 1
+        acc.expected = Self::expected_contributors(expected_children);
+
+        self.take_if_complete(layer_idx)
+    }
+
+    /// 📥 处理子树上报的部分和 (来自 `with_carry_up` 的中间节点)
+    ///
+    /// 与 `aggregate` 共用同一层缓冲与完整性检查，子节点可以混合上报普通梯度与部分和。
+    /// 纪元落后的部分和返回 `Stale` 并丢弃，更新的纪元推进本地纪元 (清空旧缓冲)。
+    pub fn aggregate_partial_sum(
+        &mut self,
+        sum: PartialGradientSum,
+        from_node: String,
+        expected_children: &[String]
+    ) -> AggregationResult {
+        if sum.epoch < self.current_epoch {
+            return AggregationResult::Stale;
+        }
+        self.advance_epoch(sum.epoch);
+
+        let layer_idx = sum.layer_index;
+        let precision = self.precision;
+        let acc = self.buffers
+            .entry(layer_idx)
+            .or_insert_with(|| LayerAccumulator::new(precision));
+        acc.absorb_sum(&sum, &from_node);
+        acc.expected = Self::expected_contributors(expected_children);
+
+        self.take_if_complete(layer_idx)
+    }
+
+    /// ✅ Helper: 该层收齐时取出结果并清理缓冲 (Root 输出平均值，中间节点输出部分和)
+    fn take_if_complete(&mut self, layer_idx: usize) -> AggregationResult {
+        // ✅ 召唤神龙：所有碎片已集齐
+        let result = match self.buffers.get(&layer_idx) {
+            Some(acc) if acc.is_complete() && self.carry_up => {
+                AggregationResult::PartialSum(acc.finalize_partial(layer_idx, self.current_epoch))
+            }
+            Some(acc) if acc.is_complete() => AggregationResult::Complete(acc.finalize(layer_idx)),
+            _ => return AggregationResult::Pending,
+        };
+
+        // 清理缓冲区 (该层本轮已完成)
+        self.buffers.remove(&layer_idx);
+        result
+    }
+
+    /// 📦 处理多层梯度包 (按 Epoch 原子聚合)
+    ///
+    /// 包内所有层一起吸收；只有当这些层全部收齐时才整体输出，
+    /// 避免部分层先行更新导致模型处于 "半新半旧" 的状态。
+    /// 中间节点 (`with_carry_up`) 收齐后输出 `PartialSum`，而不是平均后的 `Complete`。
+    pub fn aggregate_multi(
+        &mut self,
+        batch: MultiLayerGradient,
+        from_node: String,
+        expected_children: &[String]
+    ) -> MultiAggregationResult {
+        let layer_indices = batch.updates.iter().map(|g| g.layer_index).collect();
+        self.aggregate_layers(batch.epoch, layer_indices, expected_children, |i, acc| acc.absorb(&batch.updates[i], &from_node))
+    }
+
+    /// 📦 处理子树上报的多层部分和 (来自 `with_carry_up` 中间节点的 `MultiAggregationResult::PartialSum`)
+    ///
+    /// 与 `aggregate_multi` 相同的原子语义：所有层收齐才整体输出。
+    /// 包的纪元取各层纪元的最小值，任何一层落后即整包丢弃；空包视为 Pending。
+    pub fn aggregate_multi_partial(
+        &mut self,
+        sums: Vec<PartialGradientSum>,
+        from_node: String,
+        expected_children: &[String]
+    ) -> MultiAggregationResult {
+        let Some(epoch) = sums.iter().map(|s| s.epoch).min() else {
+            return MultiAggregationResult::Pending;
+        };
+        let layer_indices = sums.iter().map(|s| s.layer_index).collect();
+        self.aggregate_layers(epoch, layer_indices, expected_children, |i, acc| acc.absorb_sum(&sums[i], &from_node))
+    }
+
+    /// 📦 Helper: 多层原子聚合的公共流程 (纪元检查 -> 逐层吸收 -> 全部收齐才输出)
+    /// `absorb(i, acc)` 把包内第 i 层的贡献吸收进该层的累加器。
+    fn aggregate_layers(
+        &mut self,
+        epoch: u64,
+        layer_indices: Vec<usize>,
+        expected_children: &[String],
+        mut absorb: impl FnMut(usize, &mut LayerAccumulator),
+    ) -> MultiAggregationResult {
+        // 1. Epoch 检查：落后的包整体丢弃，更新的包推进纪元
+        if epoch < self.current_epoch {
+            return MultiAggregationResult::Stale;
+        }
+        self.advance_epoch(epoch);
+
+        // 2. 吸收所有层
+        let all_needed = Self::expected_contributors(expected_children);
+        let precision = self.precision;
+        for (i, &layer_idx) in layer_indices.iter().enumerate() {
+            let acc = self.buffers
+                .entry(layer_idx)
+                .or_insert_with(|| LayerAccumulator::new(precision));
+            absorb(i, acc);
+            acc.expected = all_needed.clone();
+        }
+
+        // 3. 原子完整性检查：所有层都收齐才输出
+        let all_complete = layer_indices.iter().all(|idx| {
+            self.buffers.get(idx).is_some_and(LayerAccumulator::is_complete)
+        });
+        if !all_complete {
+            return MultiAggregationResult::Pending;
+        }
+
+        let epoch = self.current_epoch;
+        let finished = layer_indices.iter().filter_map(|idx| self.buffers.remove(idx).map(|acc| (*idx, acc)));
+        if self.carry_up {
+            return MultiAggregationResult::PartialSum(finished.map(|(idx, acc)| acc.finalize_partial(idx, epoch)).collect());
+        }
+        MultiAggregationResult::Complete(MultiLayerGradient {
+            updates: finished.map(|(idx, acc)| acc.finalize(idx)).collect(),
+            epoch,
+        })
+    }
+
+    /// 🩺 Pending Report: 诊断卡住的聚合
+    ///
+    /// 对每个仍在缓冲中的层，返回尚未到达的贡献者 (期望集合 - 已贡献集合)。
+    /// 按层号排序，贡献者 ID 按字典序排序，便于日志与比对。
+    pub fn pending_report(&self) -> Vec<(usize, Vec<String>)> {
+        let mut report: Vec<(usize, Vec<String>)> = self.buffers.iter()
+            .map(|(&layer_idx, acc)| {
+                let mut missing: Vec<String> = acc.expected.difference(&acc.contributors).cloned().collect();
+                missing.sort();
+                (layer_idx, missing)
+            })
+            .collect();
+        report.sort_by_key(|(layer_idx, _)| *layer_idx);
+        report
+    }
+
+    /// 🧮 Helper: 期望的贡献者集合 = 所有子节点 + 我自己 ("SELF")
+    fn expected_contributors(expected_children: &[String]) -> HashSet<String> {
         let mut all_needed: HashSet<String> = expected_children.iter().cloned().collect();
         all_needed.insert("SELF".to_string()); // 必须包含本地计算的梯度
+        all_needed
+    }
+}
 
-        if acc.contributors.is_superset(&all_needed) {
-            // ✅ 召唤神龙：所有碎片已集齐
-            let final_grad = acc.finalize(layer_idx);
-            
-            // 清理缓冲区 (该层本轮已完成)
-            self.buffers.remove(&layer_idx);
-            
-            return AggregationResult::Complete(final_grad);
+/// 🪣 TokenBucket: 单个来源节点的令牌桶
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// 🚦 GradientRateLimiter: 按来源节点限流的梯度闸门 (Token Bucket)
+///
+/// 每个来源节点拥有独立的令牌桶：容量为 `burst`，每秒补充 `rate_per_sec` 个令牌。
+/// 每次梯度推送消耗一个令牌，桶空时拒绝。
+/// 陷入死循环的 Worker 只会耗尽自己的桶，不会挤占其他节点的 PS 带宽。
+pub struct GradientRateLimiter {
+    rate_per_sec: f64,
+    burst: f64,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+impl GradientRateLimiter {
+    pub fn new(rate_per_sec: f64, burst: f64) -> Self {
+        GradientRateLimiter {
+            rate_per_sec,
+            burst: burst.max(1.0),
+            buckets: HashMap::new(),
         }
+    }
+
+    /// 🎟️ 尝试为 `source` 消耗一个令牌；返回 false 表示超出速率
+    pub fn try_acquire(&mut self, source: &str) -> bool {
+        let now = Instant::now();
+        let burst = self.burst;
+        let bucket = self.buckets
+            .entry(source.to_string())
+            .or_insert(TokenBucket { tokens: burst, last_refill: now });
 
-        AggregationResult::Pending
+        // 按流逝时间补充令牌 (不超过桶容量)
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate_per_sec).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
     }
 }
@@ -0,0 +1,58 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// 🔤 ALPN 协议标识 (节点与客户端必须一致，否则 TLS 握手失败)
+pub const ALPN_PROTOCOL: &[u8] = b"htp-v1";
+
+/// 🛰️ 创建节点的 QUIC Endpoint
+/// 以临时自签名证书监听 `bind_addr`，同时装好默认的客户端配置，
+/// 使同一个 Endpoint 既能接收连接，也能主动连接其他节点 (Gossip / 心跳 / 上行链路)。
+pub fn make_node_endpoint(bind_addr: SocketAddr) -> Result<quinn::Endpoint, Box<dyn Error>> {
+    // 1. 生成自签名证书 (Ephemeral)
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()])?;
+    let cert_chain = vec![rustls::Certificate(cert.serialize_der()?)];
+    let priv_key = rustls::PrivateKey(cert.serialize_private_key_der());
+
+    // 2. 配置 Server TLS
+    let mut crypto = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, priv_key)?;
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()]; // Application Layer Protocol Negotiation
+
+    // 3. 绑定端口
+    let mut endpoint = quinn::Endpoint::server(quinn::ServerConfig::with_crypto(Arc::new(crypto)), bind_addr)?;
+    endpoint.set_default_client_config(make_client_config());
+    Ok(endpoint)
+}
+
+/// 🔌 客户端 TLS 配置：节点使用临时自签名证书，因此跳过证书校验
+pub fn make_client_config() -> quinn::ClientConfig {
+    let mut crypto = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+    quinn::ClientConfig::new(Arc::new(crypto))
+}
+
+/// ⚠️ 仅用于开发环境：接受任意服务端证书
+struct SkipServerVerification;
+
+impl rustls::client::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
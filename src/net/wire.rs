@@ -1,12 +1,27 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
-use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error as _;
+use crate::core::affine::AffineTuple;
 use crate::core::algebra::{Vector, Matrix, Float};
+use crate::core::neuron::HTPNeuron;
+use crate::net::node::NodeRole;
+use crate::topology::merkle::{CausalTrace, TraceNode};
 
 /// 📦 WireProtocol: 网络传输协议版本
-pub const PROTOCOL_VERSION: u32 = 2; // White-Box Era
+pub const PROTOCOL_VERSION: u32 = 3; // v3: 显式标签 + 长度前缀的包帧 (见 PacketType::wire_tag)
+
+/// 📏 包帧头: 标签 (u32 LE) + 负载长度 (u32 LE)
+const FRAME_HEADER_LEN: usize = 8;
 
 /// 📡 PacketType: 定义消息的意图
+/// ⚠️ 新增变体时必须在 `wire_tag()` 中为其分配新标签、在 `decode` 中补充对应的解码分支，并补充 `kind()`。
+///
+/// 🏷️ bincode 按变体在枚举中的位置编码判别值，在中间插入变体会让旧节点错认后续所有包。
+/// 线上因此不使用位置，而是使用 `wire_tag()` 分配的显式标签：标签一经分配永不改变、永不复用。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PacketType {
     /// 🤝 Handshake: 节点加入网络
@@ -33,6 +48,186 @@ pub enum PacketType {
     /// 🧬 ModelSync: 权重同步 (传输模型参数)
     /// "这是最新的全局共识逻辑参数。"
     ParameterBroadcast(ModelSnapshot),
+
+    /// 📦 MultiGradientPush: 多层梯度批量推送
+    /// "这是我这一轮对所有层的修正建议，请一次性应用。"
+    MultiGradientPush(MultiLayerGradient),
+
+    /// ❌ Error: 显式错误回执
+    /// "你的请求无法被处理，原因如下。" (取代静默丢包)
+    Error { code: ErrorCode, message: String },
+
+    /// 👋 Leave: 优雅下线通知
+    /// "我要关机了，请立即把我从路由表中移除。" (无需等待心跳超时)
+    Leave { node_id: String },
+
+    /// 🧬 FoldedInferenceRequest: 携带 Token 嵌入序列的推理请求
+    /// "这是一串前提，请你按 mode 折叠后再推导结论。"
+    /// 折叠在 Worker 侧 (HyperFolder) 完成，客户端无需预先折叠；回执仍为 InferenceResponse。
+    FoldedInferenceRequest {
+        request_id: u64,
+        tokens: Vec<Vector>,
+        mode: FoldMode,
+    },
+
+    /// 🚇 InferencePipelineRequest: 模型并行 (流水线) 推理
+    /// "状态已经过前面的层，请从 next_layer 开始继续推导。"
+    /// 每个节点只处理自己持有的层区间，然后转发给持有下一区间的节点；最后一段回执 InferenceResponse。
+    InferencePipelineRequest {
+        request_id: u64,
+        input: Vector,
+        next_layer: usize,
+    },
+
+    /// 🔏 FingerprintExchange: 模型指纹比对 (一致性校验)
+    /// "我的模型指纹是这个，你的呢？" 指纹不一致说明节点已悄然分叉 (丢失同步 / 应用了过期梯度)，
+    /// PS 收到不一致的指纹时回执完整快照以触发重新同步。
+    FingerprintExchange {
+        node_id: String,
+        epoch: u64,
+        fingerprint: u64,
+    },
+
+    /// 🎞️ TraceTransfer: 传输已记录的计算图 (前向与反向分离)
+    /// "这是我前向时录下的梯度磁带，请你来做 backward。"
+    /// 磁带每个节点都缓存一个 D x D 矩阵，体积很大，因此按节点区间分片发送：
+    /// 第 `part` 片 (共 `total_parts` 片) 的 `trace.nodes` 为一段连续节点 (保留全局 ID)，
+    /// `active_path` 与输出梯度 `grad_output` (dL/dOutput) 只由最后一片携带。接收方用 `TraceAssembler` 重组，
+    /// 带有输出梯度的磁带在接收节点上执行 backward，回执各层梯度 (见 `HTPNode::handle_trace_transfer`)。
+    TraceTransfer {
+        request_id: u64,
+        part: u32,
+        total_parts: u32,
+        trace: CausalTrace,
+        grad_output: Option<AffineTuple>,
+    },
+
+    /// 🔁 SyncRequest: 重连追赶请求
+    /// "我断线前最后应用的是第 last_epoch 纪元的参数，请补齐我错过的部分。"
+    /// PS 视落后程度回执增量快照 (只含变化过的层) 或完整快照。
+    SyncRequest {
+        node_id: String,
+        last_epoch: u64,
+    },
+
+    /// 💓 Heartbeat: 轻量存活信号 (与 Gossip 解耦)
+    /// "我还活着，当前纪元与负载如下。" 不携带路由表，体积固定且很小，可以高频发送；
+    /// 完整的拓扑交换 (Gossip) 则以更低的频率进行。
+    Heartbeat {
+        node_id: String,
+        epoch: u64,
+        load: f64,
+    },
+
+    /// 🗣️ PeerDiscovery: 低频 Gossip，交换完整的拓扑视图
+    /// "这是我所知道的全部节点 (包括我自己)。" 接收方按自己的 GossipMergePolicy 合并。
+    PeerDiscovery {
+        sender_id: String,
+        peers: Vec<PeerBrief>,
+    },
+}
+
+/// 🏷️ PeerBrief: Gossip 交换的节点名片
+/// 只含节点自述的配置；可靠度 / 负载 / 延迟等本地观测值不随 Gossip 传播。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerBrief {
+    pub id: String,
+    pub address: String,
+    pub role: NodeRole,
+    /// 🚇 该节点持有的全局层区间 (模型并行)，None 表示未声明
+    pub layers: Option<Range<usize>>,
+}
+
+/// 📂 FoldMode: 服务端折叠 Token 序列的方式
+/// 线上编码为 `wire_tag()` 的显式标签 (u32)，与变体的声明顺序无关。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldMode {
+    /// ⏳ 时间折叠 (有序复合，HyperFolder::fold_timeline)
+    Time,
+    /// 🌌 空间折叠 (无序平均，HyperFolder::fold_context)
+    Space,
+}
+
+/// 🚫 ErrorCode: 错误回执的分类
+/// 线上编码为 `wire_tag()` 的显式标签 (u32)，与变体的声明顺序无关。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// 🎭 角色不匹配 (例如 Worker 收到了只有 PS 才能处理的梯度)
+    RoleMismatch,
+    /// 🚦 来源节点推送过于频繁，超出速率限制
+    RateLimited,
+    /// 📭 请求内容无效 (例如没有任何 Token)
+    InvalidRequest,
+    /// 🧩 本节点不处理该类型的包 (例如来自较新版本对端的新变体)
+    UnsupportedPacket,
+}
+
+impl FoldMode {
+    /// 🏷️ 显式线上标签 (一经分配永不改变)
+    pub fn wire_tag(self) -> u32 {
+        match self {
+            FoldMode::Time => 0,
+            FoldMode::Space => 1,
+        }
+    }
+
+    /// 🏷️ 由线上标签还原 (未知标签为 None)
+    pub fn from_wire_tag(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(FoldMode::Time),
+            1 => Some(FoldMode::Space),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorCode {
+    /// 🏷️ 显式线上标签 (一经分配永不改变)
+    pub fn wire_tag(self) -> u32 {
+        match self {
+            ErrorCode::RoleMismatch => 0,
+            ErrorCode::RateLimited => 1,
+            ErrorCode::InvalidRequest => 2,
+            ErrorCode::UnsupportedPacket => 3,
+        }
+    }
+
+    /// 🏷️ 由线上标签还原 (未知标签为 None)
+    pub fn from_wire_tag(tag: u32) -> Option<Self> {
+        match tag {
+            0 => Some(ErrorCode::RoleMismatch),
+            1 => Some(ErrorCode::RateLimited),
+            2 => Some(ErrorCode::InvalidRequest),
+            3 => Some(ErrorCode::UnsupportedPacket),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for FoldMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.wire_tag())
+    }
+}
+
+impl<'de> Deserialize<'de> for FoldMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tag = u32::deserialize(deserializer)?;
+        Self::from_wire_tag(tag).ok_or_else(|| D::Error::custom(format!("Unknown FoldMode tag {}", tag)))
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.wire_tag())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tag = u32::deserialize(deserializer)?;
+        Self::from_wire_tag(tag).ok_or_else(|| D::Error::custom(format!("Unknown ErrorCode tag {}", tag)))
+    }
 }
 
 /// 📉 GradientUpdate: 梯度传输包
@@ -52,12 +247,265 @@ pub struct GradientUpdate {
     pub batch_size: usize,
 }
 
+/// ➕ PartialGradientSum: 树形聚合中向上传递的部分和 (Carry-Up)
+/// 中间节点不做平均，只上报子树内的加权和 Σ g·n 与总样本数 Σ n，
+/// 除法留给 Root 一次完成，避免逐级 "平均再加权" 引入的重复归一化与舍入。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialGradientSum {
+    /// 目标层级 ID
+    pub layer_index: usize,
+
+    /// Σ ∇W·n (未归一化，扁平化的矩阵梯度和)
+    pub weight_sum: Vec<Float>,
+
+    /// Σ ∇b·n (未归一化)
+    pub bias_sum: Vec<Float>,
+
+    /// Σ n: 子树内的总样本数
+    pub total_batch: usize,
+
+    /// 🕰️ 部分和所属的训练纪元 (父节点据此丢弃落后的子树上报)
+    pub epoch: u64,
+}
+
+impl GradientUpdate {
+    /// 📏 序列化后的字节数 (与 bincode 编码结果一致，不实际分配缓冲)
+    /// 用于带宽统计，以及评估量化 / 稀疏化带来的压缩效果。
+    pub fn wire_size(&self) -> usize {
+        bincode::serialized_size(self).expect("GradientUpdate is always serializable") as usize
+    }
+}
+
+/// 📦 MultiLayerGradient: 多层梯度打包
+/// Worker 一次性发送所有层的梯度，每轮包数从 depth 降为 1。
+/// PS / 聚合器以 Epoch 为单位原子地处理整个包。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiLayerGradient {
+    /// 各层梯度 (每层一个 GradientUpdate)
+    pub updates: Vec<GradientUpdate>,
+
+    /// 本包所属的训练纪元：PS 只接受当前纪元 (追加写入) 或下一个纪元 (应用后前进一步)
+    pub epoch: u64,
+}
+
 /// 📸 ModelSnapshot: 模型快照
 /// 用于新节点同步或 Parameter Server 广播
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelSnapshot {
     pub epoch: u64,
     pub layers: Vec<LayerState>,
+    /// 🔑 内容哈希 (覆盖所有层的 W 与 b)
+    /// Worker 据此判断快照是否与本地模型完全一致，一致则跳过写入。
+    pub content_hash: u64,
+}
+
+impl ModelSnapshot {
+    /// 构造快照并计算内容哈希
+    pub fn new(epoch: u64, layers: Vec<LayerState>) -> Self {
+        let content_hash = Self::hash_layers(layers.iter().map(|l| (l.layer_index, &l.weights, &l.bias)));
+        ModelSnapshot { epoch, layers, content_hash }
+    }
+
+    /// 🔑 对 (层号, W, b) 序列计算内容哈希
+    /// 使用 BLAKE3 (而非 DefaultHasher)，保证不同版本编译的节点之间结果一致。
+    pub fn hash_layers<'a>(layers: impl Iterator<Item = (usize, &'a Matrix, &'a Vector)>) -> u64 {
+        let mut hasher = blake3::Hasher::new();
+        for (index, weights, bias) in layers {
+            hasher.update(&(index as u64).to_le_bytes());
+            hasher.update(&(weights.rows as u64).to_le_bytes());
+            hasher.update(&(weights.cols as u64).to_le_bytes());
+            for x in weights.data.iter().chain(&bias.data) {
+                hasher.update(&x.to_bits().to_le_bytes());
+            }
+        }
+        let digest = hasher.finalize();
+        u64::from_le_bytes(digest.as_bytes()[..8].try_into().expect("BLAKE3 digest is 32 bytes"))
+    }
+}
+
+/// 🔏 Model Fingerprint: 对整个模型 (所有层的 W 与 b) 计算稳定的 64 位指纹
+/// 与 `ModelSnapshot::hash_layers` 使用同一算法 (BLAKE3，逐位)，
+/// 因此完整快照的 `content_hash` 与其对应模型的指纹相同，跨平台、跨编译版本一致。
+pub fn model_fingerprint(neurons: &[HTPNeuron]) -> u64 {
+    ModelSnapshot::hash_layers(
+        neurons.iter().enumerate().map(|(idx, n)| (idx, &n.logic_gate().linear, &n.logic_gate().translation))
+    )
+}
+
+/// 🎞️ 将 CausalTrace 切分为若干 TraceTransfer 包，每包至多 `max_nodes_per_part` 个节点 (至少 1 个)
+/// 空磁带也会生成一个 (空的) 分片，保证接收方总能完成重组。`grad_output` 随最后一片发送。
+pub fn trace_transfer_packets(
+    request_id: u64,
+    trace: &CausalTrace,
+    grad_output: Option<&AffineTuple>,
+    max_nodes_per_part: usize,
+) -> Vec<PacketType> {
+    let chunks: Vec<&[TraceNode]> = if trace.nodes.is_empty() {
+        vec![&[]]
+    } else {
+        trace.nodes.chunks(max_nodes_per_part.max(1)).collect()
+    };
+    let total_parts = chunks.len() as u32;
+
+    chunks.into_iter().enumerate()
+        .map(|(part, nodes)| {
+            let is_last = part as u32 + 1 == total_parts;
+            PacketType::TraceTransfer {
+                request_id,
+                part: part as u32,
+                total_parts,
+                trace: CausalTrace {
+                    nodes: nodes.to_vec(),
+                    active_path: if is_last { trace.active_path.clone() } else { Vec::new() },
+                },
+                grad_output: if is_last { grad_output.cloned() } else { None },
+            }
+        })
+        .collect()
+}
+
+/// 🧱 单个请求允许的最大分片数 (`total_parts` 来自对端，必须设上限再分配缓冲)
+pub const MAX_TRACE_PARTS: u32 = 4096;
+/// 🧱 同时处于重组中的请求数上限
+pub const MAX_PENDING_TRACES: usize = 64;
+/// ⏳ 未完成的重组在此时间后被丢弃 (对端中途掉线、分片丢失)
+pub const DEFAULT_TRACE_TTL: Duration = Duration::from_secs(30);
+
+/// 🎞️ AssembledTrace: 重组完成的梯度磁带 (以及随最后一片到达的输出梯度)
+#[derive(Debug, Clone)]
+pub struct AssembledTrace {
+    pub trace: CausalTrace,
+    /// dL/dOutput；只传输磁带、不请求 backward 时为 None
+    pub grad_output: Option<AffineTuple>,
+}
+
+/// ⏳ 一个尚未到齐的请求
+struct PendingTrace {
+    parts: Vec<Option<CausalTrace>>,
+    grad_output: Option<AffineTuple>,
+    started: Instant,
+}
+
+/// 🧩 TraceAssembler: 按 request_id 重组分片传输的 CausalTrace
+/// 分片可以乱序到达；重复分片覆盖旧值。
+///
+/// 分片数与并发请求数都有上限，超过 TTL 仍未到齐的请求被丢弃，
+/// 因此恶意或掉线的对端无法让缓冲无限增长。重组完成的磁带经 `CausalTrace::validate` 校验
+/// (父节点 ID 必须小于自身 ID)，backward 可以安全地按 ID 索引。
+pub struct TraceAssembler {
+    pending: HashMap<u64, PendingTrace>,
+    max_parts: u32,
+    max_pending: usize,
+    ttl: Duration,
+}
+
+impl Default for TraceAssembler {
+    fn default() -> Self {
+        TraceAssembler {
+            pending: HashMap::new(),
+            max_parts: MAX_TRACE_PARTS,
+            max_pending: MAX_PENDING_TRACES,
+            ttl: DEFAULT_TRACE_TTL,
+        }
+    }
+}
+
+impl TraceAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 🧱 设置单个请求的最大分片数 (默认 MAX_TRACE_PARTS)
+    pub fn with_max_parts(mut self, max_parts: u32) -> Self {
+        self.max_parts = max_parts;
+        self
+    }
+
+    /// 🧱 设置同时重组中的请求数上限 (默认 MAX_PENDING_TRACES)
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// ⏳ 设置未完成重组的过期时间 (默认 DEFAULT_TRACE_TTL)
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 📥 接收一个分片；全部到齐时返回完整的磁带并清理缓冲
+    pub fn accept(
+        &mut self,
+        request_id: u64,
+        part: u32,
+        total_parts: u32,
+        trace: CausalTrace,
+        grad_output: Option<AffineTuple>,
+    ) -> Result<Option<AssembledTrace>, String> {
+        if total_parts == 0 || part >= total_parts {
+            return Err(format!("Invalid trace part {}/{} for request {}", part, total_parts, request_id));
+        }
+        if total_parts > self.max_parts {
+            return Err(format!(
+                "Trace for request {} has {} parts (limit {})",
+                request_id, total_parts, self.max_parts
+            ));
+        }
+
+        self.evict_expired(Instant::now());
+        if !self.pending.contains_key(&request_id) && self.pending.len() >= self.max_pending {
+            return Err(format!(
+                "Too many traces in flight ({}); dropping part of request {}",
+                self.pending.len(), request_id
+            ));
+        }
+
+        let entry = self.pending.entry(request_id)
+            .or_insert_with(|| PendingTrace {
+                parts: vec![None; total_parts as usize],
+                grad_output: None,
+                started: Instant::now(),
+            });
+        if entry.parts.len() != total_parts as usize {
+            return Err(format!(
+                "Trace part count changed for request {}: expected {}, got {}",
+                request_id, entry.parts.len(), total_parts
+            ));
+        }
+        entry.parts[part as usize] = Some(trace);
+        if grad_output.is_some() {
+            entry.grad_output = grad_output;
+        }
+        if entry.parts.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+
+        // 全部到齐: 按分片顺序拼接节点，active_path 来自最后一片
+        let Some(done) = self.pending.remove(&request_id) else {
+            return Ok(None);
+        };
+        let mut assembled = CausalTrace::new();
+        for piece in done.parts.into_iter().flatten() {
+            assembled.nodes.extend(piece.nodes);
+            assembled.active_path.extend(piece.active_path);
+        }
+        assembled.validate()
+            .map_err(|e| format!("Reassembled trace for request {} is malformed: {}", request_id, e))?;
+        Ok(Some(AssembledTrace { trace: assembled, grad_output: done.grad_output }))
+    }
+
+    /// 🧹 丢弃开始重组至今超过 TTL 的请求，返回丢弃的个数
+    pub fn evict_expired(&mut self, now: Instant) -> usize {
+        let before = self.pending.len();
+        let ttl = self.ttl;
+        self.pending.retain(|_, entry| now.saturating_duration_since(entry.started) < ttl);
+        before - self.pending.len()
+    }
+
+    /// 📊 尚未重组完成的请求数
+    pub fn pending_requests(&self) -> usize {
+        self.pending.len()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,13 +517,155 @@ pub struct LayerState {
 
 /// 🛠️ Serialization Utilities
 impl PacketType {
-    /// 序列化为二进制流 (Bincode / Protobuf)
+    /// 序列化为二进制流
+    ///
+    /// 包帧: `[标签 u32 LE][负载长度 u32 LE][负载]`，负载为该变体字段的 bincode 编码。
+    /// 长度前缀让不认识该标签的旧节点可以整包跳过 (见 `decode`)。
     pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
-        bincode::serialize(self).map_err(|e| e.to_string())
+        let encoded = bincode::serialize(self).map_err(|e| e.to_string())?;
+        // bincode 的前 4 字节是变体位置，替换为显式标签
+        let payload = &encoded[4..];
+        let tag = self.wire_tag();
+        let len = u32::try_from(payload.len()).map_err(|_| format!("Packet payload too large: {} bytes", payload.len()))?;
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+        frame.extend_from_slice(&tag.to_le_bytes());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(payload);
+        Ok(frame)
     }
 
     /// 从二进制流反序列化
+    /// 未知标签 (来自更新版本的对端) 视为错误；需要静默跳过时使用 `decode`。
     pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
-        bincode::deserialize(data).map_err(|e| e.to_string())
+        match Self::decode(data)? {
+            Some(packet) => Ok(packet),
+            None => Err(format!("Unknown packet tag {} (sent by a newer protocol version?)", Self::peek_tag(data).unwrap_or_default())),
+        }
+    }
+
+    /// 🧩 容错解码 (滚动升级)
+    /// 帧损坏时返回 Err；帧完整但标签未知时返回 Ok(None)，调用方可以跳过该包继续处理后续流量。
+    pub fn decode(data: &[u8]) -> Result<Option<Self>, String> {
+        if data.len() < FRAME_HEADER_LEN {
+            return Err(format!("Truncated packet frame: {} bytes", data.len()));
+        }
+        let tag = u32::from_le_bytes(data[0..4].try_into().expect("4-byte slice"));
+        let len = u32::from_le_bytes(data[4..8].try_into().expect("4-byte slice")) as usize;
+        let payload = &data[FRAME_HEADER_LEN..];
+        if payload.len() != len {
+            return Err(format!("Packet frame length mismatch: header says {}, got {}", len, payload.len()));
+        }
+
+        // 负载是变体字段的 bincode 编码 (与同序元组一致)，按标签直接还原，不依赖变体位置
+        fn fields<'a, T: Deserialize<'a>>(payload: &'a [u8]) -> Result<T, String> {
+            bincode::deserialize(payload).map_err(|e| e.to_string())
+        }
+        let packet = match tag {
+            0 => {
+                let (node_id, protocol_ver) = fields(payload)?;
+                PacketType::Handshake { node_id, protocol_ver }
+            }
+            1 => {
+                let (request_id, input_state) = fields(payload)?;
+                PacketType::InferenceRequest { request_id, input_state }
+            }
+            2 => {
+                let (request_id, output_state) = fields(payload)?;
+                PacketType::InferenceResponse { request_id, output_state }
+            }
+            3 => PacketType::GradientPush(fields(payload)?),
+            4 => PacketType::ParameterBroadcast(fields(payload)?),
+            5 => PacketType::MultiGradientPush(fields(payload)?),
+            6 => {
+                let (code, message) = fields(payload)?;
+                PacketType::Error { code, message }
+            }
+            7 => PacketType::Leave { node_id: fields(payload)? },
+            8 => {
+                let (request_id, tokens, mode) = fields(payload)?;
+                PacketType::FoldedInferenceRequest { request_id, tokens, mode }
+            }
+            9 => {
+                let (request_id, input, next_layer) = fields(payload)?;
+                PacketType::InferencePipelineRequest { request_id, input, next_layer }
+            }
+            10 => {
+                let (node_id, epoch, fingerprint) = fields(payload)?;
+                PacketType::FingerprintExchange { node_id, epoch, fingerprint }
+            }
+            11 => {
+                let (request_id, part, total_parts, trace, grad_output) = fields(payload)?;
+                PacketType::TraceTransfer { request_id, part, total_parts, trace, grad_output }
+            }
+            12 => {
+                let (node_id, last_epoch) = fields(payload)?;
+                PacketType::SyncRequest { node_id, last_epoch }
+            }
+            13 => {
+                let (node_id, epoch, load) = fields(payload)?;
+                PacketType::Heartbeat { node_id, epoch, load }
+            }
+            14 => {
+                let (sender_id, peers) = fields(payload)?;
+                PacketType::PeerDiscovery { sender_id, peers }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(packet))
+    }
+
+    /// 🏷️ 读取包帧的标签 (不解码负载；帧头不完整时为 None)
+    pub fn peek_tag(data: &[u8]) -> Option<u32> {
+        data.get(0..4).map(|b| u32::from_le_bytes(b.try_into().expect("4-byte slice")))
+    }
+
+    /// 🏷️ 显式线上标签 (包帧的前 4 字节)
+    /// 标签一经分配永不改变、永不复用；新增变体 (无论插在枚举的哪个位置) 在这里分配一个新标签。
+    pub fn wire_tag(&self) -> u32 {
+        match self {
+            PacketType::Handshake { .. } => 0,
+            PacketType::InferenceRequest { .. } => 1,
+            PacketType::InferenceResponse { .. } => 2,
+            PacketType::GradientPush(_) => 3,
+            PacketType::ParameterBroadcast(_) => 4,
+            PacketType::MultiGradientPush(_) => 5,
+            PacketType::Error { .. } => 6,
+            PacketType::Leave { .. } => 7,
+            PacketType::FoldedInferenceRequest { .. } => 8,
+            PacketType::InferencePipelineRequest { .. } => 9,
+            PacketType::FingerprintExchange { .. } => 10,
+            PacketType::TraceTransfer { .. } => 11,
+            PacketType::SyncRequest { .. } => 12,
+            PacketType::Heartbeat { .. } => 13,
+            PacketType::PeerDiscovery { .. } => 14,
+        }
+    }
+
+    /// 🏷️ 变体名 (用于日志与错误回执)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PacketType::Handshake { .. } => "Handshake",
+            PacketType::InferenceRequest { .. } => "InferenceRequest",
+            PacketType::InferenceResponse { .. } => "InferenceResponse",
+            PacketType::GradientPush(_) => "GradientPush",
+            PacketType::ParameterBroadcast(_) => "ParameterBroadcast",
+            PacketType::MultiGradientPush(_) => "MultiGradientPush",
+            PacketType::Error { .. } => "Error",
+            PacketType::Leave { .. } => "Leave",
+            PacketType::FoldedInferenceRequest { .. } => "FoldedInferenceRequest",
+            PacketType::InferencePipelineRequest { .. } => "InferencePipelineRequest",
+            PacketType::FingerprintExchange { .. } => "FingerprintExchange",
+            PacketType::TraceTransfer { .. } => "TraceTransfer",
+            PacketType::SyncRequest { .. } => "SyncRequest",
+            PacketType::Heartbeat { .. } => "Heartbeat",
+            PacketType::PeerDiscovery { .. } => "PeerDiscovery",
+        }
+    }
+
+    /// 📏 整个包序列化后的字节数 (等于 `to_bytes().len()`，不实际分配缓冲)
+    /// 帧头 (标签 + 长度) 取代了 bincode 的 4 字节变体位置，因此比裸 bincode 多 4 字节。
+    pub fn wire_size(&self) -> usize {
+        bincode::serialized_size(self).expect("PacketType is always serializable") as usize + FRAME_HEADER_LEN - 4
     }
 }
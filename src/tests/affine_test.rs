@@ -0,0 +1,277 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{MANIFOLD_DIM, SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED};
+    use crate::core::affine::AffineTuple;
+    use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+
+    /// 🧪 Test 1: Parameter Interpolation (参数插值)
+    /// t = 0 返回 self，t = 0.5 为两者中点。
+    #[test]
+    fn test_lerp_midpoint_and_endpoints() {
+        println!("🧪 [Test] AffineTuple Lerp...");
+
+        let a = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 1),
+            ConceptEmbedder::embed_token(1),
+        );
+        let b = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 2),
+            ConceptEmbedder::embed_token(2),
+        );
+
+        assert_eq!(a.lerp(&b, 0.0), a);
+
+        let mid = a.lerp(&b, 0.5);
+        let expected = a.commutative_merge(&b).unwrap();
+        let max_err = mid.linear.data.iter().zip(&expected.linear.data)
+            .chain(mid.translation.data.iter().zip(&expected.translation.data))
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+        assert!(max_err < 1e-6, "❌ Midpoint mismatch ({})", max_err);
+    }
+
+    /// 🧪 Test 2: Homogeneous Coordinates (齐次坐标)
+    /// H(A2 ⊕ A1) == H(A2) · H(A1)，且 from_homogeneous 精确往返；非仿射矩阵被拒绝。
+    #[test]
+    fn test_homogeneous_matmul_matches_compose() {
+        println!("🧪 [Test] Homogeneous Composition...");
+
+        let a1 = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 3),
+            ConceptEmbedder::embed_token(3),
+        );
+        let a2 = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 4),
+            ConceptEmbedder::embed_token(4),
+        );
+
+        let h = a1.to_homogeneous();
+        assert_eq!((h.rows, h.cols), (MANIFOLD_DIM + 1, MANIFOLD_DIM + 1));
+        assert_eq!(AffineTuple::from_homogeneous(&h).unwrap(), a1);
+
+        let composed = a2.compose_unchecked(&a1);
+        let via_matmul = AffineTuple::from_homogeneous(&a2.to_homogeneous().matmul(&h)).unwrap();
+        let max_err = composed.linear.data.iter().zip(&via_matmul.linear.data)
+            .chain(composed.translation.data.iter().zip(&via_matmul.translation.data))
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+        assert!(max_err < 1e-5, "❌ Homogeneous matmul diverged from compose ({})", max_err);
+
+        // 最后一行被破坏 -> 不是仿射变换
+        let mut projective = h.clone();
+        projective.data[MANIFOLD_DIM * (MANIFOLD_DIM + 1)] = 0.5;
+        assert!(AffineTuple::from_homogeneous(&projective).is_err());
+    }
+
+    /// 🧪 Test 3: Random Constructors (随机构造)
+    /// random 等价于 Xavier 矩阵 + 零偏置；random_stable 的谱范数带余量地裁剪到 Lipschitz 上限以内，
+    /// 多个 random_stable 元组的链式复合能通过 compose 的检查。
+    #[test]
+    fn test_random_stable_respects_lipschitz_bound() {
+        println!("🧪 [Test] AffineTuple::random / random_stable...");
+
+        let raw = AffineTuple::random(7);
+        assert_eq!(raw, AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 7),
+            WeightInitializer::init_bias(MANIFOLD_DIM),
+        ));
+
+        let stable = AffineTuple::random_stable(7);
+        let raw_norm = raw.linear.estimate_spectral_norm(20);
+        let (stable_norm, _) = stable.linear.estimate_spectral_norm_seeded(500, 99);
+        println!("   > Spectral norm: {:.3} -> {:.4}", raw_norm, stable_norm);
+        assert!(raw_norm > 1.01, "❌ Xavier init should exceed the bound before clipping");
+        assert!(stable_norm <= 1.0, "❌ random_stable leaves no margin below the Lipschitz bound: {}", stable_norm);
+        assert_eq!(stable.translation, raw.translation);
+        assert_eq!(AffineTuple::random_stable(7), stable, "❌ random_stable must be deterministic");
+
+        let chain = (8..11).map(AffineTuple::random_stable)
+            .try_fold(stable, |acc, next| next.compose(&acc));
+        assert!(chain.is_ok(), "❌ Folding random_stable tuples tripped the guard: {:?}", chain.err());
+    }
+
+    /// 🧪 Test 4: Identity Fast Path (单位元快速路径)
+    /// I ∘ A 与 A ∘ I 必须逐位等于 A；仅含平移的 No-Op 步骤与完整 matmul 路径结果一致。
+    #[test]
+    fn test_compose_identity_fast_path() {
+        use crate::core::algebra::Matrix;
+
+        println!("🧪 [Test] Compose Identity Fast Path...");
+
+        let a = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 11),
+            ConceptEmbedder::embed_token(11),
+        );
+        let identity = AffineTuple::identity();
+        assert!(identity.is_identity());
+        assert!(!a.is_identity());
+
+        assert_eq!(identity.compose_unchecked(&a), a);
+        assert_eq!(a.compose_unchecked(&identity), a);
+
+        // 平移算子 (I·x + c): 快速路径与显式的 matmul 结果一致
+        let shift = AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(12));
+        let after = shift.compose_unchecked(&a);
+        assert_eq!(after.linear, a.linear);
+        assert_eq!(after.translation, a.translation.add(&shift.translation));
+        let before = a.compose_unchecked(&shift);
+        assert_eq!(before.linear, a.linear);
+        assert_eq!(before.translation, a.linear.matmul_vec(&shift.translation).add(&a.translation));
+    }
+
+    /// 🧪 Test 5: Point Leaf Round-Trip (点叶子往返)
+    /// Vector -> 叶子 -> Vector 无损；点叶子的线性部分为 I，作用于向量等价于平移。
+    #[test]
+    fn test_point_leaf_round_trip() {
+        println!("🧪 [Test] Point Leaf Round-Trip...");
+
+        let v = ConceptEmbedder::embed_token(21);
+        let leaf = v.clone().to_affine_leaf();
+        assert!(leaf.linear.is_identity());
+        assert_eq!(leaf.as_point(), &v);
+
+        // 两个点叶子复合 = 两点相加
+        let w = ConceptEmbedder::embed_token(22);
+        let composed = w.clone().to_affine_leaf().compose(&leaf).unwrap();
+        assert!(composed.linear.is_identity());
+        assert_eq!(composed.as_point(), &v.add(&w));
+    }
+
+    /// 🧪 Test 6: Stability Projection (稳定性投影)
+    /// 扩张的元组被裁剪到谱范数恰为上限 (以裁剪所用的种子幂迭代读数为准)，偏置不变；已稳定的元组原样返回。
+    #[test]
+    fn test_clamp_to_stable_hits_bound() {
+        println!("🧪 [Test] AffineTuple::clamp_to_stable...");
+
+        let expansive = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 8).scale(3.0),
+            ConceptEmbedder::embed_token(8),
+        );
+        let clamped = expansive.clamp_to_stable(1.05);
+        let (norm, _) = clamped.linear.estimate_spectral_norm_seeded(SPECTRAL_PROBE_ITERATIONS, SPECTRAL_PROBE_SEED);
+        println!("   > Spectral norm after clamp: {:.5}", norm);
+        assert!((norm - 1.05).abs() < 1e-3, "❌ Clamped norm should sit at the bound: {}", norm);
+        assert_eq!(clamped.translation, expansive.translation);
+
+        // 幂等: 已在上限以内的元组不变
+        assert_eq!(AffineTuple::identity().clamp_to_stable(1.05), AffineTuple::identity());
+    }
+
+    /// 🧪 Test 7: Norm Growth Monitoring (范数增长观测)
+    /// 正交 (符号翻转) 门的复合 ratio ≈ 1；两个扩张方向错开的门复合后 ratio ≈ 0.5，
+    /// 且返回的元组与普通 compose 完全一致；NaN 权重与形状不匹配返回 Err。
+    #[test]
+    fn test_compose_checked_norm_growth_ratio() {
+        use crate::core::algebra::{Float, Matrix, Vector};
+        println!("🧪 [Test] AffineTuple::compose_checked...");
+
+        let diagonal = |entries: &dyn Fn(usize) -> Float| {
+            let mut m = Matrix::identity();
+            for i in 0..MANIFOLD_DIM {
+                m.data[i * MANIFOLD_DIM + i] = entries(i);
+            }
+            m
+        };
+
+        // 1. 正交门: 交替符号的对角矩阵，σ = 1
+        let flip_even = AffineTuple::new(diagonal(&|i| if i % 2 == 0 { -1.0 } else { 1.0 }), ConceptEmbedder::embed_token(1));
+        let flip_odd = AffineTuple::new(diagonal(&|i| if i % 2 == 1 { -1.0 } else { 1.0 }), ConceptEmbedder::embed_token(2));
+        let (composed, ratio) = flip_even.compose_checked(&flip_odd).unwrap();
+        println!("   > Orthogonal ratio: {:.5}", ratio);
+        assert!((ratio - 1.0).abs() < 1e-3, "❌ Orthogonal composition should preserve norm: {}", ratio);
+        assert_eq!(composed, flip_even.compose(&flip_odd).unwrap());
+
+        // 2. 扩张门: 分别沿 e0 / e1 放大 2 倍，复合后 σ = 2 而上界为 4
+        let stretch_x = AffineTuple::new(diagonal(&|i| if i == 0 { 2.0 } else { 1.0 }), ConceptEmbedder::embed_token(3));
+        let stretch_y = AffineTuple::new(diagonal(&|i| if i == 1 { 2.0 } else { 1.0 }), ConceptEmbedder::embed_token(4));
+        let (_, misaligned) = stretch_x.compose_checked(&stretch_y).unwrap();
+        println!("   > Misaligned expansive ratio: {:.5}", misaligned);
+        assert!((misaligned - 0.5).abs() < 1e-2, "❌ Misaligned stretches should grow at half the bound: {}", misaligned);
+
+        // 3. 同方向扩张: 最坏情况累积，ratio ≈ 1
+        let (_, aligned) = stretch_x.compose_checked(&stretch_x).unwrap();
+        println!("   > Aligned expansive ratio: {:.5}", aligned);
+        assert!((aligned - 1.0).abs() < 1e-2, "❌ Aligned stretches should hit the bound: {}", aligned);
+
+        // 4. 被污染的权重 / 形状不匹配: 返回 Err 而不是 NaN 比值或 panic
+        let mut poisoned = stretch_x.clone();
+        poisoned.linear.data[0] = Float::NAN;
+        let err = poisoned.compose_checked(&stretch_y).unwrap_err();
+        assert!(err.contains("Non-finite"), "❌ Unexpected error: {}", err);
+        let narrow = AffineTuple::new(Matrix::new(2, 2, vec![1.0, 0.0, 0.0, 1.0]), Vector::new(vec![0.0; 2]));
+        let err = stretch_x.compose_checked(&narrow).unwrap_err();
+        assert!(err.contains("Shape Mismatch"), "❌ Unexpected error: {}", err);
+    }
+
+    /// 🧪 Test 8: Lipschitz Guard (Lipschitz 硬边界)
+    /// 谱范数裁剪到 1 以内的两个门复合通过检查，结果与 compose_unchecked 一致；
+    /// 两个 Xavier 门 (σ ≈ 2) 的复合被拒绝，错误信息带有实测范数。
+    #[test]
+    fn test_compose_enforces_lipschitz_bound() {
+        println!("🧪 [Test] AffineTuple::compose Lipschitz guard...");
+
+        let a = AffineTuple::random(31).clamp_to_stable(0.95);
+        let b = AffineTuple::random(32).clamp_to_stable(0.95);
+        let stable = a.compose(&b).expect("contractive composition must pass the guard");
+        assert_eq!(stable, a.compose_unchecked(&b));
+
+        let err = AffineTuple::random(31).compose(&AffineTuple::random(32)).unwrap_err();
+        println!("   > {}", err);
+        assert!(err.contains("exceeds Lipschitz bound"), "❌ Unexpected error: {}", err);
+        assert!(err.contains("norm"), "❌ Error should report the measured norm: {}", err);
+    }
+
+    /// 🧪 Test 9: Reversible Logic Step (可逆逻辑步)
+    /// A ∘ A⁻¹ 与 A⁻¹ ∘ A 都近似单位元 (经过带 Lipschitz 检查的 compose)；
+    /// 奇异门的错误由 Matrix::inverse 原样传出。
+    #[test]
+    fn test_inverse_round_trip_and_singular_error() {
+        use crate::core::algebra::Matrix;
+        println!("🧪 [Test] AffineTuple::inverse...");
+
+        let a = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 41),
+            ConceptEmbedder::embed_token(41),
+        );
+        let inv = a.inverse().expect("Xavier gate should be invertible");
+        let identity = AffineTuple::identity();
+        for (name, round_trip) in [("A ∘ A⁻¹", a.compose(&inv)), ("A⁻¹ ∘ A", inv.compose(&a))] {
+            let round_trip = round_trip.unwrap_or_else(|e| panic!("❌ {} failed the guard: {}", name, e));
+            let max_err = round_trip.linear.data.iter().zip(&identity.linear.data)
+                .chain(round_trip.translation.data.iter().zip(&identity.translation.data))
+                .fold(0.0f32, |acc, (x, y)| acc.max((x - y).abs()));
+            println!("   > {}: max |· - I| = {:.3e}", name, max_err);
+            assert!(max_err < 1e-4, "❌ {} is not the identity ({})", name, max_err);
+        }
+
+        let singular = AffineTuple::new(Matrix::identity().scale(0.0), ConceptEmbedder::embed_token(42));
+        let err = singular.inverse().unwrap_err();
+        assert!(err.contains("Cannot invert") || err.contains("singular"), "❌ Unexpected error: {}", err);
+    }
+
+    /// 🧪 Test 10: Guard Without Blind Spot (无盲区的 Lipschitz 检查)
+    /// 只沿 e0 - e1 方向放大 2 倍的门 (主奇异向量与均匀探测向量正交)：
+    /// 均匀探测的幂迭代读数为 1，但 compose 的检查仍必须拒绝它。
+    #[test]
+    fn test_compose_guard_catches_uniform_blind_spot() {
+        use crate::core::algebra::{Float, Matrix};
+        println!("🧪 [Test] Lipschitz guard vs. uniform-probe blind spot...");
+
+        // W = I + u uᵀ，u = (e0 - e1) / √2，σ_max = 2
+        let mut w = Matrix::identity();
+        let half: Float = 0.5;
+        w.data[0] += half;
+        w.data[1] -= half;
+        w.data[MANIFOLD_DIM] -= half;
+        w.data[MANIFOLD_DIM + 1] += half;
+        assert!((w.estimate_spectral_norm_uniform(20) - 1.0).abs() < 1e-3, "uniform probe should sit in the blind spot");
+
+        let gate = AffineTuple::new(w, ConceptEmbedder::embed_token(5));
+        let shift = AffineTuple::new(Matrix::identity().scale(0.999), ConceptEmbedder::embed_token(6));
+        let err = gate.compose(&shift).unwrap_err();
+        println!("   > {}", err);
+        assert!(err.contains("exceeds Lipschitz bound"));
+    }
+}
@@ -0,0 +1,403 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, Matrix, Vector, MANIFOLD_DIM};
+    use crate::core::primes::{CombineMode, ConceptEmbedder, WeightInitializer};
+
+    /// 🧪 Test 1: Pseudo-Inverse (伪逆)
+    /// 对于满秩的 Tall 矩阵，A⁺ · A ≈ I；Wide 矩阵则 A · A⁺ ≈ I。
+    #[test]
+    fn test_pseudo_inverse_recovers_identity() {
+        println!("🧪 [Test] Moore-Penrose Pseudo-Inverse...");
+
+        let tall = WeightInitializer::init_matrix(8, 4, 11);
+        let pinv = tall.pseudo_inverse(1e-6);
+        assert_eq!((pinv.rows, pinv.cols), (4, 8));
+        let left = pinv.matmul(&tall);
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((left.data[i * 4 + j] - expected).abs() < 1e-3, "❌ A⁺A[{}][{}] = {}", i, j, left.data[i * 4 + j]);
+            }
+        }
+
+        let wide = WeightInitializer::init_matrix(3, 6, 12);
+        let wide_pinv = wide.pseudo_inverse(1e-6);
+        assert_eq!((wide_pinv.rows, wide_pinv.cols), (6, 3));
+        let right = wide.matmul(&wide_pinv);
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((right.data[i * 3 + j] - expected).abs() < 1e-3);
+            }
+        }
+    }
+
+    /// 🧪 Test 2: Projection & Rejection (投影与正交补)
+    /// 投影到坐标轴只保留该分量；正交补与方向正交，二者之和还原原向量。
+    #[test]
+    fn test_project_onto_unit_axis() {
+        println!("🧪 [Test] Vector Projection...");
+
+        let mut v = Vector::zeros();
+        v.data[0] = 3.0;
+        v.data[1] = -4.0;
+        v.data[7] = 2.5;
+
+        // 方向不必归一化
+        let mut axis = Vector::zeros();
+        axis.data[1] = 2.0;
+
+        let proj = v.project_onto(&axis);
+        assert!((proj.data[1] + 4.0).abs() < 1e-6);
+        assert!(proj.data.iter().enumerate().all(|(i, x)| i == 1 || *x == 0.0));
+
+        let rej = v.reject_from(&axis);
+        assert!(rej.data[1].abs() < 1e-6);
+        assert_eq!(rej.add(&proj), v);
+
+        // 零方向: 投影为零向量
+        assert_eq!(v.project_onto(&Vector::zeros()), Vector::zeros());
+    }
+
+    /// 🧪 Test 3: Banded Initialization (带状初始化)
+    /// 带外元素必须严格为 0，带内元素非零。
+    #[test]
+    fn test_banded_init_is_zero_outside_band() {
+        println!("🧪 [Test] Banded Weight Init...");
+
+        let (dim, bandwidth) = (16, 2);
+        let w = WeightInitializer::init_banded(dim, bandwidth, 7);
+        for i in 0..dim {
+            for j in 0..dim {
+                let v = w.data[i * dim + j];
+                if i.abs_diff(j) > bandwidth {
+                    assert_eq!(v, 0.0, "❌ Entry ({}, {}) outside band is {}", i, j, v);
+                } else {
+                    assert!(v != 0.0, "❌ Entry ({}, {}) inside band is zero", i, j);
+                }
+            }
+        }
+    }
+
+    /// 🧪 Test 4: Vec Interop (与 Vec<Float> 互转)
+    /// Vector / Matrix 与原始数据往返转换后保持不变；形状不符时 from_parts 返回错误。
+    #[test]
+    fn test_vec_round_trip_conversions() {
+        println!("🧪 [Test] Vec<Float> Round-Trip...");
+
+        let raw: Vec<Float> = (0..16).map(|i| i as Float * 0.5).collect();
+        let v: Vector = raw.clone().into();
+        assert_eq!(v.data, raw);
+        let back: Vec<Float> = v.into();
+        assert_eq!(back, raw);
+
+        let m = Matrix::from_parts(4, 4, raw.clone()).expect("valid shape");
+        assert_eq!((m.rows, m.cols), (4, 4));
+        assert_eq!(m.into_vec(), raw);
+
+        assert!(Matrix::from_parts(3, 5, raw).is_err());
+    }
+
+    /// 🧪 Test 5: Vector Statistics (均值 / 方差 / 标准差)
+    /// 已知向量 [2, 4, 4, 4, 5, 5, 7, 9]：μ = 5, σ² = 4, σ ≈ 2；常数向量的 std 为 √ε > 0。
+    #[test]
+    fn test_vector_statistics() {
+        println!("🧪 [Test] Vector Mean / Variance / Std...");
+
+        let v = Vector::from(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert!((v.mean() - 5.0).abs() < 1e-6, "❌ Mean: {}", v.mean());
+        assert!((v.variance() - 4.0).abs() < 1e-5, "❌ Variance: {}", v.variance());
+        assert!((v.std() - 2.0).abs() < 1e-5, "❌ Std: {}", v.std());
+
+        let flat = Vector::from(vec![3.0; 8]);
+        assert_eq!(flat.variance(), 0.0);
+        assert!(flat.std() > 0.0, "❌ Std of a constant vector must stay positive");
+    }
+
+    /// 🧪 Test 6: N-Gram Embedding (多 Token 组合嵌入)
+    /// Mean 等于各 Token 嵌入的平均，Sum 为 Mean 的 n 倍；WeightedDecay 以最后一个 Token 为主。
+    #[test]
+    fn test_embed_ngram_modes() {
+        println!("🧪 [Test] N-Gram Embedding...");
+
+        let (a, b) = (ConceptEmbedder::embed_token(11), ConceptEmbedder::embed_token(22));
+        let close = |x: &Vector, y: &Vector| x.data.iter().zip(&y.data).all(|(p, q)| (p - q).abs() < 1e-6);
+
+        let mean = ConceptEmbedder::embed_ngram(&[11, 22], CombineMode::Mean);
+        assert!(close(&mean, &a.add(&b).scale(0.5)), "❌ Mean is not the average of the token embeddings");
+
+        let sum = ConceptEmbedder::embed_ngram(&[11, 22], CombineMode::Sum);
+        assert!(close(&sum, &mean.scale(2.0)));
+
+        // γ = 0.5: 权重 (1/3, 2/3)
+        let decayed = ConceptEmbedder::embed_ngram(&[11, 22], CombineMode::WeightedDecay(0.5));
+        assert!(close(&decayed, &a.scale(1.0 / 3.0).add(&b.scale(2.0 / 3.0))));
+
+        assert_eq!(ConceptEmbedder::embed_ngram(&[], CombineMode::Mean), Vector::zeros());
+    }
+
+    /// 🧪 Test 7: NumPy .npy Round-Trip (与 NumPy 互通)
+    /// 头部为 v1.0 格式且按 64 字节对齐，数据读回后逐位不变。
+    #[test]
+    fn test_npy_round_trip() {
+        println!("🧪 [Test] Matrix .npy Export / Import...");
+
+        let m = WeightInitializer::init_matrix(3, 4, 9);
+        let path = std::env::temp_dir().join(format!("htp_gate_{}.npy", std::process::id()));
+        m.save_npy(&path).expect("save");
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0, "❌ Data offset must be 64-byte aligned");
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(bytes.len(), 10 + header_len + 3 * 4 * 4);
+
+        let loaded = Matrix::load_npy(&path).expect("load");
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded, m);
+
+        assert!(Matrix::load_npy(&std::env::temp_dir().join("htp_missing.npy")).is_err());
+    }
+
+    /// 🧪 Test 8: Scalar Reductions (迹与分量和)
+    /// tr(I) = D；小方阵的迹为对角元之和，矩形矩阵 panic；已知向量的分量和。
+    #[test]
+    fn test_trace_and_sum() {
+        println!("🧪 [Test] Matrix Trace & Vector Sum...");
+
+        assert_eq!(Matrix::identity().trace(), MANIFOLD_DIM as Float);
+        let square = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 5.0]);
+        assert_eq!(square.trace(), 6.0);
+        let rect = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert!(std::panic::catch_unwind(|| rect.trace()).is_err(), "❌ Trace of a non-square matrix must panic");
+
+        assert_eq!(Vector::from(vec![1.5, -2.0, 4.0, 0.5]).sum(), 4.0);
+        assert_eq!(Vector::from(Vec::new()).sum(), 0.0);
+    }
+
+    /// 🧪 Test 9: Dominant Singular Vector (主奇异方向)
+    /// 对角矩阵 diag(1, 5, 2, 0.5) 的主方向是第 1 轴，奇异值为 5，且与 estimate_spectral_norm 一致。
+    #[test]
+    fn test_dominant_singular_vector_on_diagonal() {
+        println!("🧪 [Test] Dominant Singular Vector...");
+
+        let diag = [1.0, 5.0, 2.0, 0.5];
+        let mut data = vec![0.0; 16];
+        for (i, d) in diag.iter().enumerate() {
+            data[i * 4 + i] = *d;
+        }
+        let m = Matrix::new(4, 4, data);
+
+        let (sigma, v) = m.dominant_singular_vector(30);
+        assert!((sigma - 5.0).abs() < 1e-4, "❌ Sigma: {}", sigma);
+        assert!((v.norm() - 1.0).abs() < 1e-5);
+        assert!(v.data[1].abs() > 0.9999, "❌ Vector not aligned with the largest axis: {:?}", v.data);
+        assert_eq!(sigma, m.estimate_spectral_norm(30));
+    }
+
+    /// 🧪 Test 10: Fused Add-Scaled (原地融合加法)
+    /// `add_scaled` 与 `add(&other.scale(k))` 逐位一致。
+    #[test]
+    fn test_add_scaled_matches_add_of_scaled() {
+        println!("🧪 [Test] Matrix::add_scaled...");
+
+        let a = WeightInitializer::init_matrix(16, 8, 31);
+        let b = WeightInitializer::init_matrix(16, 8, 32);
+        for factor in [-1e-3, 0.5, 1.0, -2.75] {
+            let mut fused = a.clone();
+            fused.add_scaled(&b, factor);
+            assert_eq!(fused, a.add(&b.scale(factor)), "❌ add_scaled diverged for factor {}", factor);
+        }
+    }
+
+    /// 🧪 Test 11: Condition Number (条件数)
+    /// 单位矩阵 κ = 1；对角元跨越 4 个数量级的矩阵 κ ≈ 1e4；奇异矩阵 κ = ∞。
+    #[test]
+    fn test_condition_number_detects_ill_conditioning() {
+        println!("🧪 [Test] Matrix::condition_number...");
+
+        let identity = Matrix::identity().condition_number();
+        assert!((identity - 1.0).abs() < 1e-3, "❌ Identity should be perfectly conditioned, got {}", identity);
+
+        let n = 8;
+        let mut diag = vec![0.0; n * n];
+        for i in 0..n {
+            diag[i * n + i] = if i == n - 1 { 1e-4 } else { 1.0 };
+        }
+        let kappa = Matrix::new(n, n, diag.clone()).condition_number();
+        println!("   κ(ill-conditioned) = {:.3e}", kappa);
+        assert!((kappa / 1e4 - 1.0).abs() < 1e-2, "❌ Expected κ ≈ 1e4, got {}", kappa);
+
+        diag[(n - 1) * n + (n - 1)] = 0.0;
+        assert_eq!(Matrix::new(n, n, diag).condition_number(), Float::INFINITY);
+        assert_eq!(Matrix::new(2, 3, vec![1.0; 6]).condition_number(), Float::INFINITY);
+    }
+
+    /// 🧪 Test 12: Dot Product / Cosine Similarity (内积与余弦相似度)
+    /// 两个 Token 嵌入的相似度落在 [-1, 1]；自身为 1、取反为 -1；零向量为 0；维度不一致时 dot 返回 Err。
+    #[test]
+    fn test_dot_and_cosine_similarity() {
+        println!("🧪 [Test] Vector::dot / cosine_similarity...");
+
+        let a = ConceptEmbedder::embed_token(1);
+        let b = ConceptEmbedder::embed_token(2);
+        let sim = a.cosine_similarity(&b);
+        println!("   > cos(token 1, token 2) = {:.4}", sim);
+        assert!((-1.0..=1.0).contains(&sim));
+
+        assert!((a.dot(&a).unwrap() - a.norm() * a.norm()).abs() < 1e-5);
+        assert!((a.cosine_similarity(&a) - 1.0).abs() < 1e-5);
+        assert!((a.cosine_similarity(&a.scale(-3.0)) + 1.0).abs() < 1e-5);
+        assert_eq!(a.cosine_similarity(&Vector::zeros()), 0.0);
+
+        let short = Vector::from(vec![1.0, 2.0]);
+        assert_eq!(short.dot(&Vector::from(vec![3.0, 4.0])), Ok(11.0));
+        assert!(short.dot(&a).is_err(), "❌ Mismatched dimensions must not be silently truncated");
+    }
+
+    /// 🧪 Test 13: Transpose (转置)
+    /// 非方阵的形状与元素对调正确；两次转置还原原矩阵；(AB)^T = B^T A^T；与 `transpose_matmul_vec` 一致。
+    #[test]
+    fn test_transpose_round_trip() {
+        println!("🧪 [Test] Matrix::transpose...");
+
+        let m = WeightInitializer::init_matrix(3, 5, 41);
+        let t = m.transpose();
+        assert_eq!((t.rows, t.cols), (5, 3));
+        for i in 0..3 {
+            for j in 0..5 {
+                assert_eq!(t.data[j * 3 + i], m.data[i * 5 + j]);
+            }
+        }
+        assert_eq!(t.transpose(), m, "❌ Double transpose must restore the matrix");
+        assert_eq!(Matrix::identity().transpose(), Matrix::identity());
+
+        let b = WeightInitializer::init_matrix(5, 2, 42);
+        assert_eq!(m.matmul(&b).transpose(), b.transpose().matmul(&m.transpose()));
+
+        let x = Vector::from(vec![0.5, -1.0, 2.0]);
+        assert_eq!(t.matmul_vec(&x), m.transpose_matmul_vec(&x));
+    }
+
+    /// 🧪 Test 14: Exact Inverse at Manifold Scale (流形尺度的精确逆)
+    /// 512x512 的随机逻辑门满足 M · M⁻¹ ≈ I (逐元素误差 < 1e-4)；奇异矩阵与非方阵返回 Err。
+    #[test]
+    fn test_inverse_recovers_identity_at_manifold_dim() {
+        println!("🧪 [Test] Matrix::inverse ({}x{})...", MANIFOLD_DIM, MANIFOLD_DIM);
+
+        let m = WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 51);
+        let inv = m.inverse().expect("random gate should be invertible");
+        let product = m.matmul(&inv);
+        let max_err = product.data.iter()
+            .zip(Matrix::identity().data.iter())
+            .fold(0.0 as Float, |acc, (a, b)| acc.max((a - b).abs()));
+        println!("   > max |M·M⁻¹ - I| = {:.3e}", max_err);
+        assert!(max_err < 1e-4, "❌ M · M⁻¹ deviates from identity by {}", max_err);
+
+        // 秩亏: 两行相同
+        let mut singular = WeightInitializer::init_matrix(8, 8, 52);
+        let first_row = singular.data[0..8].to_vec();
+        singular.data[8..16].copy_from_slice(&first_row);
+        assert!(singular.inverse().is_err(), "❌ Rank-deficient matrix must be rejected");
+
+        assert!(Matrix::new(2, 3, vec![1.0; 6]).inverse().is_err());
+    }
+
+    /// 🧪 Test 15: Determinant (行列式)
+    /// 已知 3x3 矩阵的行列式；交换两行变号；秩亏为 0；det(AB) = det(A)·det(B)；det(I) = 1。
+    #[test]
+    fn test_determinant_via_lu() {
+        println!("🧪 [Test] Matrix::determinant...");
+
+        // 需要选主元 (a00 = 0) 的 3x3 矩阵，按第一行展开: 0·(-18) - 2·2 + 1·14 = 10
+        let a = Matrix::new(3, 3, vec![
+            0.0, 2.0, 1.0,
+            3.0, 1.0, 4.0,
+            1.0, 5.0, 2.0,
+        ]);
+        let det_a = a.determinant();
+        println!("   det(A) = {:.5}", det_a);
+        assert!((det_a - 10.0).abs() < 1e-5, "❌ Expected det = 10, got {}", det_a);
+
+        let swapped = Matrix::new(3, 3, vec![
+            3.0, 1.0, 4.0,
+            0.0, 2.0, 1.0,
+            1.0, 5.0, 2.0,
+        ]);
+        assert!((swapped.determinant() + det_a).abs() < 1e-5, "❌ Row swap must flip the sign");
+
+        let singular = Matrix::new(3, 3, vec![
+            1.0, 2.0, 3.0,
+            2.0, 4.0, 6.0,
+            1.0, 0.0, 1.0,
+        ]);
+        assert_eq!(singular.determinant(), 0.0);
+
+        let b = WeightInitializer::init_matrix(3, 3, 61);
+        let product = a.matmul(&b).determinant();
+        assert!((product - det_a * b.determinant()).abs() < 1e-4, "❌ det(AB) != det(A)·det(B)");
+
+        assert!((Matrix::identity().determinant() - 1.0).abs() < 1e-6);
+    }
+
+    /// 🧪 Test 16: Transpose-Aware GEMM (免转置乘法)
+    /// 四种转置组合都与显式 `transpose()` 后的 `matmul` 逐位一致；形状按 op(A)·op(B) 推导。
+    #[test]
+    fn test_gemm_matches_materialized_transpose() {
+        println!("🧪 [Test] Matrix::gemm...");
+
+        let a = WeightInitializer::init_matrix(4, 3, 71);
+        let b = WeightInitializer::init_matrix(4, 5, 72);
+        let c = WeightInitializer::init_matrix(5, 3, 73);
+
+        // A^T B: (3x4)(4x5)
+        let atb = a.gemm(true, &b, false);
+        assert_eq!((atb.rows, atb.cols), (3, 5));
+        assert_eq!(atb, a.transpose().matmul(&b));
+
+        // A C^T: (4x3)(3x5)
+        assert_eq!(a.gemm(false, &c, true), a.matmul(&c.transpose()));
+        // A^T D^T: (3x4)(4x5)
+        let d = WeightInitializer::init_matrix(5, 4, 74);
+        assert_eq!(a.gemm(true, &d, true), a.transpose().matmul(&d.transpose()));
+        // 无转置退化为 matmul
+        assert_eq!(b.gemm(false, &c, false), b.matmul(&c));
+    }
+
+    /// 🧪 Test 17: Seeded Power Iteration (随机起点幂迭代)
+    /// A = I + 3·u uᵀ 且 u ⟂ 均匀向量：`estimate_spectral_norm_uniform` 停在 σ = 1，随机起点找到真正的 σ_max = 4，
+    /// 并在迭代上限之前收敛；默认的 `estimate_spectral_norm` 同样不落入盲区；同一种子结果可复现。
+    #[test]
+    fn test_seeded_spectral_norm_escapes_uniform_blind_spot() {
+        println!("🧪 [Test] Matrix::estimate_spectral_norm_seeded...");
+
+        let n = 8;
+        let mut u = vec![0.0 as Float; n];
+        u[0] = (0.5 as Float).sqrt();
+        u[1] = -(0.5 as Float).sqrt();
+        let mut data = vec![0.0; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                data[i * n + j] = 3.0 * u[i] * u[j] + if i == j { 1.0 } else { 0.0 };
+            }
+        }
+        let a = Matrix::new(n, n, data);
+
+        let blind = a.estimate_spectral_norm_uniform(50);
+        let (sigma, used) = a.estimate_spectral_norm_seeded(200, 7);
+        println!("   > uniform probe: {:.5}, seeded: {:.5} after {} iterations", blind, sigma, used);
+        assert!((blind - 1.0).abs() < 1e-4, "❌ Uniform probe should be stuck on the blind spot");
+        assert!((sigma - 4.0).abs() < 1e-3, "❌ Seeded probe should find σ_max = 4, got {}", sigma);
+        assert!(used < 200, "❌ Power iteration should terminate early once converged");
+        let default = a.estimate_spectral_norm(200);
+        assert!((default - 4.0).abs() < 1e-3, "❌ Default estimator must use the seeded probe, got {}", default);
+
+        assert_eq!(a.estimate_spectral_norm_seeded(200, 7), (sigma, used));
+        assert_eq!(Matrix::new(2, 2, vec![0.0; 4]).estimate_spectral_norm_seeded(10, 1).0, 0.0);
+    }
+}
@@ -0,0 +1,425 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+    use crate::net::discovery::{DiscoveryService, GossipMergePolicy, PeerInfo, RoutingStrategy, SplitBrainDetected};
+    use crate::net::node::{HTPNode, NodeRole};
+    use crate::net::wire::PacketType;
+
+    /// 🧪 Test 1: Topology Change Notification (拓扑变化推送)
+    /// PS 掉线后，订阅者应立即收到新的拓扑 (Worker 失去 Uplink)；已知节点的角色改变同样触发推送。
+    #[tokio::test]
+    async fn test_topology_change_on_peer_removal() {
+        println!("🧪 [Test] Topology Change Event...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        ).with_peer_ttl(Duration::from_millis(50));
+        let mut events = discovery.topology_changed();
+
+        // 1. PS 加入 -> Worker 获得 Parent
+        discovery.add_seed_peer("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        assert!(events.has_changed().unwrap(), "❌ Join did not fire a topology event");
+        let parent = events.borrow_and_update().parent.clone();
+        assert_eq!(parent.map(|p| p.id), Some("ps-00".to_string()));
+
+        // 2. PS 超时 -> 被 GC 移除 -> Worker 成为孤儿
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        discovery.purge_dead_peers().await;
+        assert!(events.has_changed().unwrap(), "❌ Peer removal did not fire a topology event");
+        assert!(events.borrow_and_update().parent.is_none());
+
+        // 3. 无变化的 GC 不应触发事件
+        discovery.purge_dead_peers().await;
+        assert!(!events.has_changed().unwrap());
+
+        // 4. 已知节点的角色改变 (Worker -> PS) 触发事件；角色不变的心跳不触发
+        discovery.register_heartbeat("node-02".to_string(), "127.0.0.1:5002".to_string(), NodeRole::Worker).await;
+        events.borrow_and_update();
+        discovery.register_heartbeat("node-02".to_string(), "127.0.0.1:5002".to_string(), NodeRole::Worker).await;
+        assert!(!events.has_changed().unwrap(), "❌ Plain heartbeat should not fire a topology event");
+        discovery.register_heartbeat("node-02".to_string(), "127.0.0.1:5002".to_string(), NodeRole::ParameterServer).await;
+        assert!(events.has_changed().unwrap(), "❌ Role change did not fire a topology event");
+        assert_eq!(events.borrow_and_update().parent.as_ref().map(|p| p.id.as_str()), Some("node-02"));
+    }
+
+    /// 🧪 Test 2: Reliability-Weighted Selection (避开不稳定节点)
+    /// 反复超时又重新加入的 PS 应被降权，Worker 选择稳定的 PS 作为 Parent。
+    #[tokio::test]
+    async fn test_flaky_peer_is_deprioritized() {
+        println!("🧪 [Test] Flaky Peer Deprioritization...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        ).with_peer_ttl(Duration::from_millis(20));
+
+        // 1. ps-flaky 反复掉线重连，ps-stable 持续心跳
+        for _ in 0..3 {
+            discovery.add_seed_peer("ps-flaky".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            discovery.register_heartbeat("ps-stable".to_string(), "127.0.0.1:5002".to_string(), NodeRole::ParameterServer).await;
+            discovery.purge_dead_peers().await;
+        }
+        discovery.add_seed_peer("ps-flaky".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+
+        // 2. 评分：掉线三次 -> 0.5^3
+        let flaky = discovery.get_peer("ps-flaky").await.expect("flaky peer rejoined");
+        let stable = discovery.get_peer("ps-stable").await.expect("stable peer alive");
+        assert!(flaky.reliability < stable.reliability);
+        assert!((flaky.reliability - 0.125).abs() < 1e-9);
+
+        // 3. Parent 选择避开不稳定的 PS
+        let topology = discovery.build_topology().await;
+        assert_eq!(topology.parent.map(|p| p.id), Some("ps-stable".to_string()));
+    }
+
+    /// 🧪 Test 3: Inference Routing (推理路由)
+    /// 三个 Worker 轮询必须依次轮转；LeastLoaded / LowestLatency 选出对应的最优者。
+    #[tokio::test]
+    async fn test_select_worker_round_robin() {
+        println!("🧪 [Test] Worker Selection (Round-Robin)...");
+
+        let discovery = DiscoveryService::new(
+            "gateway".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:4000".to_string(),
+        );
+        assert!(discovery.select_worker(RoutingStrategy::RoundRobin).await.is_none());
+
+        discovery.add_seed_peer("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        for i in 1..=3 {
+            discovery.add_seed_peer(format!("worker-0{}", i), format!("127.0.0.1:500{}", i), NodeRole::Worker).await;
+        }
+
+        // 1. Round-Robin: PS 不参与，Worker 按 ID 轮转
+        let mut picked = Vec::new();
+        for _ in 0..6 {
+            picked.push(discovery.select_worker(RoutingStrategy::RoundRobin).await.unwrap().id);
+        }
+        assert_eq!(picked, ["worker-01", "worker-02", "worker-03", "worker-01", "worker-02", "worker-03"]);
+
+        // 2. LeastLoaded & LowestLatency
+        discovery.report_metrics("worker-01", 5.0, Some(Duration::from_millis(3))).await;
+        discovery.report_metrics("worker-02", 1.0, None).await;
+        discovery.report_metrics("worker-03", 2.0, Some(Duration::from_millis(9))).await;
+
+        let least = discovery.select_worker(RoutingStrategy::LeastLoaded).await.unwrap();
+        assert_eq!(least.id, "worker-02");
+        let fastest = discovery.select_worker(RoutingStrategy::LowestLatency).await.unwrap();
+        assert_eq!(fastest.id, "worker-01");
+    }
+
+    /// 🧪 Test 4: Graceful Leave (优雅下线)
+    /// 收到 Leave 后立即移除节点并推送新拓扑，可靠度不受惩罚；未知节点的 Leave 不触发事件。
+    #[tokio::test]
+    async fn test_leave_removes_peer_immediately() {
+        println!("🧪 [Test] Graceful Leave...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        );
+        discovery.add_seed_peer("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        let mut events = discovery.topology_changed();
+        assert!(events.borrow_and_update().parent.is_some());
+
+        // 1. Leave: 无需等待心跳 TTL
+        assert!(discovery.handle_leave("ps-00").await);
+        assert!(discovery.get_peer("ps-00").await.is_none());
+        assert!(events.has_changed().unwrap(), "❌ Leave did not fire a topology event");
+        assert!(events.borrow_and_update().parent.is_none());
+
+        // 2. 重复 / 未知的 Leave 被忽略
+        assert!(!discovery.handle_leave("ps-00").await);
+        assert!(!events.has_changed().unwrap());
+
+        // 3. 重新加入时可靠度未被衰减
+        discovery.add_seed_peer("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        assert_eq!(discovery.get_peer("ps-00").await.unwrap().reliability, 1.0);
+    }
+
+    /// 🧪 Test 5: Split-Brain Detection (分区愈合检测)
+    /// 两个 PS 同时加入不算脑裂；ps-b 超时掉线 (分区) 后经 Gossip 重新出现，而 ps-a 一直存活，
+    /// 必须广播 SplitBrainDetected；主动下线后重新加入不触发。
+    #[tokio::test]
+    async fn test_split_brain_on_partition_heal() {
+        println!("🧪 [Test] Split-Brain Detection...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        ).with_peer_ttl(Duration::from_millis(50));
+        let mut events = discovery.split_brain_events();
+
+        // 1. 正常启动: 两个 PS
+        discovery.add_seed_peer("ps-a".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        discovery.add_seed_peer("ps-b".to_string(), "127.0.0.1:5002".to_string(), NodeRole::ParameterServer).await;
+        assert!(events.try_recv().is_err(), "❌ Fresh PS join is not a split brain");
+
+        // 2. 分区: ps-b 停止心跳被 GC，ps-a 保持存活
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        discovery.register_heartbeat("ps-a".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        discovery.purge_dead_peers().await;
+        assert!(discovery.get_peer("ps-b").await.is_none());
+
+        // 3. 愈合: ps-b 经 Gossip 重新出现
+        discovery.handle_gossip(vec![PeerInfo {
+            id: "ps-b".to_string(),
+            address: "127.0.0.1:5002".to_string(),
+            role: NodeRole::ParameterServer,
+            last_seen: SystemTime::now(),
+            reliability: 1.0,
+            load: 0.0,
+            latency: None,
+            layers: None,
+            epoch: None,
+        }]).await;
+        assert_eq!(events.try_recv().unwrap(), SplitBrainDetected {
+            known_ps: "ps-a".to_string(),
+            rejoined_ps: "ps-b".to_string(),
+        });
+        assert!(events.try_recv().is_err());
+
+        // 4. 主动下线后重新加入不是分区
+        assert!(discovery.handle_leave("ps-b").await);
+        discovery.add_seed_peer("ps-b".to_string(), "127.0.0.1:5002".to_string(), NodeRole::ParameterServer).await;
+        assert!(events.try_recv().is_err(), "❌ Graceful rejoin must not report a split brain");
+    }
+
+    /// 🧪 Test 6: Gossip Merge Policies (八卦冲突合并策略)
+    /// 本地已知 worker-02 (地址 A)，八卦带来地址 B 与层区间的冲突记录：
+    /// Union 只更新层区间；PreferNewerClock 采用更新的记录、忽略陈旧记录；PreferLocal 完全保留本地。
+    #[tokio::test]
+    async fn test_gossip_merge_policies() {
+        println!("🧪 [Test] Gossip Merge Policies...");
+
+        let record = |last_seen: SystemTime| PeerInfo {
+            id: "worker-02".to_string(),
+            address: "10.0.0.2:6000".to_string(),
+            role: NodeRole::Worker,
+            last_seen,
+            reliability: 1.0,
+            load: 0.0,
+            latency: None,
+            layers: Some(0..4),
+            epoch: None,
+        };
+        let merged = |policy: GossipMergePolicy, incoming: PeerInfo| async move {
+            let discovery = DiscoveryService::new(
+                "worker-01".to_string(),
+                NodeRole::Worker,
+                "127.0.0.1:5001".to_string(),
+            ).with_merge_policy(policy);
+            discovery.add_seed_peer("worker-02".to_string(), "127.0.0.1:5002".to_string(), NodeRole::Worker).await;
+            discovery.handle_gossip(vec![incoming]).await;
+            discovery.get_peer("worker-02").await.unwrap()
+        };
+        let newer = || record(SystemTime::now() + Duration::from_secs(5));
+        let stale = || record(SystemTime::now() - Duration::from_secs(3600));
+
+        // 1. Union (默认): 地址保留本地，层区间随八卦更新
+        let union = merged(GossipMergePolicy::Union, stale()).await;
+        assert_eq!(union.address, "127.0.0.1:5002");
+        assert_eq!(union.layers, Some(0..4));
+
+        // 2. PreferNewerClock: 更新的记录整体胜出，陈旧记录被忽略
+        let fresh = merged(GossipMergePolicy::PreferNewerClock, newer()).await;
+        assert_eq!(fresh.address, "10.0.0.2:6000");
+        assert_eq!(fresh.layers, Some(0..4));
+        let ignored = merged(GossipMergePolicy::PreferNewerClock, stale()).await;
+        assert_eq!(ignored.address, "127.0.0.1:5002");
+        assert_eq!(ignored.layers, None);
+
+        // 3. PreferLocal: 即使记录更新也不覆盖本地
+        let local = merged(GossipMergePolicy::PreferLocal, newer()).await;
+        assert_eq!(local.address, "127.0.0.1:5002");
+        assert_eq!(local.layers, None);
+    }
+
+    /// 🧪 Test 7: Multi-Seed Bootstrap (多种子引导)
+    /// 所有种子都进入路由表并按原顺序返回；任一条目格式错误时整批拒绝。
+    #[tokio::test]
+    async fn test_multiple_seeds_all_registered() {
+        println!("🧪 [Test] Multi-Seed Bootstrap...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        );
+        let seeds = vec![
+            "ps-00@127.0.0.1:5000".to_string(),
+            " ps-01@127.0.0.1:5100".to_string(),
+            "ps-02@127.0.0.1:5200".to_string(),
+        ];
+        let parsed = discovery.add_seed_peers(&seeds, NodeRole::ParameterServer).await.unwrap();
+        assert_eq!(parsed.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["ps-00", "ps-01", "ps-02"]);
+        for (id, addr) in &parsed {
+            let peer = discovery.get_peer(id).await.expect("❌ Seed missing from peer table");
+            assert_eq!(&peer.address, addr);
+            assert_eq!(peer.role, NodeRole::ParameterServer);
+        }
+
+        // 格式错误: 不注册任何种子
+        let fresh = DiscoveryService::new("worker-02".to_string(), NodeRole::Worker, "127.0.0.1:5002".to_string());
+        let bad = vec!["ps-00@127.0.0.1:5000".to_string(), "127.0.0.1:5100".to_string()];
+        assert!(fresh.add_seed_peers(&bad, NodeRole::ParameterServer).await.is_err());
+        assert!(fresh.get_peer("ps-00").await.is_none());
+    }
+
+    /// 🧪 Test 8: Lightweight Heartbeat (轻量心跳)
+    /// 心跳刷新 last_seen 并记录纪元与负载，但不携带路由表：不会引入新节点，也不触发拓扑事件；
+    /// 心跳包的体积与发送方路由表的大小无关。
+    #[tokio::test]
+    async fn test_heartbeat_refreshes_liveness_only() {
+        println!("🧪 [Test] Lightweight Heartbeat...");
+
+        let discovery = DiscoveryService::new(
+            "worker-01".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:5001".to_string(),
+        );
+        discovery.add_seed_peer("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        let before = discovery.get_peer("ps-00").await.unwrap();
+        assert_eq!(before.epoch, None);
+        let mut events = discovery.topology_changed();
+        events.borrow_and_update();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let PacketType::Heartbeat { node_id, epoch, load } = ps.heartbeat_packet(3.0) else {
+            panic!("❌ heartbeat_packet must build a Heartbeat");
+        };
+        assert!(discovery.handle_heartbeat(&node_id, epoch, load).await);
+
+        let after = discovery.get_peer("ps-00").await.unwrap();
+        assert!(after.last_seen > before.last_seen, "❌ Heartbeat did not refresh last_seen");
+        assert_eq!((after.epoch, after.load), (Some(ps.epoch()), 3.0));
+        assert!(!events.has_changed().unwrap(), "❌ Heartbeat must not touch the topology");
+
+        // 未知节点的心跳不会把它加入路由表 (心跳不携带地址)
+        assert!(!discovery.handle_heartbeat("ghost", 1, 0.0).await);
+        assert!(discovery.get_peer("ghost").await.is_none());
+
+        // 体积固定且很小: 与负载数值、发送方知道多少邻居都无关
+        let size = ps.heartbeat_packet(0.0).wire_size();
+        assert_eq!(size, ps.heartbeat_packet(123.0).wire_size());
+        println!("   > Heartbeat: {} bytes", size);
+        assert!(size < 64);
+    }
+
+    /// 🧪 Test 9: Bounded Heartbeat Fan-out (有界心跳目标)
+    /// 每轮心跳至多发给 MAX_HEARTBEAT_TARGETS 个节点，Parent 每轮都在其中；
+    /// 其余节点轮流接收，TTL (PEER_TTL_MS) 内的若干轮合起来覆盖整个路由表。
+    #[tokio::test]
+    async fn test_heartbeat_targets_are_bounded_and_rotate() {
+        use std::collections::HashSet;
+        use crate::net::discovery::{HEARTBEAT_INTERVAL_MS, MAX_HEARTBEAT_TARGETS, PEER_TTL_MS};
+
+        println!("🧪 [Test] Bounded Heartbeat Targets...");
+
+        let discovery = DiscoveryService::new("worker-00".to_string(), NodeRole::Worker, "127.0.0.1:6000".to_string());
+        discovery.register_heartbeat("ps-00".to_string(), "127.0.0.1:5000".to_string(), NodeRole::ParameterServer).await;
+        for i in 1..=20 {
+            discovery.register_heartbeat(format!("worker-{:02}", i), format!("127.0.0.1:{}", 6000 + i), NodeRole::Worker).await;
+        }
+        let parent = discovery.build_topology().await.parent.expect("worker should hang under the PS").address;
+
+        let rounds = (PEER_TTL_MS / HEARTBEAT_INTERVAL_MS) as usize;
+        let mut covered = HashSet::new();
+        for _ in 0..rounds {
+            let targets = discovery.heartbeat_targets().await;
+            assert!(targets.len() <= MAX_HEARTBEAT_TARGETS, "❌ Heartbeat fan-out is unbounded: {}", targets.len());
+            assert!(targets.contains(&parent), "❌ Parent must receive every heartbeat");
+            covered.extend(targets);
+        }
+        println!("   > {} rounds covered {} / 21 peers", rounds, covered.len());
+        assert_eq!(covered.len(), 21, "❌ Some peers never receive a heartbeat within the TTL");
+    }
+
+    /// 🧪 Test 10: Bounded Peer Table (有界路由表)
+    /// 八卦带来的节点超过 max_peers 时，最久未见的 Worker 被淘汰；PS 即使最旧也永远保留。
+    #[tokio::test]
+    async fn test_max_peers_evicts_least_recently_seen() {
+        println!("🧪 [Test] Peer Capacity Eviction...");
+
+        let discovery = DiscoveryService::new(
+            "worker-00".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:6000".to_string(),
+        ).with_merge_policy(GossipMergePolicy::PreferNewerClock).with_max_peers(4);
+
+        // 1. 2 个 PS (最旧的时钟) + 6 个 Worker (worker-1 最新, worker-6 最旧)
+        let now = SystemTime::now();
+        let peer = |id: &str, role: NodeRole, age_secs: u64| PeerInfo {
+            id: id.to_string(),
+            address: format!("10.0.0.{}:6000", age_secs),
+            role,
+            last_seen: now - Duration::from_secs(age_secs),
+            reliability: 1.0,
+            load: 0.0,
+            latency: None,
+            layers: None,
+            epoch: None,
+        };
+        let mut incoming = vec![
+            peer("ps-a", NodeRole::ParameterServer, 50),
+            peer("ps-b", NodeRole::ParameterServer, 51),
+        ];
+        incoming.extend((1..=6).map(|k| peer(&format!("worker-{}", k), NodeRole::Worker, k)));
+        discovery.handle_gossip(incoming).await;
+
+        let mut ids: Vec<String> = discovery.generate_gossip().await.1.into_iter().map(|p| p.id).collect();
+        ids.sort();
+        println!("   > Retained: {:?}", ids);
+        assert_eq!(ids, vec!["ps-a", "ps-b", "worker-1", "worker-2"]);
+
+        // 2. 直接心跳引入新节点: 表仍有界，最旧的 Worker 让位
+        discovery.register_heartbeat("worker-7".to_string(), "10.0.0.7:6000".to_string(), NodeRole::Worker).await;
+        assert_eq!(discovery.generate_gossip().await.1.len(), 4);
+        assert!(discovery.get_peer("worker-7").await.is_some());
+        assert!(discovery.get_peer("worker-2").await.is_none(), "❌ Least-recently-seen worker should be evicted");
+        assert!(discovery.get_peer("ps-a").await.is_some() && discovery.get_peer("ps-b").await.is_some());
+    }
+
+    /// 🧪 Test 11: Gossip Packet Round Trip (八卦包往返)
+    /// 发送方的 PeerDiscovery 包附带自己的名片 (含层区间)；接收方把名片转回 PeerInfo 合并后，
+    /// 既认识了发送方，也能按层区间把流水线请求路由给它。
+    #[tokio::test]
+    async fn test_gossip_packet_introduces_sender() {
+        println!("🧪 [Test] PeerDiscovery Round Trip...");
+
+        let sender = DiscoveryService::new(
+            "worker-b".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:7002".to_string(),
+        ).with_local_layers(4..8);
+        sender.register_heartbeat("ps-01".to_string(), "127.0.0.1:7000".to_string(), NodeRole::ParameterServer).await;
+
+        let (_, view) = sender.generate_gossip().await;
+        let bytes = sender.gossip_packet(&view).to_bytes().unwrap();
+        let Ok(PacketType::PeerDiscovery { sender_id, peers }) = PacketType::from_bytes(&bytes) else {
+            panic!("❌ Gossip packet did not decode as PeerDiscovery");
+        };
+        assert_eq!(sender_id, sender.local_id());
+        assert_eq!(peers.len(), 2, "❌ Gossip should carry the known peer plus the sender itself");
+
+        let receiver = DiscoveryService::new(
+            "worker-a".to_string(),
+            NodeRole::Worker,
+            "127.0.0.1:7001".to_string(),
+        );
+        receiver.handle_gossip(peers.into_iter().map(PeerInfo::from).collect()).await;
+
+        let owner = receiver.layer_owner(5).await.expect("❌ Sender's layer range was not gossiped");
+        assert_eq!((owner.id.as_str(), owner.address.as_str()), ("worker-b", "127.0.0.1:7002"));
+        assert_eq!(receiver.build_topology().await.parent.map(|p| p.id), Some("ps-01".to_string()));
+    }
+}
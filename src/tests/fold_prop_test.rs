@@ -0,0 +1,65 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::core::algebra::{Float, Matrix, Vector};
+    use crate::core::affine::AffineTuple;
+    use crate::topology::folding::HyperFolder;
+
+    /// 📏 属性测试使用的小维度 (D³ 的 matmul 在数百个用例下仍然很快)
+    const DIM: usize = 8;
+
+    /// 🎲 Strategy: 随机仿射元组
+    /// 元素取自 [-0.3, 0.3]，使 ||W||_F <= 2.4，长时间线复合后既不溢出也不过早塌缩为 0。
+    fn affine_tuple() -> impl Strategy<Value = AffineTuple> {
+        (
+            prop::collection::vec(-0.3 as Float..0.3, DIM * DIM),
+            prop::collection::vec(-1.0 as Float..1.0, DIM),
+        )
+            .prop_map(|(w, b)| AffineTuple::new(Matrix::new(DIM, DIM, w), Vector { data: b }))
+    }
+
+    /// 🎲 Strategy: 长度为 1..=max_len 的随机时间线
+    fn timeline(max_len: usize) -> impl Strategy<Value = Vec<AffineTuple>> {
+        prop::collection::vec(affine_tuple(), 1..=max_len)
+    }
+
+    /// ⚖️ 逐元素比较两个仿射元组 (相对容差：以两者中的最大幅值为尺度)
+    fn assert_affine_close(a: &AffineTuple, b: &AffineTuple, rel_tol: Float) -> Result<(), TestCaseError> {
+        let pairs = || a.linear.data.iter().zip(&b.linear.data)
+            .chain(a.translation.data.iter().zip(&b.translation.data));
+        let scale = pairs().map(|(x, y)| x.abs().max(y.abs())).fold(1.0, Float::max);
+        let diff = pairs().map(|(x, y)| (x - y).abs()).fold(0.0, Float::max);
+        prop_assert!(diff <= rel_tol * scale, "max |a - b| = {:e} exceeds {:e}", diff, rel_tol * scale);
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        /// 🧪 Property 1: Parallel Fold == Serial Fold (并行折叠等价于逐步复合)
+        /// Rayon 的树形归约必须与从左到右的串行 compose 给出相同的结论。
+        #[test]
+        fn prop_fold_timeline_matches_serial_compose(steps in timeline(12)) {
+            let folded = HyperFolder::fold_timeline(&steps).expect("non-empty timeline");
+
+            let serial = steps[1..].iter().fold(steps[0].clone(), |acc, next| {
+                next.compose(&acc).expect("compose")
+            });
+
+            assert_affine_close(&folded, &serial, 1e-4)?;
+        }
+
+        /// 🧪 Property 2: Compose Associativity (复合结合律)
+        /// (A∘B)∘C ≈ A∘(B∘C)，这是一切并行时间折叠的数学前提。
+        #[test]
+        fn prop_compose_is_associative(a in affine_tuple(), b in affine_tuple(), c in affine_tuple()) {
+            let left = a.compose(&b).expect("compose").compose(&c).expect("compose");
+            let right = a.compose(&b.compose(&c).expect("compose")).expect("compose");
+
+            assert_affine_close(&left, &right, 1e-5)?;
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Vector, Matrix, MANIFOLD_DIM};
+    use crate::core::affine::AffineTuple;
+    use crate::core::neuron::HTPNeuron;
+    use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+
+    /// 🧪 Test 1: Output Cache (推理缓存)
+    /// 相同输入只计算一次；权重变化后缓存必须失效。
+    #[test]
+    fn test_output_cache_hit_and_invalidation() {
+        println!("🧪 [Test] Neuron Output Cache...");
+
+        let w = WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 7);
+        let b = WeightInitializer::init_bias(MANIFOLD_DIM);
+        let mut neuron = HTPNeuron::with_weights(w, b).with_cache(8);
+        let input = ConceptEmbedder::embed_token(42);
+
+        // 1. Miss -> Hit
+        let first = neuron.absorb(&input);
+        let second = neuron.absorb(&input);
+        assert_eq!(first, second);
+        assert_eq!(neuron.cache_stats(), Some((1, 1)), "❌ Expected exactly one computation");
+
+        // 2. 权重变化 -> 缓存失效 -> 重新计算出新结果
+        let shifted = AffineTuple::new(Matrix::identity(), Vector::new(vec![0.5; MANIFOLD_DIM]));
+        neuron.set_logic_gate(shifted);
+        let third = neuron.absorb(&input);
+        assert_eq!(neuron.cache_stats(), Some((1, 2)));
+        assert_ne!(third, first, "❌ Stale cached output served after weight change");
+    }
+
+    /// 🧪 Test 2: Cache Collisions & LRU Order (缓存碰撞与淘汰顺序)
+    /// 同一个键下的不同输入 (哈希碰撞) 不能互相命中；命中会刷新 LRU 位置，满员时淘汰最久未使用的条目。
+    #[test]
+    fn test_output_cache_rejects_collisions_and_evicts_lru() {
+        use crate::core::neuron::OutputCache;
+
+        println!("🧪 [Test] Output Cache Collisions & LRU...");
+
+        let a = ConceptEmbedder::embed_token(1);
+        let b = ConceptEmbedder::embed_token(2);
+        let c = ConceptEmbedder::embed_token(3);
+        let out = |x: &Vector| x.scale(2.0);
+
+        // 1. 强制碰撞: b 借用 a 的键，只能未命中，并覆盖该槽位
+        let cache = OutputCache::new(2);
+        let key_a = OutputCache::key_of(&a);
+        cache.store(key_a, 0, &a, out(&a));
+        assert_eq!(cache.lookup(key_a, 0, &b), None, "❌ Colliding input served another input's output");
+        cache.store(key_a, 0, &b, out(&b));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.lookup(key_a, 0, &a), None);
+        assert_eq!(cache.lookup(key_a, 0, &b), Some(out(&b)));
+
+        // 2. LRU: 存 a、b，命中 a，再存 c → 淘汰 b
+        let cache = OutputCache::new(2);
+        let key = OutputCache::key_of;
+        cache.store(key(&a), 0, &a, out(&a));
+        cache.store(key(&b), 0, &b, out(&b));
+        assert!(cache.lookup(key(&a), 0, &a).is_some());
+        cache.store(key(&c), 0, &c, out(&c));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup(key(&b), 0, &b).is_none(), "❌ Recently used entry was evicted instead of the LRU one");
+        assert_eq!(cache.lookup(key(&a), 0, &a), Some(out(&a)));
+        assert_eq!(cache.lookup(key(&c), 0, &c), Some(out(&c)));
+
+        // 3. 版本变化: 旧条目整体作废
+        assert!(cache.lookup(key(&a), 1, &a).is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats(), (3, 2));
+    }
+
+    /// 🧪 Test 3: Perturb & Undo (扰动与还原)
+    /// 扰动必须确定性地改变权重，撤销后逐位还原；扰动期间的缓存不会泄漏到还原之后。
+    #[test]
+    fn test_perturb_then_undo_restores_weights() {
+        println!("🧪 [Test] Neuron Perturbation...");
+
+        let mut neuron = HTPNeuron::with_weights(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 3),
+            ConceptEmbedder::embed_token(9),
+        ).with_cache(4);
+        let original = neuron.logic_gate().clone();
+        let input = ConceptEmbedder::embed_token(1);
+        let clean_output = neuron.absorb(&input);
+
+        // 1. 扰动: 同一 seed 结果一致，幅度受 scale 约束
+        neuron.perturb(1e-2, 42);
+        assert!(neuron.is_perturbed());
+        assert_ne!(neuron.logic_gate(), &original);
+        let max_delta = neuron.logic_gate().linear.data.iter().zip(&original.linear.data)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(max_delta <= 1e-2 + 1e-6, "❌ Perturbation exceeds scale ({})", max_delta);
+
+        let mut twin = HTPNeuron::with_weights(original.linear.clone(), original.translation.clone());
+        twin.perturb(1e-2, 42);
+        assert_eq!(twin.logic_gate(), neuron.logic_gate(), "❌ Perturbation is not deterministic");
+        assert_ne!(neuron.absorb(&input), clean_output);
+
+        // 2. 二次扰动 + 撤销: 回到最初的权重
+        neuron.perturb(1e-2, 7);
+        assert!(neuron.undo_perturb());
+        assert_eq!(neuron.logic_gate(), &original, "❌ Undo did not restore the original weights");
+        assert_eq!(neuron.absorb(&input), clean_output);
+        assert!(!neuron.undo_perturb());
+    }
+
+    /// 🧪 Test 3: Integrity Covers the Logic Gate (完整性检查覆盖逻辑门)
+    /// 状态正常但权重矩阵 / 偏置被注入 NaN / Inf 时，verify_integrity 必须报错。
+    #[test]
+    fn test_verify_integrity_detects_corrupt_gate() {
+        println!("🧪 [Test] Integrity Check on Logic Gate...");
+
+        let mut neuron = HTPNeuron::new();
+        assert!(neuron.verify_integrity().is_ok());
+        assert!(neuron.logic_gate().linear.is_finite() && neuron.state.is_finite());
+
+        neuron.gate_mut().linear.data[7] = f32::NAN;
+        assert!(!neuron.logic_gate().linear.is_finite());
+        let err = neuron.verify_integrity().unwrap_err();
+        assert!(err.contains("matrix"), "❌ Unexpected error: {}", err);
+
+        let mut neuron = HTPNeuron::new();
+        neuron.gate_mut().translation.data[0] = f32::INFINITY;
+        assert!(neuron.verify_integrity().is_err(), "❌ Infinite bias passed the integrity check");
+    }
+
+    /// 🧪 Test 4: Recurrent Sequence (局部循环推演)
+    /// absorb_sequence 与手写循环 S_t = absorb(x_t + S_{t-1}) 逐位一致，并保留最终状态。
+    #[test]
+    fn test_absorb_sequence_matches_manual_loop() {
+        println!("🧪 [Test] Neuron Recurrent Sequence...");
+
+        let template = HTPNeuron::with_weights(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 5),
+            ConceptEmbedder::embed_token(5),
+        );
+        let inputs: Vec<Vector> = (0..4).map(ConceptEmbedder::embed_token).collect();
+
+        let mut neuron = template.clone();
+        let states = neuron.absorb_sequence(&inputs);
+
+        let mut manual = template.clone();
+        let mut expected = Vec::new();
+        let mut state = manual.state.clone();
+        for x in &inputs {
+            state = manual.absorb(&x.add(&state));
+            expected.push(state.clone());
+        }
+
+        assert_eq!(states, expected);
+        assert_eq!(neuron.state, *expected.last().unwrap(), "❌ Final state not retained");
+
+        let mut idle = template.clone();
+        assert!(idle.absorb_sequence(&[]).is_empty());
+        assert_eq!(idle.state, template.state);
+    }
+
+    /// 🧪 Test 5: Tied Weights (跨层权重绑定)
+    /// 层 0 与层 2 共享逻辑门：更新任一层，另一层随之改变；层 1 不受影响。
+    /// 同一步中两层各自的梯度更新都被保留 (而不是互相覆盖)。
+    #[test]
+    fn test_tied_layers_share_updates() {
+        use crate::train_loop::SimpleOptimizer;
+
+        println!("🧪 [Test] Tied Weights...");
+
+        let mut model = [HTPNeuron::new(), HTPNeuron::new(), HTPNeuron::new()];
+        let shared = model[0].share_gate();
+        model[2] = HTPNeuron::new().tie_to(&shared);
+        assert!(model[0].is_tied() && model[2].is_tied() && !model[1].is_tied());
+
+        // 1. 更新层 0 -> 层 2 同步变化
+        let mut opt = SimpleOptimizer::new(0.5);
+        let grad_w = Matrix::identity().scale(0.2);
+        let grad_b = ConceptEmbedder::embed_token(5);
+        opt.step_neuron(0, &mut model[0], &grad_w, &grad_b);
+        assert_ne!(model[0].logic_gate(), &AffineTuple::identity());
+
+        let x = ConceptEmbedder::embed_token(6);
+        let out_0 = model[0].absorb(&x);
+        assert_eq!(model[2].absorb(&x), out_0, "❌ Tied layer 2 did not see layer 0's update");
+        assert_eq!(model[2].logic_gate(), model[0].logic_gate());
+        assert_eq!(model[1].logic_gate(), &AffineTuple::identity(), "❌ Untied layer must not change");
+
+        // 2. 同一步内两层都更新: 两次更新累积在共享门上
+        let before = shared.read();
+        opt.step_neuron(0, &mut model[0], &grad_w, &grad_b);
+        opt.step_neuron(2, &mut model[2], &grad_w, &grad_b);
+        let expected_bias = before.translation.sub(&grad_b.scale(0.5 * 2.0));
+        let diff = shared.read().translation.sub(&expected_bias).norm();
+        assert!(diff < 1e-5, "❌ Tied updates overwrote each other ({})", diff);
+        assert!(model[0].sync_tied_gate(), "❌ Layer 0 should pull layer 2's update");
+        assert_eq!(model[0].logic_gate(), &shared.read());
+    }
+
+    /// 🧪 Test 6: White-Box Explanation (神经元白盒解释)
+    /// 恒等门: σ ≈ 1 (保距)、无偏置、没有唯一不动点；
+    /// 0.5·I 门: 压缩，不动点满足 x* = W x* + b；缩放后的随机门按其主方向放大最多。
+    #[test]
+    fn test_explain_identity_and_contractive_gates() {
+        use crate::core::neuron::GateRegime;
+
+        println!("🧪 [Test] HTPNeuron::explain...");
+
+        let identity = HTPNeuron::new().explain();
+        println!("   > identity: {}", identity);
+        assert!((identity.spectral_norm - 1.0).abs() < 1e-4);
+        assert_eq!(identity.regime, GateRegime::Isometric);
+        assert_eq!(identity.bias_norm, 0.0);
+        assert!(identity.fixed_point.is_none());
+        assert!(identity.to_string().contains("no unique fixed point"));
+
+        let bias = ConceptEmbedder::embed_token(8);
+        let neuron = HTPNeuron::with_weights(Matrix::identity().scale(0.5), bias.clone());
+        let report = neuron.explain();
+        println!("   > contractive: {}", report);
+        assert_eq!(report.regime, GateRegime::Contractive);
+        assert!((report.spectral_norm - 0.5).abs() < 1e-4);
+        assert!((report.bias_norm - 1.0).abs() < 1e-5);
+        let x = report.fixed_point.expect("❌ A contractive gate has a unique fixed point");
+        let image = neuron.logic_gate().linear.matmul_vec(&x).add(&neuron.logic_gate().translation);
+        assert!(image.sub(&x).norm() < 1e-4, "❌ Reported point is not fixed");
+
+        let expansive = HTPNeuron::with_weights(WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 5).scale(20.0), Vector::zeros());
+        let report = expansive.explain();
+        assert_eq!(report.regime, GateRegime::Expansive);
+        let gain = expansive.logic_gate().linear.matmul_vec(&report.dominant_direction).norm();
+        assert!((gain - report.spectral_norm).abs() < 1e-2 * report.spectral_norm);
+        assert!((report.dominant_direction.norm() - 1.0).abs() < 1e-4);
+    }
+
+    /// 🧪 Test 7: Gate Write Guard (逻辑门写入守卫)
+    /// 通过 `gate_mut` 原地修改逻辑门后，缓存自动失效，绑定层也能看到这次写入。
+    #[test]
+    fn test_gate_mut_invalidates_and_publishes() {
+        println!("🧪 [Test] HTPNeuron::gate_mut...");
+
+        let input = ConceptEmbedder::embed_token(3);
+        let mut owner = HTPNeuron::new().with_cache(4);
+        let shared = owner.share_gate();
+        let mut tied = HTPNeuron::new().tie_to(&shared);
+
+        let before = owner.absorb(&input);
+        let version = owner.weights_version();
+        owner.gate_mut().translation.data[0] += 1.0;
+        assert!(owner.weights_version() > version, "❌ Guard drop did not bump the weights version");
+
+        let after = owner.absorb(&input);
+        assert!((after.data[0] - before.data[0] - 1.0).abs() < 1e-6, "❌ Cached output survived a gate write");
+        assert!(tied.sync_tied_gate(), "❌ Tied layer did not see the guarded write");
+        assert_eq!(tied.logic_gate(), owner.logic_gate());
+    }
+}
@@ -0,0 +1,1056 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Vector, MANIFOLD_DIM};
+    use crate::net::node::{HTPNode, NodeRole};
+    use crate::net::sync::{AggregationResult, GradientAggregator, MultiAggregationResult};
+    use crate::net::wire::{PacketType, ErrorCode, GradientUpdate, MultiLayerGradient};
+
+    fn unit_gradient(layer_index: usize) -> GradientUpdate {
+        GradientUpdate {
+            layer_index,
+            weight_grad: vec![1.0; MANIFOLD_DIM * MANIFOLD_DIM],
+            bias_grad: vec![1.0; MANIFOLD_DIM],
+            batch_size: 1,
+        }
+    }
+
+    /// 🧪 Test 1: Multi-Layer Gradient Push (多层梯度批量推送)
+    /// 一个包携带 12 层梯度，PS 必须一次性全部应用。
+    #[tokio::test]
+    async fn test_multi_layer_gradient_push() {
+        println!("🧪 [Test] Multi-Layer Gradient Push (12 Layers)...");
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 12);
+        let batch = MultiLayerGradient {
+            updates: (0..12).map(unit_gradient).collect(),
+            epoch: 1,
+        };
+
+        let response = ps.process_packet(PacketType::MultiGradientPush(batch)).await;
+        assert!(matches!(response, Some(PacketType::ParameterBroadcast(_))));
+        assert_eq!(ps.epoch(), 1);
+
+        // 每一层的 Bias 都应被 -lr * 1.0 修正
+        let model = ps.model.load();
+        for (idx, neuron) in model.iter().enumerate() {
+            let b0 = neuron.logic_gate().translation.data[0];
+            assert!((b0 + 1e-3).abs() < 1e-6, "❌ Layer {} was not updated (b0 = {})", idx, b0);
+        }
+    }
+
+    /// 🧪 Test 2: Atomic Multi-Layer Aggregation (原子聚合)
+    /// 只有所有贡献者都到齐时，整个多层包才一起输出。
+    #[test]
+    fn test_multi_layer_aggregation_is_atomic() {
+        let mut aggregator = GradientAggregator::new();
+        let children = vec!["worker-01".to_string()];
+        let batch = || MultiLayerGradient { updates: (0..3).map(unit_gradient).collect(), epoch: 1 };
+
+        let first = aggregator.aggregate_multi(batch(), "SELF".to_string(), &children);
+        assert!(matches!(first, MultiAggregationResult::Pending));
+
+        match aggregator.aggregate_multi(batch(), "worker-01".to_string(), &children) {
+            MultiAggregationResult::Complete(out) => {
+                assert_eq!(out.updates.len(), 3);
+                assert_eq!(out.updates[0].batch_size, 2);
+            }
+            _ => panic!("❌ Expected the whole batch to complete"),
+        }
+
+        let stale = MultiLayerGradient { updates: vec![unit_gradient(0)], epoch: 0 };
+        assert!(matches!(aggregator.aggregate_multi(stale, "SELF".to_string(), &children), MultiAggregationResult::Stale));
+    }
+
+    /// 🧪 Test 3: PS-Owned Epoch & Batch Validation (纪元归 PS 所有 + 梯度包校验)
+    /// 来自 "未来" 的纪元、过期纪元、重复层、形状错误的梯度都以 InvalidRequest 拒绝，
+    /// PS 的纪元与权重保持不变；合法的包只把纪元推进一步。
+    #[tokio::test]
+    async fn test_multi_gradient_rejects_future_epoch_and_malformed_batches() {
+        println!("🧪 [Test] MultiLayerGradient Epoch Ownership & Validation...");
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let truncated = GradientUpdate { weight_grad: vec![1.0; 3], ..unit_gradient(1) };
+        let short_bias = GradientUpdate { bias_grad: vec![], ..unit_gradient(0) };
+        let rejected = [
+            MultiLayerGradient { updates: vec![unit_gradient(0)], epoch: u64::MAX },
+            MultiLayerGradient { updates: vec![unit_gradient(0)], epoch: 2 },
+            MultiLayerGradient { updates: vec![unit_gradient(0), unit_gradient(0)], epoch: 1 },
+            MultiLayerGradient { updates: vec![unit_gradient(0), truncated], epoch: 1 },
+            MultiLayerGradient { updates: vec![short_bias], epoch: 1 },
+            MultiLayerGradient { updates: vec![unit_gradient(5)], epoch: 1 },
+        ];
+        for batch in rejected {
+            let epoch = batch.epoch;
+            match ps.process_packet(PacketType::MultiGradientPush(batch)).await {
+                Some(PacketType::Error { code: ErrorCode::InvalidRequest, message }) => println!("   > epoch {}: {}", epoch, message),
+                other => panic!("❌ Expected InvalidRequest, got {:?}", other),
+            }
+        }
+        assert_eq!(ps.epoch(), 0, "❌ A rejected batch moved the epoch");
+        assert!(ps.model.load().iter().all(|n| n.logic_gate().translation.data[0] == 0.0), "❌ A rejected batch touched the weights");
+
+        // 合法的包: 纪元前进一步，随后旧纪元的包过期
+        let ok = ps.process_packet(PacketType::MultiGradientPush(MultiLayerGradient { updates: vec![unit_gradient(0)], epoch: 1 })).await;
+        assert!(matches!(ok, Some(PacketType::ParameterBroadcast(_))));
+        assert_eq!(ps.epoch(), 1);
+        let stale = ps.process_packet(PacketType::MultiGradientPush(MultiLayerGradient { updates: vec![unit_gradient(1)], epoch: 0 })).await;
+        assert!(matches!(stale, Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })));
+
+        // 单层推送同样校验形状
+        let bad_single = GradientUpdate { weight_grad: vec![], ..unit_gradient(0) };
+        let response = ps.process_packet(PacketType::GradientPush(bad_single)).await;
+        assert!(matches!(response, Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })));
+    }
+
+    /// 🧪 Test 4: Pending Aggregation Report (聚合卡住诊断)
+    /// 部分到齐的层必须报告缺席的贡献者；收齐后的层不再出现在报告中。
+    #[test]
+    fn test_pending_report_lists_missing_contributors() {
+        println!("🧪 [Test] Pending Aggregation Report...");
+
+        let mut aggregator = GradientAggregator::new();
+        let children = vec!["worker-01".to_string(), "worker-02".to_string()];
+        assert!(aggregator.pending_report().is_empty());
+
+        // Layer 1: SELF + worker-01 到齐，worker-02 缺席；Layer 0: 只有 SELF
+        aggregator.aggregate(unit_gradient(1), "SELF".to_string(), &children);
+        aggregator.aggregate(unit_gradient(1), "worker-01".to_string(), &children);
+        aggregator.aggregate(unit_gradient(0), "SELF".to_string(), &children);
+
+        assert_eq!(aggregator.pending_report(), vec![
+            (0, vec!["worker-01".to_string(), "worker-02".to_string()]),
+            (1, vec!["worker-02".to_string()]),
+        ]);
+
+        // Layer 1 收齐后从报告中消失
+        let done = aggregator.aggregate(unit_gradient(1), "worker-02".to_string(), &children);
+        assert!(matches!(done, AggregationResult::Complete(_)));
+        assert_eq!(aggregator.pending_report().len(), 1);
+    }
+
+    /// 🧪 Test 5: Role Mismatch Is Observable (角色错配显式报错)
+    /// 没有 Optimizer 的 Worker 收到梯度时必须回执 Error，而不是静默丢弃。
+    #[tokio::test]
+    async fn test_gradient_to_worker_returns_error() {
+        println!("🧪 [Test] Gradient Push to Optimizer-less Node...");
+
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 2);
+        assert!(!worker.can_apply_gradients());
+
+        match worker.process_packet(PacketType::GradientPush(unit_gradient(0))).await {
+            Some(PacketType::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::RoleMismatch);
+                assert!(message.contains("worker-01"), "❌ Error should name the node: {}", message);
+            }
+            other => panic!("❌ Expected RoleMismatch error, got {:?}", other),
+        }
+
+        let batch = MultiLayerGradient { updates: vec![unit_gradient(0)], epoch: 0 };
+        let response = worker.process_packet(PacketType::MultiGradientPush(batch)).await;
+        assert!(matches!(response, Some(PacketType::Error { code: ErrorCode::RoleMismatch, .. })));
+
+        // Worker 的权重未被触碰
+        let model = worker.model.load();
+        assert_eq!(model[0].logic_gate().translation.data[0], 0.0);
+    }
+
+    /// 🧪 Test 6: Per-Source Rate Limiting (按来源限流)
+    /// 超出令牌桶容量的推送被拒绝，其他来源不受影响。
+    #[tokio::test]
+    async fn test_gradient_rate_limit_rejects_flood() {
+        println!("🧪 [Test] Gradient Push Rate Limiting...");
+
+        // 每秒 1 个，突发 3 个
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 1)
+            .with_gradient_rate_limit(1.0, 3.0);
+
+        let mut accepted = 0;
+        let mut rejected = 0;
+        for _ in 0..10 {
+            match ps.process_packet_from("worker-buggy", PacketType::GradientPush(unit_gradient(0))).await {
+                Some(PacketType::ParameterBroadcast(_)) => accepted += 1,
+                Some(PacketType::Error { code: ErrorCode::RateLimited, .. }) => rejected += 1,
+                other => panic!("❌ Unexpected response {:?}", other),
+            }
+        }
+        assert_eq!((accepted, rejected), (3, 7));
+
+        // 另一个 Worker 拥有自己的令牌桶
+        let response = ps.process_packet_from("worker-polite", PacketType::GradientPush(unit_gradient(0))).await;
+        assert!(matches!(response, Some(PacketType::ParameterBroadcast(_))));
+    }
+
+    /// 🧪 Test 7: Idempotent Sync (幂等同步)
+    /// 内容哈希与本地一致的快照不触发写入；不同的快照照常覆盖。
+    #[tokio::test]
+    async fn test_identical_snapshot_skips_write() {
+        println!("🧪 [Test] Snapshot Content Hash...");
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 2);
+
+        // 1. 初始状态相同 -> 跳过
+        let Some(PacketType::ParameterBroadcast(snapshot)) =
+            ps.process_packet(PacketType::MultiGradientPush(MultiLayerGradient { updates: vec![], epoch: 0 })).await
+        else { panic!("❌ PS did not broadcast") };
+        worker.process_packet(PacketType::ParameterBroadcast(snapshot)).await;
+        assert_eq!(worker.skipped_syncs(), 1);
+
+        // 2. PS 更新后 -> 写入，且之后重复收到同一快照 -> 跳过
+        let Some(PacketType::ParameterBroadcast(updated)) =
+            ps.process_packet(PacketType::GradientPush(unit_gradient(1))).await
+        else { panic!("❌ PS did not broadcast") };
+        worker.process_packet(PacketType::ParameterBroadcast(updated.clone())).await;
+        assert_eq!(worker.skipped_syncs(), 1);
+        assert!((worker.model.load()[1].logic_gate().translation.data[0] + 1e-3).abs() < 1e-6);
+
+        worker.process_packet(PacketType::ParameterBroadcast(updated)).await;
+        assert_eq!(worker.skipped_syncs(), 2);
+    }
+
+    /// 🧪 Test 8: Lock-Free Inference (双缓冲推理)
+    /// Worker 持续同步新参数的同时，推理请求依然按时完成；旧快照的持有者不受后续更新影响。
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_inference_not_blocked_by_updates() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        println!("🧪 [Test] Concurrent Inference During Sync...");
+
+        // 1. PS 产生一串不同的快照
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let mut snapshots = Vec::new();
+        for _ in 0..30 {
+            if let Some(PacketType::ParameterBroadcast(s)) = ps.process_packet(PacketType::GradientPush(unit_gradient(0))).await {
+                snapshots.push(s);
+            }
+        }
+
+        let worker = Arc::new(HTPNode::new("worker-01".to_string(), NodeRole::Worker, 2));
+        let before = worker.model.load_full();
+
+        // 2. 后台持续写入
+        let syncer = {
+            let worker = worker.clone();
+            tokio::spawn(async move {
+                for s in snapshots {
+                    worker.process_packet(PacketType::ParameterBroadcast(s)).await;
+                }
+            })
+        };
+
+        // 3. 前台推理：每个请求都必须在时限内返回
+        for request_id in 0..20 {
+            let request = PacketType::InferenceRequest { request_id, input_state: Vector::zeros() };
+            let response = tokio::time::timeout(Duration::from_millis(500), worker.process_packet(request)).await
+                .expect("❌ Inference stalled behind model updates");
+            assert!(matches!(response, Some(PacketType::InferenceResponse { .. })));
+        }
+        syncer.await.unwrap();
+
+        // 4. 旧版本保持不变，新版本包含全部 30 次更新
+        assert_eq!(before[0].logic_gate().translation.data[0], 0.0);
+        let b0 = worker.model.load()[0].logic_gate().translation.data[0];
+        assert!((b0 + 30.0 * 1e-3).abs() < 1e-4, "❌ Lost updates (b0 = {})", b0);
+    }
+
+    /// (span 名, 字段, 父 span 名)
+    type SpanRecord = (String, String, Option<String>);
+
+    /// 🔭 SpanRecorder: 测试用 tracing Layer，记录每个新建 Span 的名字、字段与父 Span
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: std::sync::Arc<std::sync::Mutex<Vec<SpanRecord>>>,
+    }
+
+    struct FieldWriter<'a>(&'a mut String);
+
+    impl tracing::field::Visit for FieldWriter<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!("{}={:?};", field.name(), value));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = String::new();
+            attrs.record(&mut FieldWriter(&mut fields));
+            let parent = ctx.span(id).and_then(|s| s.parent()).map(|p| p.name().to_string());
+            let name = attrs.metadata().name().to_string();
+            self.spans.lock().unwrap().push((name, fields, parent));
+        }
+    }
+
+    /// 🧪 Test 9: Server-Side Folding (服务端折叠)
+    /// 多 Token 的 Time 折叠请求，结果必须与客户端本地折叠后发送的普通请求一致；空请求回执错误。
+    #[tokio::test]
+    async fn test_folded_inference_matches_local_fold() {
+        use crate::core::affine::AffineTuple;
+        use crate::core::primes::ConceptEmbedder;
+        use crate::net::wire::FoldMode;
+        use crate::topology::folding::HyperFolder;
+
+        println!("🧪 [Test] Server-Side Folded Inference...");
+
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 1);
+        let tokens: Vec<_> = [12u32, 7, 42].iter().map(|&t| ConceptEmbedder::embed_token(t)).collect();
+
+        // 1. 本地折叠 -> 普通推理请求
+        let timeline: Vec<AffineTuple> = tokens.iter()
+            .map(|e| e.clone().to_affine_leaf())
+            .collect();
+        let local_state = HyperFolder::fold_timeline(&timeline).unwrap().as_point().clone();
+        let expected = match worker.process_packet(PacketType::InferenceRequest { request_id: 1, input_state: local_state }).await {
+            Some(PacketType::InferenceResponse { output_state, .. }) => output_state,
+            other => panic!("❌ Unexpected response: {:?}", other),
+        };
+
+        // 2. 服务端折叠
+        let request = PacketType::FoldedInferenceRequest { request_id: 2, tokens, mode: FoldMode::Time };
+        match worker.process_packet(request).await {
+            Some(PacketType::InferenceResponse { request_id, output_state }) => {
+                assert_eq!(request_id, 2);
+                let diff = output_state.sub(&expected).norm();
+                assert!(diff < 1e-5, "❌ Server-side fold differs from local fold ({})", diff);
+            }
+            other => panic!("❌ Unexpected response: {:?}", other),
+        }
+
+        // 3. 空 Token 序列
+        let empty = PacketType::FoldedInferenceRequest { request_id: 3, tokens: vec![], mode: FoldMode::Space };
+        assert!(matches!(
+            worker.process_packet(empty).await,
+            Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })
+        ));
+    }
+
+    /// 🧪 Test 10: Pipeline (Model-Parallel) Inference (流水线推理)
+    /// 4 层模型拆分到两个节点 (0..2 / 2..4)，经 Discovery 路由后的结果必须与单节点一致。
+    #[tokio::test]
+    async fn test_pipeline_inference_matches_single_node() {
+        use std::sync::Arc;
+        use crate::core::neuron::HTPNeuron;
+        use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+        use crate::net::discovery::DiscoveryService;
+
+        println!("🧪 [Test] Two-Node Pipeline Inference...");
+
+        let layers: Vec<HTPNeuron> = (0..4)
+            .map(|i| HTPNeuron::with_weights(
+                WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 50 + i),
+                ConceptEmbedder::embed_token(i as u32).scale(0.1),
+            ))
+            .collect();
+        let input = ConceptEmbedder::embed_token(99);
+
+        // 1. 单节点: 持有全部 4 层
+        let single = HTPNode::new("worker-full".to_string(), NodeRole::Worker, 4);
+        single.model.store(Arc::new(layers.clone()));
+        let request = PacketType::InferencePipelineRequest { request_id: 1, input: input.clone(), next_layer: 0 };
+        let expected = match single.process_packet(request).await {
+            Some(PacketType::InferenceResponse { output_state, .. }) => output_state,
+            other => panic!("❌ Unexpected response: {:?}", other),
+        };
+
+        // 2. 两个分片 + Discovery 路由表
+        let head = HTPNode::new("worker-a".to_string(), NodeRole::Worker, 2).with_layer_shard(0, 4);
+        head.model.store(Arc::new(layers[..2].to_vec()));
+        let tail = HTPNode::new("worker-b".to_string(), NodeRole::Worker, 2).with_layer_shard(2, 4);
+        tail.model.store(Arc::new(layers[2..].to_vec()));
+        assert_eq!(tail.layer_range(), 2..4);
+
+        let discovery = DiscoveryService::new("gateway".to_string(), NodeRole::Worker, "127.0.0.1:4000".to_string());
+        for (node, addr) in [(&head, "127.0.0.1:5001"), (&tail, "127.0.0.1:5002")] {
+            discovery.add_seed_peer(node.id.clone(), addr.to_string(), NodeRole::Worker).await;
+            discovery.register_layers(&node.id, node.layer_range()).await;
+        }
+
+        // 3. 逐段转发，直到某个分片回执最终结果
+        let mut packet = PacketType::InferencePipelineRequest { request_id: 2, input, next_layer: 0 };
+        let mut hops = 0;
+        let output = loop {
+            let PacketType::InferencePipelineRequest { next_layer, .. } = &packet else { unreachable!() };
+            let owner = discovery.layer_owner(*next_layer).await.expect("❌ No owner for layer");
+            let node = if owner.id == head.id { &head } else { &tail };
+            hops += 1;
+            match node.process_packet(packet).await {
+                Some(next @ PacketType::InferencePipelineRequest { .. }) => packet = next,
+                Some(PacketType::InferenceResponse { request_id, output_state }) => {
+                    assert_eq!(request_id, 2);
+                    break output_state;
+                }
+                other => panic!("❌ Unexpected response: {:?}", other),
+            }
+        };
+        assert_eq!(hops, 2);
+        assert_eq!(output, expected, "❌ Pipeline result differs from single-node inference");
+
+        // 4. 路由错误的分片回执错误
+        let misrouted = PacketType::InferencePipelineRequest { request_id: 3, input: Vector::zeros(), next_layer: 0 };
+        assert!(matches!(tail.process_packet(misrouted).await, Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })));
+    }
+
+    /// 🧪 Test 11: Model Fingerprint Consensus (模型指纹一致性)
+    /// 相同模型指纹相同，改动一个权重即不同；PS 发现分叉后下发快照，同步后指纹重新一致。
+    #[tokio::test]
+    async fn test_fingerprint_detects_divergence() {
+        use std::sync::Arc;
+        use crate::net::wire::model_fingerprint;
+
+        println!("🧪 [Test] Model Fingerprint Consensus...");
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 2);
+
+        // 1. 相同模型 -> 相同指纹；修改一个权重 -> 指纹改变
+        let model = ps.model.load_full();
+        assert_eq!(model_fingerprint(&model), model_fingerprint(&worker.model.load()));
+        assert_eq!(ps.fingerprint(), model_fingerprint(&model));
+
+        let mut diverged = Vec::clone(&model);
+        diverged[1].gate_mut().linear.data[7] += 1e-6;
+        assert_ne!(model_fingerprint(&diverged), model_fingerprint(&model));
+
+        // 2. 一致时无需回执
+        assert!(ps.process_packet(worker.fingerprint_packet()).await.is_none());
+
+        // 3. Worker 悄然分叉 -> PS 下发完整快照
+        worker.model.store(Arc::new(diverged));
+        let resync = ps.process_packet(worker.fingerprint_packet()).await;
+        let Some(snapshot @ PacketType::ParameterBroadcast(_)) = resync else {
+            panic!("❌ PS did not answer a mismatched fingerprint with a snapshot");
+        };
+
+        // Worker 收到不一致的指纹时只记录警告，不回执 (避免 Worker 之间来回 Ping-Pong)
+        assert!(worker.process_packet(ps.fingerprint_packet()).await.is_none());
+
+        worker.process_packet(snapshot).await;
+        assert_eq!(worker.fingerprint(), ps.fingerprint(), "❌ Resync did not restore consensus");
+    }
+
+    /// 🧪 Test 12: Structured Tracing Spans (结构化追踪)
+    /// 一次推理请求必须产生嵌套在 `packet` 之下、携带 request_id 与 node_id 的 `inference` Span。
+    #[tokio::test]
+    async fn test_inference_emits_structured_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        println!("🧪 [Test] Structured Tracing Spans...");
+
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let worker = HTPNode::new("worker-07".to_string(), NodeRole::Worker, 1);
+        let request = PacketType::InferenceRequest {
+            request_id: 7,
+            input_state: Vector::zeros(),
+        };
+        let response = worker.process_packet(request).await;
+        assert!(matches!(response, Some(PacketType::InferenceResponse { request_id: 7, .. })));
+
+        let spans = recorder.spans.lock().unwrap();
+        let (_, packet_fields, _) = spans.iter().find(|(name, _, _)| name == "packet")
+            .expect("❌ Missing packet span");
+        assert!(packet_fields.contains("node_id=worker-07") && packet_fields.contains("epoch=0"));
+
+        let (_, fields, parent) = spans.iter().find(|(name, _, _)| name == "inference")
+            .expect("❌ Missing inference span");
+        assert!(fields.contains("request_id=7"), "❌ request_id not recorded: {}", fields);
+        assert!(fields.contains("node_id=worker-07"), "❌ node_id not recorded: {}", fields);
+        assert_eq!(parent.as_deref(), Some("packet"));
+    }
+
+    /// 🧪 Test 12: NodeRole String Round-Trip (角色字符串互转)
+    /// "worker" / "ps" 经 FromStr -> Display 往返不变；非法角色返回错误而不是 panic。
+    #[test]
+    fn test_node_role_round_trip() {
+        println!("🧪 [Test] NodeRole FromStr / Display...");
+
+        for s in ["worker", "ps"] {
+            let role: NodeRole = s.parse().expect("valid role");
+            assert_eq!(role.to_string(), s);
+        }
+        assert_eq!("ps".parse::<NodeRole>(), Ok(NodeRole::ParameterServer));
+
+        let err = "foo".parse::<NodeRole>().unwrap_err();
+        assert!(err.contains("foo"), "❌ Error should name the bad input: {}", err);
+    }
+
+    /// 🧪 Test 13: Aggregator Recovery (聚合器人工恢复)
+    /// reset_layer 只丢弃单层；clear 清空全部缓冲，之后同一节点可以重新贡献。
+    #[test]
+    fn test_aggregator_clear_and_reset_layer() {
+        println!("🧪 [Test] GradientAggregator Clear / Reset...");
+
+        let mut aggregator = GradientAggregator::new();
+        let children = vec!["worker-01".to_string()];
+        for layer in 0..3 {
+            aggregator.aggregate(unit_gradient(layer), "SELF".to_string(), &children);
+        }
+        assert_eq!(aggregator.pending_report().len(), 3);
+
+        // 单层恢复
+        assert!(aggregator.reset_layer(1));
+        assert!(!aggregator.reset_layer(1), "❌ Layer 1 was already reset");
+        let layers: Vec<usize> = aggregator.pending_report().iter().map(|(layer, _)| *layer).collect();
+        assert_eq!(layers, vec![0, 2]);
+
+        // 全部清空
+        aggregator.clear();
+        assert!(aggregator.pending_report().is_empty());
+
+        // 清空后 SELF 的新贡献不会被当作重复提交
+        let result = aggregator.aggregate(unit_gradient(0), "SELF".to_string(), &[]);
+        match result {
+            AggregationResult::Complete(grad) => assert_eq!(grad.batch_size, 1),
+            _ => panic!("❌ Fresh contribution after clear was not aggregated"),
+        }
+    }
+
+    /// 🧪 Test 14: Trace Transfer (梯度磁带分片传输)
+    /// 磁带被切成多个 TraceTransfer 包，经序列化往返并乱序到达后，重组结果与原磁带一致，
+    /// 且在接收端执行的 backward 与本地结果相同。
+    #[test]
+    fn test_trace_transfer_round_trip() {
+        use crate::core::affine::AffineTuple;
+        use crate::core::algebra::Matrix;
+        use crate::net::wire::{trace_transfer_packets, TraceAssembler};
+        use crate::topology::merkle::CausalTrace;
+
+        println!("🧪 [Test] CausalTrace Transfer...");
+
+        let tuple = |k: f32| AffineTuple::new(
+            Matrix::new(2, 2, vec![1.0, k, 0.0, 1.0]),
+            Vector { data: vec![k, -k] },
+        );
+        let mut trace = CausalTrace::new();
+        let (a, b, c) = (trace.push_leaf(tuple(0.1)), trace.push_leaf(tuple(0.2)), trace.push_leaf(tuple(0.3)));
+        let ab = trace.push_compose(a, b, tuple(0.2).compose_unchecked(&tuple(0.1)));
+        trace.push_n_ary_merge(vec![ab, c], tuple(0.5));
+        trace.active_path = vec![0, 1, 3, 4];
+
+        // 5 个节点 / 每片 2 个 -> 3 片
+        let grad = tuple(1.0);
+        let packets = trace_transfer_packets(42, &trace, Some(&grad), 2);
+        assert_eq!(packets.len(), 3);
+
+        let mut assembler = TraceAssembler::new();
+        let mut assembled = None;
+        for packet in packets.iter().rev() {
+            let bytes = packet.to_bytes().expect("serialize");
+            match PacketType::from_bytes(&bytes).expect("deserialize") {
+                PacketType::TraceTransfer { request_id, part, total_parts, trace, grad_output } => {
+                    assert_eq!((request_id, total_parts), (42, 3));
+                    assert_eq!(grad_output.is_some(), part + 1 == total_parts, "❌ Output gradient must ride on the last part only");
+                    assembled = assembler.accept(request_id, part, total_parts, trace, grad_output).expect("valid part");
+                }
+                other => panic!("❌ Unexpected packet: {:?}", other),
+            }
+        }
+        let assembled = assembled.expect("❌ Trace was not reassembled after the last part");
+        assert_eq!(assembled.grad_output.as_ref(), Some(&grad));
+        let received = assembled.trace;
+        assert_eq!(assembler.pending_requests(), 0);
+
+        assert_eq!(received.active_path, trace.active_path);
+        assert_eq!(received.nodes.len(), trace.nodes.len());
+        for (got, want) in received.nodes.iter().zip(&trace.nodes) {
+            assert_eq!((got.id, &got.parents, &got.value), (want.id, &want.parents, &want.value));
+        }
+        assert_eq!(received.backward(&grad), trace.backward(&grad));
+
+        assert!(assembler.accept(7, 3, 3, CausalTrace::new(), None).is_err(), "❌ Out-of-range part accepted");
+    }
+
+    /// 🧪 Test 15: Bounded Trace Reassembly (有界的磁带重组)
+    /// 分片数与并发请求数超限的分片被拒绝，过期的半成品被清理，父节点指向 "未来" 的磁带在重组时被拒绝。
+    #[test]
+    fn test_trace_assembler_limits_ttl_and_parent_validation() {
+        use std::time::{Duration, Instant};
+        use crate::core::affine::AffineTuple;
+        use crate::net::wire::TraceAssembler;
+        use crate::topology::merkle::CausalTrace;
+
+        println!("🧪 [Test] TraceAssembler Limits...");
+
+        let mut assembler = TraceAssembler::new().with_max_parts(8).with_max_pending(1).with_ttl(Duration::from_millis(50));
+
+        // 1. total_parts 来自对端，超过上限时不分配缓冲
+        assert!(assembler.accept(1, 0, u32::MAX, CausalTrace::new(), None).is_err(), "❌ Unbounded part count accepted");
+        assert_eq!(assembler.pending_requests(), 0);
+
+        // 2. 并发请求数上限
+        assert!(matches!(assembler.accept(1, 0, 2, CausalTrace::new(), None), Ok(None)));
+        assert!(assembler.accept(2, 0, 2, CausalTrace::new(), None).is_err(), "❌ Pending limit not enforced");
+
+        // 3. TTL: 永远等不到第二片的请求过期后被清理，新请求得以进入
+        assert_eq!(assembler.evict_expired(Instant::now() + Duration::from_millis(60)), 1);
+        assert!(matches!(assembler.accept(2, 0, 2, CausalTrace::new(), None), Ok(None)));
+        assert_eq!(assembler.evict_expired(Instant::now() + Duration::from_millis(60)), 1);
+
+        // 4. 父节点 ID 必须小于自身 ID (否则 backward 会越界或依赖尚未计算的梯度)
+        let mut forged = CausalTrace::new();
+        forged.push_leaf(AffineTuple::identity());
+        forged.push_leaf(AffineTuple::identity());
+        forged.push_n_ary_merge(vec![0, 1], AffineTuple::identity());
+        forged.nodes[2].parents = vec![0, 7];
+        let err = assembler.accept(3, 0, 1, forged, None).expect_err("❌ Forged parent accepted");
+        println!("   > {}", err);
+        assert_eq!(assembler.pending_requests(), 0);
+    }
+
+    /// 🧪 Test 16: Remote Backward via TraceTransfer (远端反向传播)
+    /// Worker A 录制前向磁带并分片发给 Worker B；B 执行 backward，回执的各层梯度与本地 backward 一致，
+    /// 且可以直接被 PS 接受。
+    #[tokio::test]
+    async fn test_trace_transfer_runs_backward_on_receiver() {
+        use crate::core::affine::AffineTuple;
+        use crate::core::algebra::Matrix;
+        use crate::core::primes::ConceptEmbedder;
+        use crate::net::wire::trace_transfer_packets;
+        use crate::topology::tensor::HyperTensor;
+
+        println!("🧪 [Test] Remote Backward via TraceTransfer...");
+
+        let depth = 2;
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, depth);
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, depth);
+
+        // 前向: 输入叶子在前，模型各层在后 (与 train_step_sgd 一致)
+        let mut timeline: Vec<AffineTuple> = (0..2)
+            .map(|t| AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(t)))
+            .collect();
+        timeline.extend(ps.model.load().iter().map(|n| n.logic_gate().clone()));
+        let tensor = HyperTensor::forward(&timeline, true).expect("small trace");
+        let trace = tensor.trace.as_ref().expect("training mode records a trace");
+        let mut grad_output = AffineTuple::zeros();
+        grad_output.translation = tensor.root.translation.scale(2.0);
+        let local = trace.backward(&grad_output);
+
+        let mut reply = None;
+        for packet in trace_transfer_packets(9, trace, Some(&grad_output), 2) {
+            let packet = PacketType::from_bytes(&packet.to_bytes().unwrap()).unwrap();
+            reply = worker.process_packet(packet).await;
+        }
+        let Some(PacketType::MultiGradientPush(batch)) = reply else {
+            panic!("❌ Expected layer gradients, got {:?}", reply);
+        };
+        assert_eq!(batch.updates.len(), depth);
+        for (layer, grad) in batch.updates.iter().enumerate() {
+            let want = &local[2 + layer];
+            assert_eq!(grad.layer_index, layer);
+            assert_eq!((&grad.weight_grad, &grad.bias_grad), (&want.linear.data, &want.translation.data));
+        }
+
+        let applied = ps.process_packet(PacketType::MultiGradientPush(batch)).await;
+        assert!(matches!(applied, Some(PacketType::ParameterBroadcast(_))), "❌ PS rejected remote gradients");
+
+        // 输出梯度形状不符: 显式拒绝
+        let wrong = AffineTuple::new(Matrix::identity(), Vector { data: vec![0.0; 3] });
+        let bad = trace_transfer_packets(10, trace, Some(&wrong), usize::MAX);
+        let response = worker.process_packet(bad.into_iter().next().unwrap()).await;
+        assert!(matches!(response, Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })));
+    }
+
+    /// 🧪 Test 15: Sync Invalidates Output Cache (同步使推理缓存失效)
+    /// Worker 的神经元启用缓存后，同步新权重必须递增权重版本与模型版本，
+    /// 下一次推理重新计算 (返回新权重的结果)，而不是命中旧缓存。
+    #[tokio::test]
+    async fn test_parameter_sync_invalidates_output_cache() {
+        use std::sync::Arc;
+        use crate::core::algebra::Matrix;
+        use crate::core::neuron::HTPNeuron;
+        use crate::net::wire::{LayerState, ModelSnapshot};
+
+        println!("🧪 [Test] Parameter Sync Cache Invalidation...");
+
+        let worker = HTPNode::new("worker-00".to_string(), NodeRole::Worker, 1);
+        let mut cached = HTPNeuron::new().with_cache(4);
+        let input = Vector::new(vec![1.0; MANIFOLD_DIM]);
+        let before = cached.absorb(&input);
+        worker.model.store(Arc::new(vec![cached]));
+
+        let infer = |request_id| PacketType::InferenceRequest { request_id, input_state: input.clone() };
+        let output_of = |reply: Option<PacketType>| match reply {
+            Some(PacketType::InferenceResponse { output_state, .. }) => output_state,
+            other => panic!("❌ Expected InferenceResponse, got {:?}", other),
+        };
+        assert_eq!(output_of(worker.process_packet(infer(1)).await), before);
+        // 推理直接在共享模型上运行：缓存跨请求保留，第一次请求即命中
+        assert_eq!(worker.model.load()[0].cache_stats(), Some((1, 1)), "❌ Inference did not reuse the model's cache");
+
+        // 1. 同步新的偏置
+        let new_bias = Vector::new(vec![0.5; MANIFOLD_DIM]);
+        let snapshot = ModelSnapshot::new(1, vec![LayerState {
+            layer_index: 0,
+            weights: Matrix::identity(),
+            bias: new_bias.clone(),
+        }]);
+        let version_before = worker.model_version();
+        assert!(worker.process_packet(PacketType::ParameterBroadcast(snapshot)).await.is_none());
+        assert_eq!(worker.model_version(), version_before + 1, "❌ Model version not bumped by sync");
+        assert_eq!(worker.model.load()[0].weights_version(), 1, "❌ Neuron weights version not bumped by sync");
+
+        // 2. 下一次推理必须重新计算: I·x + b_new
+        let after = output_of(worker.process_packet(infer(2)).await);
+        assert_eq!(after, input.add(&new_bias), "❌ Stale cached output served after sync");
+
+        // 3. 同步前缓存过的输入在新版本下记为未命中 (真实计算)，之后的请求命中新条目
+        assert_eq!(worker.model.load()[0].cache_stats(), Some((1, 2)), "❌ Pre-sync cache entry was reused");
+        assert_eq!(output_of(worker.process_packet(infer(3)).await), after);
+        assert_eq!(worker.model.load()[0].cache_stats(), Some((2, 2)));
+    }
+
+    /// 🧪 Test 16: Startup Config Validation (启动时校验配置)
+    /// 维度与编译时 MANIFOLD_DIM 不符的配置必须在建立节点时被拒绝，错误信息指明原因；
+    /// 合法配置按 depth 构建模型。
+    #[test]
+    fn test_from_params_rejects_dimension_mismatch() {
+        use crate::core::param::HyperParams;
+
+        println!("🧪 [Test] Node Startup Config Validation...");
+
+        let bad = HyperParams { dimension: MANIFOLD_DIM + 1, ..HyperParams::default() };
+        let err = HTPNode::from_params("worker-00".to_string(), NodeRole::Worker, &bad)
+            .err()
+            .expect("❌ Node started with a mismatched dimension");
+        assert!(err.contains("Dimension Mismatch"), "❌ Unclear startup error: {}", err);
+
+        let good = HyperParams { depth: 3, ..HyperParams::default() };
+        let ps = HTPNode::from_params("ps-00".to_string(), NodeRole::ParameterServer, &good).expect("valid config");
+        assert_eq!(ps.model.load().len(), 3);
+        assert!(ps.can_apply_gradients());
+    }
+
+    /// 🧪 Test 17: Gradient Wire Size & Traffic Stats (带宽统计)
+    /// `wire_size` 必须等于实际序列化长度；PS 按纪元统计收到的梯度字节与发出的快照字节，
+    /// 纪元推进后计数清零。
+    #[tokio::test]
+    async fn test_wire_size_and_traffic_report() {
+        println!("🧪 [Test] Gradient Wire Size & Traffic Stats...");
+
+        let grad = unit_gradient(0);
+        assert_eq!(grad.wire_size(), bincode::serialize(&grad).unwrap().len());
+        let packet = PacketType::GradientPush(grad);
+        assert_eq!(packet.wire_size(), packet.to_bytes().unwrap().len());
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 2);
+        let reply = ps.process_packet(packet.clone()).await.expect("PS must broadcast a snapshot");
+        let report = ps.traffic_report();
+        assert_eq!(report.epoch, 0);
+        assert_eq!(report.gradient_bytes_received, packet.wire_size() as u64);
+        assert_eq!(report.snapshot_bytes_sent, reply.wire_size() as u64);
+        assert_eq!((report.gradient_bytes_sent, report.snapshot_bytes_received), (0, 0));
+
+        // 纪元推进 -> 下一个包到达时上一纪元的计数被汇总并清零
+        let batch = MultiLayerGradient { updates: vec![unit_gradient(1)], epoch: 1 };
+        ps.process_packet(PacketType::MultiGradientPush(batch)).await;
+        ps.process_packet(PacketType::Handshake { node_id: "w".to_string(), protocol_ver: 2 }).await;
+        let rolled = ps.traffic_report();
+        assert_eq!(rolled.epoch, 1);
+        assert_eq!((rolled.gradient_bytes_received, rolled.snapshot_bytes_sent), (0, 0));
+    }
+
+    /// 🧪 Test 18: Reconnect Catch-Up (重连追赶)
+    /// Worker 在第 2 纪元后断线、错过第 3、4 纪元的更新：重连请求只取回变化过的层 (增量)；
+    /// 落后超过上限的新节点取回完整快照。两者最终都与 PS 指纹一致。
+    #[tokio::test]
+    async fn test_reconnect_catch_up_with_delta_or_full_snapshot() {
+        println!("🧪 [Test] Reconnect Catch-Up...");
+
+        let ps = HTPNode::new("ps-00".to_string(), NodeRole::ParameterServer, 4).with_max_delta_epochs(2);
+        let worker = HTPNode::new("worker-00".to_string(), NodeRole::Worker, 4);
+        let push = |layers: &[usize], epoch: u64| PacketType::MultiGradientPush(MultiLayerGradient {
+            updates: layers.iter().copied().map(unit_gradient).collect(),
+            epoch,
+        });
+        let layers_of = |packet: &PacketType| match packet {
+            PacketType::ParameterBroadcast(snapshot) => snapshot.layers.iter().map(|l| l.layer_index).collect::<Vec<_>>(),
+            other => panic!("❌ Expected ParameterBroadcast, got {:?}", other),
+        };
+
+        // 1. 在线: 第 1、2 纪元的广播都被应用
+        for (layers, epoch) in [(&[0, 1, 2, 3][..], 1), (&[1][..], 2)] {
+            let broadcast = ps.process_packet(push(layers, epoch)).await.unwrap();
+            worker.process_packet(broadcast).await;
+        }
+        assert_eq!(worker.epoch(), 2);
+        assert_eq!(worker.fingerprint(), ps.fingerprint());
+
+        // 2. 断线: 错过第 3、4 纪元 (广播丢失)
+        ps.process_packet(push(&[2], 3)).await;
+        ps.process_packet(push(&[3], 4)).await;
+        assert_ne!(worker.fingerprint(), ps.fingerprint());
+
+        // 3. 重连: 增量快照只含第 2 纪元起变化过的层 (层 0 未变，不发送)
+        let delta = ps.process_packet(worker.sync_request_packet()).await.expect("delta snapshot");
+        assert_eq!(layers_of(&delta), vec![1, 2, 3]);
+        worker.process_packet(delta).await;
+        assert_eq!(worker.fingerprint(), ps.fingerprint(), "❌ Worker did not catch up");
+        assert_eq!(worker.epoch(), 4);
+
+        // 4. 落后太多 (0 -> 4 > 2): 完整快照
+        let fresh = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 4);
+        let full = ps.process_packet(fresh.sync_request_packet()).await.expect("full snapshot");
+        assert_eq!(layers_of(&full), vec![0, 1, 2, 3]);
+        fresh.process_packet(full).await;
+        assert_eq!(fresh.fingerprint(), ps.fingerprint());
+
+        // Worker 不响应追赶请求
+        assert!(worker.process_packet(fresh.sync_request_packet()).await.is_none());
+    }
+
+    /// 🧪 Test 19: Unsupported Packet (未处理的包类型)
+    /// 节点不处理的包类型回执 UnsupportedPacket 错误而不是静默丢弃；收到 Error 包本身不再回执。
+    #[tokio::test]
+    async fn test_unsupported_packet_returns_error() {
+        println!("🧪 [Test] Unsupported Packet...");
+
+        let worker = HTPNode::new("worker-00".to_string(), NodeRole::Worker, 1);
+        let stray = PacketType::InferenceResponse { request_id: 7, output_state: Vector::zeros() };
+        match worker.process_packet(stray).await {
+            Some(PacketType::Error { code, message }) => {
+                assert_eq!(code, ErrorCode::UnsupportedPacket);
+                assert!(message.contains("InferenceResponse"), "❌ Error should name the packet kind: {}", message);
+            }
+            other => panic!("❌ Expected UnsupportedPacket error, got {:?}", other),
+        }
+
+        let error = PacketType::Error { code: ErrorCode::UnsupportedPacket, message: "no".to_string() };
+        assert!(worker.process_packet(error).await.is_none(), "❌ Errors must not bounce back");
+    }
+
+    /// 🧪 Test 20: f64 Gradient Accumulation (高精度聚合)
+    /// 1 万个贡献者 (各 batch 1，梯度 0.1) 聚合：f32 加权和在 1e3 量级逐次舍入、均值漂移，
+    /// f64 累加则精确还原 0.1。
+    #[test]
+    fn test_f64_accumulation_avoids_drift() {
+        use crate::net::sync::AccumulationPrecision;
+
+        println!("🧪 [Test] f64 Gradient Accumulation...");
+
+        let aggregate = |precision: AccumulationPrecision| -> f32 {
+            let mut aggregator = GradientAggregator::new().with_precision(precision);
+            let grad = |batch_size| GradientUpdate { layer_index: 0, weight_grad: vec![0.1], bias_grad: vec![0.1], batch_size };
+            // 子节点列表留空：只等 "SELF"，避免为每个贡献者重建 1 万项的期望集合
+            for i in 0..10_000 {
+                assert!(matches!(aggregator.aggregate(grad(1), format!("worker-{}", i), &[]), AggregationResult::Pending));
+            }
+            match aggregator.aggregate(grad(0), "SELF".to_string(), &[]) {
+                AggregationResult::Complete(out) => {
+                    assert_eq!(out.batch_size, 10_000);
+                    out.weight_grad[0]
+                }
+                _ => panic!("❌ Aggregation should complete once SELF arrives"),
+            }
+        };
+
+        let (lossy, exact) = (aggregate(AccumulationPrecision::F32), aggregate(AccumulationPrecision::F64));
+        println!("   > Mean gradient: f32 = {:.7}, f64 = {:.7}", lossy, exact);
+        assert_eq!(exact, 0.1, "❌ f64 accumulation should recover the exact mean");
+        assert!((lossy - 0.1).abs() > 1e-6, "❌ Expected f32 accumulation to drift at this scale");
+    }
+
+    /// 🧪 Test 21: Hierarchical Carry-Up (树形聚合的部分和上报)
+    /// 两级树 (Root <- {A: a1, a2}, {B: b1}) 中，中间节点上报未归一化的部分和，Root 统一做除法：
+    /// 结果与把所有贡献者直接交给一个聚合器 (扁平聚合) 完全一致；多层包的树形聚合同样一致，
+    /// 落后纪元的部分和被丢弃。
+    #[test]
+    fn test_tree_aggregation_matches_flat() {
+        use crate::net::wire::PartialGradientSum;
+
+        println!("🧪 [Test] Hierarchical Partial-Sum Aggregation...");
+
+        let grad = |g: f32, batch_size| GradientUpdate { layer_index: 2, weight_grad: vec![g, -g], bias_grad: vec![2.0 * g], batch_size };
+        let ids = |xs: &[&str]| xs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // 各叶子的 (来源, 梯度, batch)；中间节点与 Root 各自也有本地梯度 ("SELF")
+        let a_leaves = [("a1", 0.25, 3), ("a2", -1.5, 1), ("SELF", 3.0, 2)];
+        let b_leaves = [("b1", 0.75, 4), ("SELF", -0.5, 5)];
+        let root_self = (2.0, 1);
+
+        // 1. 树形聚合
+        let subtree = |leaves: &[(&str, f32, usize)], children: &[String]| -> PartialGradientSum {
+            let mut aggregator = GradientAggregator::new().with_carry_up();
+            let mut out = None;
+            for &(from, g, n) in leaves {
+                if let AggregationResult::PartialSum(sum) = aggregator.aggregate(grad(g, n), from.to_string(), children) {
+                    out = Some(sum);
+                }
+            }
+            out.expect("❌ Intermediate node did not emit a partial sum")
+        };
+        let sum_a = subtree(&a_leaves, &ids(&["a1", "a2"]));
+        let sum_b = subtree(&b_leaves, &ids(&["b1"]));
+        assert_eq!(sum_a, PartialGradientSum { layer_index: 2, weight_sum: vec![5.25, -5.25], bias_sum: vec![10.5], total_batch: 6, epoch: 0 });
+
+        let root_children = ids(&["node-a", "node-b"]);
+        let mut root = GradientAggregator::new();
+        assert!(matches!(root.aggregate_partial_sum(sum_a, "node-a".to_string(), &root_children), AggregationResult::Pending));
+        assert!(matches!(root.aggregate(grad(root_self.0, root_self.1), "SELF".to_string(), &root_children), AggregationResult::Pending));
+        let AggregationResult::Complete(tree) = root.aggregate_partial_sum(sum_b, "node-b".to_string(), &root_children) else {
+            panic!("❌ Root should complete once every subtree reported");
+        };
+
+        // 2. 扁平聚合
+        let everyone = ids(&["a1", "a2", "a-self", "b1", "b-self"]);
+        let mut flat = GradientAggregator::new();
+        let leaves = [("a1", 0.25, 3), ("a2", -1.5, 1), ("a-self", 3.0, 2), ("b1", 0.75, 4), ("b-self", -0.5, 5)];
+        for (from, g, n) in leaves {
+            assert!(matches!(flat.aggregate(grad(g, n), from.to_string(), &everyone), AggregationResult::Pending));
+        }
+        let AggregationResult::Complete(flat) = flat.aggregate(grad(root_self.0, root_self.1), "SELF".to_string(), &everyone) else {
+            panic!("❌ Flat aggregation should complete");
+        };
+
+        println!("   > tree = {:?}, flat = {:?}", tree.weight_grad, flat.weight_grad);
+        assert_eq!((tree.batch_size, flat.batch_size), (16, 16));
+        assert_eq!(tree.weight_grad, flat.weight_grad);
+        assert_eq!(tree.bias_grad, flat.bias_grad);
+
+        // 3. 多层路径: 中间节点对多层包同样只上报部分和，Root 经 aggregate_multi_partial 原子聚合
+        let multi = |g: f32, n: usize| MultiLayerGradient {
+            updates: vec![grad(g, n), GradientUpdate { layer_index: 5, ..grad(-g, n) }],
+            epoch: 1,
+        };
+        let multi_subtree = |leaves: &[(&str, f32, usize)], children: &[String]| -> Vec<PartialGradientSum> {
+            let mut aggregator = GradientAggregator::new().with_carry_up();
+            let mut out = None;
+            for &(from, g, n) in leaves {
+                match aggregator.aggregate_multi(multi(g, n), from.to_string(), children) {
+                    MultiAggregationResult::PartialSum(sums) => out = Some(sums),
+                    MultiAggregationResult::Pending => {}
+                    _ => panic!("❌ Intermediate node must carry multi-layer sums up instead of finalizing"),
+                }
+            }
+            out.expect("❌ Intermediate node did not emit multi-layer partial sums")
+        };
+        let sums_a = multi_subtree(&a_leaves, &ids(&["a1", "a2"]));
+        let sums_b = multi_subtree(&b_leaves, &ids(&["b1"]));
+        assert!(sums_a.iter().chain(&sums_b).all(|s| s.epoch == 1), "❌ Partial sums must carry the batch epoch");
+        assert_eq!(sums_a[0].weight_sum, vec![5.25, -5.25]);
+
+        let mut root = GradientAggregator::new();
+        assert!(matches!(root.aggregate_multi_partial(sums_a, "node-a".to_string(), &root_children), MultiAggregationResult::Pending));
+        assert!(matches!(root.aggregate_multi(multi(root_self.0, root_self.1), "SELF".to_string(), &root_children), MultiAggregationResult::Pending));
+        let MultiAggregationResult::Complete(tree_multi) = root.aggregate_multi_partial(sums_b.clone(), "node-b".to_string(), &root_children) else {
+            panic!("❌ Root should complete the multi-layer batch once every subtree reported");
+        };
+        assert_eq!(tree_multi.epoch, 1);
+        assert_eq!(tree_multi.updates[0].weight_grad, flat.weight_grad);
+        assert_eq!(tree_multi.updates[1].bias_grad, flat.bias_grad.iter().map(|x| -x).collect::<Vec<_>>());
+
+        // 4. 落后纪元的部分和被丢弃 (单层与多层)
+        let stale = PartialGradientSum { epoch: 0, ..sums_b[0].clone() };
+        assert!(matches!(root.aggregate_partial_sum(stale.clone(), "node-b".to_string(), &root_children), AggregationResult::Stale));
+        assert!(matches!(root.aggregate_multi_partial(vec![stale], "node-b".to_string(), &root_children), MultiAggregationResult::Stale));
+    }
+
+    /// 🧪 Test 22: Tagged Wire Frames (显式标签与滚动升级)
+    /// 包帧以显式标签开头；来自更新版本对端的未知标签 (带长度前缀的新变体) 被 `decode` 优雅跳过，
+    /// 不影响随后的已知包；损坏的帧仍报错。各变体 (含嵌套的 ErrorCode / FoldMode) 按显式标签往返。
+    #[test]
+    fn test_unknown_wire_tag_is_skipped() {
+        println!("🧪 [Test] Tagged Wire Frames...");
+
+        let handshake = PacketType::Handshake { node_id: "worker-01".to_string(), protocol_ver: 3 };
+        let leave = PacketType::Leave { node_id: "worker-01".to_string() };
+        let bytes = handshake.to_bytes().unwrap();
+        assert_eq!(PacketType::peek_tag(&bytes), Some(0));
+        assert_eq!(PacketType::peek_tag(&leave.to_bytes().unwrap()), Some(7));
+        assert_eq!(bytes.len(), handshake.wire_size());
+
+        // 更新版本的节点发来一个新变体 (标签 99，负载为任意字段)
+        let payload = bincode::serialize(&("future-node", 42u64, vec![1.0f32; 3])).unwrap();
+        let mut future = 99u32.to_le_bytes().to_vec();
+        future.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        future.extend_from_slice(&payload);
+
+        assert!(matches!(PacketType::decode(&future), Ok(None)), "❌ Unknown tag should be skipped, not fail");
+        let err = PacketType::from_bytes(&future).unwrap_err();
+        assert!(err.contains("Unknown packet tag 99"), "❌ Unexpected error: {}", err);
+
+        // 跳过之后，已知的包照常解码
+        let Ok(Some(PacketType::Handshake { node_id, protocol_ver })) = PacketType::decode(&bytes) else {
+            panic!("❌ Known packet failed to decode after skipping an unknown one");
+        };
+        assert_eq!((node_id.as_str(), protocol_ver), ("worker-01", 3));
+
+        // 损坏的帧 (截断 / 长度不符) 是错误，而不是 "未知"
+        assert!(PacketType::decode(&future[..5]).is_err());
+        assert!(PacketType::decode(&future[..future.len() - 1]).is_err());
+
+        // 每个变体按自己的标签往返；嵌套枚举同样使用显式标签
+        use crate::net::wire::FoldMode;
+        let samples = vec![
+            PacketType::Error { code: ErrorCode::UnsupportedPacket, message: "nope".to_string() },
+            PacketType::FoldedInferenceRequest { request_id: 5, tokens: vec![Vector::zeros()], mode: FoldMode::Space },
+            PacketType::InferencePipelineRequest { request_id: 6, input: Vector::zeros(), next_layer: 2 },
+            PacketType::FingerprintExchange { node_id: "w".to_string(), epoch: 3, fingerprint: 9 },
+            PacketType::SyncRequest { node_id: "w".to_string(), last_epoch: 4 },
+            PacketType::Heartbeat { node_id: "w".to_string(), epoch: 8, load: 0.5 },
+            PacketType::PeerDiscovery {
+                sender_id: "w".to_string(),
+                peers: vec![crate::net::wire::PeerBrief {
+                    id: "ps".to_string(),
+                    address: "10.0.0.1:5000".to_string(),
+                    role: NodeRole::ParameterServer,
+                    layers: Some(0..4),
+                }],
+            },
+        ];
+        for packet in samples {
+            let bytes = packet.to_bytes().unwrap();
+            assert_eq!(PacketType::peek_tag(&bytes), Some(packet.wire_tag()));
+            let decoded = PacketType::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.kind(), packet.kind());
+            assert_eq!(decoded.to_bytes().unwrap(), bytes, "❌ {} did not round-trip", packet.kind());
+        }
+        assert_eq!((ErrorCode::UnsupportedPacket.wire_tag(), FoldMode::Space.wire_tag()), (3, 1));
+        assert_eq!(bincode::serialize(&ErrorCode::RateLimited).unwrap(), 1u32.to_le_bytes());
+        assert!(bincode::deserialize::<FoldMode>(&7u32.to_le_bytes()).is_err(), "❌ Unknown FoldMode tag must be rejected");
+        assert_eq!(bincode::serialize(&NodeRole::ParameterServer).unwrap(), 1u32.to_le_bytes());
+    }
+
+    /// 🧪 Test 23: Backward Reasoning (逆向推理)
+    /// 对推理回执的 output_state 应用逻辑门的逆变换，恢复出原始的 input_state。
+    #[tokio::test]
+    async fn test_inverse_recovers_inference_premise() {
+        use std::sync::Arc;
+        use crate::core::neuron::HTPNeuron;
+        use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+
+        println!("🧪 [Test] Backward Reasoning via AffineTuple::inverse...");
+
+        let gate = HTPNeuron::with_weights(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 70),
+            ConceptEmbedder::embed_token(70).scale(0.1),
+        );
+        let worker = HTPNode::new("worker-01".to_string(), NodeRole::Worker, 1);
+        worker.model.store(Arc::new(vec![gate.clone()]));
+
+        let premise = ConceptEmbedder::embed_token(123);
+        let request = PacketType::InferenceRequest { request_id: 1, input_state: premise.clone() };
+        let conclusion = match worker.process_packet(request).await {
+            Some(PacketType::InferenceResponse { output_state, .. }) => output_state,
+            other => panic!("❌ Unexpected response: {:?}", other),
+        };
+
+        let inverse = gate.logic_gate().inverse().expect("Xavier gate should be invertible");
+        let mut backward = HTPNeuron::with_weights(inverse.linear, inverse.translation);
+        let recovered = backward.absorb(&conclusion);
+        let err = recovered.sub(&premise).norm();
+        println!("   > ||A⁻¹(A(x)) - x|| = {:.3e}", err);
+        assert!(err < 1e-3, "❌ Backward reasoning did not recover the premise ({})", err);
+    }
+}
@@ -0,0 +1,211 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::affine::AffineTuple;
+    use crate::core::algebra::{Float, Matrix, Vector};
+    use crate::core::oracle::{LogicOracle, Reduction, BatchLoss, VerifyReport, SOLVER_PROXIMITY_WEIGHT};
+
+    /// 🧪 Test 1: Orthogonal Premise Batch (正交前提批量生成)
+    /// 生成的前提必须两两正交且为单位长度。
+    #[test]
+    fn test_genesis_premise_batch_is_orthonormal() {
+        println!("🧪 [Test] Orthogonal Premise Batch...");
+
+        let batch = LogicOracle::genesis_premise_batch(2025, 32);
+        assert_eq!(batch.len(), 32);
+
+        for (i, a) in batch.iter().enumerate() {
+            assert!((a.norm() - 1.0).abs() < 1e-4, "❌ Premise {} is not unit length", i);
+            for b in batch.iter().skip(i + 1) {
+                let dot: Float = a.data.iter().zip(&b.data).map(|(x, y)| x * y).sum();
+                assert!(dot.abs() < 1e-4, "❌ Premises are not orthogonal (dot = {})", dot);
+            }
+        }
+    }
+
+    /// 🧪 Test 2: Batch Loss Reduction (批量归约)
+    /// Mean == Sum / n，None 返回逐样本 Loss。
+    #[test]
+    fn test_batch_loss_reduction() {
+        println!("🧪 [Test] Batch Loss Reduction...");
+
+        let preds = LogicOracle::genesis_premise_batch(7, 4);
+        let targets: Vec<Vector> = preds.iter().map(|p| p.scale(0.5)).collect();
+
+        let sum = LogicOracle::batch_loss(&preds, &targets, Reduction::Sum).value();
+        let mean = LogicOracle::batch_loss(&preds, &targets, Reduction::Mean).value();
+        assert!((mean - sum / 4.0).abs() < 1e-6, "❌ Mean ({}) != Sum / n ({})", mean, sum / 4.0);
+
+        match LogicOracle::batch_loss(&preds, &targets, Reduction::None) {
+            BatchLoss::PerExample(losses) => {
+                assert_eq!(losses.len(), 4);
+                // 单位向量与其一半的距离平方 = 0.25
+                assert!(losses.iter().all(|l| (l - 0.25).abs() < 1e-4));
+            }
+            other => panic!("❌ Expected per-example losses, got {:?}", other),
+        }
+
+        assert_eq!(LogicOracle::batch_loss(&[], &[], Reduction::Mean), BatchLoss::Reduced(0.0));
+    }
+
+    /// 🧪 Test 3: Shape-Agnostic Solver (任意维度求解器)
+    /// 在 16 维方阵与 3x5 矩形门上，ΔW 的形状跟随 target x input，且一步更新后命中目标。
+    #[test]
+    fn test_ideal_update_small_and_rectangular_dims() {
+        println!("🧪 [Test] Ideal Update at Non-Manifold Dimensions...");
+
+        for (out_dim, in_dim) in [(16, 16), (3, 5)] {
+            let input = Vector { data: (0..in_dim).map(|j| 1.0 + j as Float * 0.25).collect() };
+            let target = Vector { data: (0..out_dim).map(|i| (i as Float).sin()).collect() };
+            let gate = AffineTuple::new(
+                Matrix::new(out_dim, in_dim, vec![0.1; out_dim * in_dim]),
+                Vector { data: vec![0.05; out_dim] },
+            );
+
+            let delta = LogicOracle::compute_ideal_update(&input, &target, &gate);
+            assert_eq!((delta.rows, delta.cols), (out_dim, in_dim));
+
+            let updated = AffineTuple::new(gate.linear.add(&delta), gate.translation.clone());
+            let pred = updated.linear.matmul_vec(&input).add(&updated.translation);
+            assert!(LogicOracle::calculate_loss(&pred, &target) < 1e-6, "❌ Solver missed target at {}x{}", out_dim, in_dim);
+        }
+    }
+
+    /// 🧪 Test 4: Identity-Regularized Solver (恒等先验)
+    /// 同样的误差下，带恒等先验的更新得到的新权重谱范数更小，且仍然命中目标。
+    #[test]
+    fn test_identity_regularization_shrinks_spectral_norm() {
+        use crate::core::primes::WeightInitializer;
+
+        println!("🧪 [Test] Identity-Regularized Solver...");
+
+        let dim = 32;
+        let input = Vector { data: (0..dim).map(|j| ((j as Float) * 0.7).sin()).collect() };
+        let target = Vector { data: (0..dim).map(|i| ((i as Float) * 0.3).cos()).collect() };
+        let gate = AffineTuple::new(
+            WeightInitializer::init_matrix(dim, dim, 17).scale(2.0),
+            Vector { data: vec![0.0; dim] },
+        );
+
+        let plain = gate.linear.add(&LogicOracle::compute_ideal_update(&input, &target, &gate));
+        let regularized = gate.linear.add(&LogicOracle::compute_ideal_update_regularized(&input, &target, &gate, 1.0, SOLVER_PROXIMITY_WEIGHT));
+
+        let plain_norm = plain.estimate_spectral_norm(50);
+        let reg_norm = regularized.estimate_spectral_norm(50);
+        println!("   > Spectral Norm: plain {:.3} vs regularized {:.3}", plain_norm, reg_norm);
+        assert!(reg_norm < plain_norm, "❌ Identity prior did not shrink the spectral norm");
+
+        // 先验与拟合之间存在折衷 (残差 ∝ μ / (μ + ||x||²))，但误差仍显著下降
+        let initial_loss = LogicOracle::calculate_loss(&gate.linear.matmul_vec(&input), &target);
+        let reg_loss = LogicOracle::calculate_loss(&regularized.matmul_vec(&input), &target);
+        println!("   > Loss: {:.3} -> {:.3e}", initial_loss, reg_loss);
+        assert!(reg_loss < 0.1 * initial_loss, "❌ Regularized solve barely reduced the error");
+
+        // μ = 0 与原求解器一致
+        let zero = LogicOracle::compute_ideal_update_regularized(&input, &target, &gate, 0.0, SOLVER_PROXIMITY_WEIGHT);
+        assert_eq!(zero, LogicOracle::compute_ideal_update(&input, &target, &gate));
+    }
+
+    /// 🧪 Test 5: Graded Identity Prior (先验强度连续可调)
+    /// 接近项取单位权重时，正交补上的拉回比例为 μ/(1 + μ)：
+    /// 中等强度的 μ 得到介于无先验与强先验之间的谱范数，而不是 "一开即重置为 I"。
+    #[test]
+    fn test_intermediate_identity_weight_gives_intermediate_norm() {
+        use crate::core::primes::WeightInitializer;
+
+        println!("🧪 [Test] Graded Identity Prior...");
+
+        let dim = 32;
+        let input = Vector { data: (0..dim).map(|j| ((j as Float) * 0.7).sin()).collect() };
+        let target = Vector { data: (0..dim).map(|i| ((i as Float) * 0.3).cos()).collect() };
+        let gate = AffineTuple::new(
+            WeightInitializer::init_matrix(dim, dim, 17).scale(2.0),
+            Vector { data: vec![0.0; dim] },
+        );
+
+        let norm_at = |mu: Float| {
+            let delta = LogicOracle::compute_ideal_update_regularized(&input, &target, &gate, mu, SOLVER_PROXIMITY_WEIGHT);
+            gate.linear.add(&delta).estimate_spectral_norm(200)
+        };
+        let norms: Vec<Float> = [0.0, 0.25, 1.0, 4.0, 100.0].iter().map(|&mu| norm_at(mu)).collect();
+        println!("   > Spectral Norms (μ = 0, 0.25, 1, 4, 100): {:?}", norms);
+
+        assert!(norms.windows(2).all(|w| w[1] < w[0]), "❌ Spectral norm should shrink monotonically with μ: {:?}", norms);
+        let (plain, strong) = (norms[0], norms[4]);
+        let gap = plain - strong;
+        assert!(norms[2] > strong + 0.2 * gap && norms[2] < plain - 0.2 * gap,
+            "❌ μ = 1 should land between no prior ({}) and a strong prior ({}): {}", plain, strong, norms[2]);
+    }
+
+    /// 🧪 Test 6: Huber Loss (鲁棒损失)
+    /// 小误差处与 ½r² 一致 (二次)，大误差处随 |r| 线性增长，梯度被截断为 ±δ。
+    #[test]
+    fn test_huber_loss_quadratic_then_linear() {
+        println!("🧪 [Test] Huber Loss...");
+
+        let target = Vector { data: vec![0.0; 3] };
+        let delta = 1.0;
+
+        // 1. 二次区: r = (0.5, -0.2, 0) -> ½ (0.25 + 0.04) = 0.145，梯度 = r
+        let small = Vector { data: vec![0.5, -0.2, 0.0] };
+        let (loss, grad) = LogicOracle::huber_loss(&small, &target, delta);
+        assert!((loss - 0.145).abs() < 1e-6, "❌ Quadratic region loss: {}", loss);
+        assert_eq!(grad, small);
+
+        // 2. 线性区: 误差翻倍，损失大约翻倍 (而不是 4 倍)，梯度恒为 ±δ
+        let (l10, g10) = LogicOracle::huber_loss(&Vector { data: vec![10.0, -10.0, 0.0] }, &target, delta);
+        let (l20, g20) = LogicOracle::huber_loss(&Vector { data: vec![20.0, -20.0, 0.0] }, &target, delta);
+        assert_eq!(l10, 2.0 * (10.0 - 0.5));
+        assert_eq!(l20 - l10, 2.0 * 10.0 * delta, "❌ Loss must grow linearly beyond delta");
+        assert_eq!(g10.data, vec![1.0, -1.0, 0.0]);
+        assert_eq!(g20, g10);
+
+        // 3. 离群值的影响远小于平方误差
+        let outlier = Vector { data: vec![0.0, 0.0, 100.0] };
+        let (robust, _) = LogicOracle::huber_loss(&outlier, &target, delta);
+        assert!(robust < LogicOracle::calculate_loss(&outlier, &target) / 50.0);
+    }
+
+    /// 🧪 Test 7: Batch Verification (批量零幻觉校验)
+    /// 两个样本通过、两个失败 (其中一个为 NaN)，报告逐项正确；空 Batch 全部通过。
+    #[test]
+    fn test_batch_verify_report() {
+        println!("🧪 [Test] Batch Verification Report...");
+
+        let v = |data: Vec<Float>| Vector { data };
+        let preds = vec![v(vec![1.0, 0.0]), v(vec![0.0, 2.0]), v(vec![0.5, 0.5]), v(vec![Float::NAN, 0.0])];
+        let targets = vec![v(vec![1.0, 0.0]), v(vec![0.0, 0.0]), v(vec![0.5, 0.51]), v(vec![0.0, 0.0])];
+
+        let report = LogicOracle::batch_verify(&preds, &targets, 1e-3);
+        assert_eq!((report.passed, report.failed), (2, 2));
+        assert!(!report.all_passed());
+        assert_eq!(report.failures[0], (1, 4.0));
+        assert_eq!(report.failures[1].0, 3);
+        assert!(report.failures[1].1.is_nan());
+
+        assert_eq!(LogicOracle::batch_verify(&[], &[], 1e-3), VerifyReport::default());
+    }
+
+    /// 🧪 Test 7: Synthetic Premise / Target (合成数据)
+    /// genesis_premise 位于单位球面上且确定；genesis_target 确定且落在前提附近 (距离 0.1)。
+    #[test]
+    fn test_genesis_premise_is_unit_and_target_is_nearby() {
+        println!("🧪 [Test] Genesis Premise / Target...");
+
+        for seed in [0, 1, 42, u64::MAX] {
+            let premise = LogicOracle::genesis_premise(seed);
+            assert!((premise.norm() - 1.0).abs() < 1e-5, "❌ Premise {} is not a unit vector: {}", seed, premise.norm());
+            assert_eq!(premise, LogicOracle::genesis_premise(seed), "❌ Premise must be deterministic");
+
+            let target = LogicOracle::genesis_target(&premise, seed);
+            assert_eq!(target, LogicOracle::genesis_target(&premise, seed), "❌ Target must be deterministic");
+            let distance = target.sub(&premise).norm();
+            assert!((distance - 0.1).abs() < 1e-4, "❌ Target should sit 0.1 from the premise, got {}", distance);
+        }
+        assert_ne!(
+            LogicOracle::genesis_target(&LogicOracle::genesis_premise(3), 1),
+            LogicOracle::genesis_target(&LogicOracle::genesis_premise(3), 2),
+        );
+    }
+}
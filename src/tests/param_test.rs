@@ -0,0 +1,174 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, Matrix, Vector};
+    use crate::core::affine::AffineTuple;
+    use crate::core::neuron::HTPNeuron;
+    use crate::core::param::{HyperParams, PartialHyperParams};
+    use crate::core::primes::WeightInitializer;
+    use crate::topology::folding::HyperFolder;
+
+    /// 🧪 Test 1: Per-Layer Lipschitz Budget (逐层稳定性预算)
+    /// 逐层上界的 depth 次方必须还原端到端目标；超出预算的配置只警告，不拒绝。
+    #[test]
+    fn test_per_layer_bound_composes_to_target() {
+        println!("🧪 [Test] Per-Layer Lipschitz Bound...");
+
+        for params in [HyperParams::default(), HyperParams::high_fidelity(), HyperParams::fast_inference()] {
+            let k = params.per_layer_lipschitz_bound();
+            let total = k.powi(params.depth as i32);
+            assert!((total - params.end_to_end_bound).abs() < 1e-4, "❌ K^d = {} != target {}", total, params.end_to_end_bound);
+            // 预设配置本身都在预算之内
+            assert!(params.effective_lipschitz_budget() <= params.end_to_end_bound);
+        }
+
+        // 深网络 + 宽松单层上界: 1.5^24 远超目标，但 validate 仍通过
+        let deep = HyperParams { depth: 24, lipschitz_bound: 1.5, ..HyperParams::default() };
+        assert!(deep.effective_lipschitz_budget() > deep.end_to_end_bound);
+        assert!(deep.per_layer_lipschitz_bound() < deep.lipschitz_bound);
+        assert!(deep.validate().is_ok());
+    }
+
+    /// 🧪 Test 2: Linear Proof Mode (线性证明模式)
+    /// 证明模式拒绝奇异的逻辑门；由可逆门折叠出的 Root 可以被精确逆推回输入。
+    #[test]
+    fn test_proof_mode_fold_is_invertible() {
+        println!("🧪 [Test] Linear Proof Mode...");
+
+        let proof = HyperParams { linear_proof_mode: true, ..HyperParams::default() };
+        let dim = 16;
+        let eye = || {
+            let mut m = Matrix::new(dim, dim, vec![0.0; dim * dim]);
+            (0..dim).for_each(|i| m.data[i * dim + i] = 1.0);
+            m
+        };
+
+        // 1. 奇异门 (第一行全零) 只在证明模式下被拒绝
+        let mut singular = eye();
+        singular.data[..dim].fill(0.0);
+        let singular = AffineTuple::new(singular, Vector { data: vec![0.0; dim] });
+        assert!(HyperParams::default().admit_gate(&singular).is_ok());
+        assert!(proof.admit_gate(&singular).is_err());
+
+        let mut neuron = HTPNeuron::new();
+        assert!(neuron.set_logic_gate_checked(singular, &proof).is_err());
+        assert_eq!(neuron.logic_gate(), &AffineTuple::identity(), "❌ Rejected gate must not be installed");
+
+        // 2. 可逆门 (I + 0.1·Xavier) 组成的时间线
+        let gates: Vec<AffineTuple> = (0..4u64)
+            .map(|seed| AffineTuple::new(
+                eye().add(&WeightInitializer::init_matrix(dim, dim, seed).scale(0.1)),
+                Vector { data: (0..dim).map(|i| (seed as Float + i as Float).sin()).collect() },
+            ))
+            .collect();
+        for gate in &gates {
+            proof.admit_gate(gate).expect("well-conditioned gate");
+        }
+
+        // 3. 折叠 -> 前向 -> 逆推
+        let root = HyperFolder::fold_timeline(&gates).unwrap();
+        let x = Vector { data: (0..dim).map(|i| i as Float * 0.25 - 2.0).collect() };
+        let y = root.linear.matmul_vec(&x).add(&root.translation);
+
+        let inv = root.inverse().expect("fold of invertible gates is invertible");
+        let recovered = inv.linear.matmul_vec(&y).add(&inv.translation);
+        let err = recovered.sub(&x).norm();
+        println!("   > ||A⁻¹(A(x)) - x||: {:.3e}", err);
+        assert!(err < 1e-4, "❌ Proof-mode fold could not be inverted exactly");
+    }
+
+    /// 🧪 Test 3: Proof Mode Guards Every Write (证明模式守住所有写入路径)
+    /// 开启 `linear_proof_mode` 后，优化器、Solver 与参数同步都不能装入不可逆的 W；
+    /// 关闭时同样的更新照常写入 (对照组)。
+    #[tokio::test]
+    async fn test_linear_proof_mode_guards_optimizer_solver_and_sync() {
+        use crate::core::algebra::MANIFOLD_DIM;
+        use crate::net::node::{HTPNode, NodeRole};
+        use crate::net::wire::{ErrorCode, LayerState, ModelSnapshot, PacketType};
+        use crate::train_loop::{AdamOptimizer, SimpleOptimizer, TrainingLoop};
+
+        println!("🧪 [Test] Linear Proof Mode Write Guards...");
+
+        // 只在 (0,0) 上有梯度: lr = 1 时恰好把 W[0][0] 推到 0 → 奇异
+        let mut data = vec![0.0; MANIFOLD_DIM * MANIFOLD_DIM];
+        data[0] = 1.0;
+        let grad_w = Matrix::new(MANIFOLD_DIM, MANIFOLD_DIM, data);
+        let grad_b = Vector::zeros();
+
+        // 1. SGD
+        let mut guarded = HTPNeuron::new();
+        SimpleOptimizer::new(1.0).with_linear_proof_mode(true).step_neuron(0, &mut guarded, &grad_w, &grad_b);
+        assert_eq!(guarded.logic_gate().linear, Matrix::identity(), "❌ SGD installed a singular gate in proof mode");
+        let mut free = HTPNeuron::new();
+        SimpleOptimizer::new(1.0).step_neuron(0, &mut free, &grad_w, &grad_b);
+        assert!(free.logic_gate().inverse().is_err(), "❌ Control SGD step should have produced a singular gate");
+
+        // 2. Adam: 首步 ≈ lr · sign(g)
+        let mut guarded = HTPNeuron::new();
+        AdamOptimizer::new(1.0).with_linear_proof_mode(true).step_neuron(0, &mut guarded, &grad_w, &grad_b);
+        assert_eq!(guarded.logic_gate().linear, Matrix::identity(), "❌ Adam installed a singular gate in proof mode");
+        let mut free = HTPNeuron::new();
+        AdamOptimizer::new(1.0).step_neuron(0, &mut free, &grad_w, &grad_b);
+        assert!(free.logic_gate().inverse().is_err(), "❌ Control Adam step should have produced a singular gate");
+
+        // 3. Solver: 大范数输入 x = 1000·e₀、目标 0 → W' = I - e₀e₀ᵀ (阻尼项被 ‖x‖² 淹没)
+        let mut x = vec![0.0; MANIFOLD_DIM];
+        x[0] = 1000.0;
+        let input = Vector::new(x);
+        let target = Vector::zeros();
+        let proof = HyperParams { linear_proof_mode: true, ..HyperParams::default() };
+
+        let mut guarded = HTPNeuron::new();
+        let mut trainer = TrainingLoop::new(proof.clone());
+        let loss = trainer.train_step_solver(&mut guarded, &input, &target);
+        assert!(loss > 0.0);
+        assert_eq!(guarded.logic_gate().linear, Matrix::identity(), "❌ Solver installed a singular gate in proof mode");
+        let mut free = HTPNeuron::new();
+        TrainingLoop::new(HyperParams::default()).train_step_solver(&mut free, &input, &target);
+        assert!(free.logic_gate().inverse().is_err(), "❌ Control solver step should have produced a singular gate");
+
+        // 4. 参数同步: 含奇异层的快照整体被拒，模型与纪元不变
+        let worker = HTPNode::from_params("worker-00".to_string(), NodeRole::Worker, &HyperParams { depth: 2, ..proof })
+            .expect("valid config");
+        let fingerprint = worker.fingerprint();
+        let epoch = worker.epoch();
+        let snapshot = ModelSnapshot::new(epoch + 1, vec![
+            LayerState { layer_index: 0, weights: Matrix::identity(), bias: Vector::zeros() },
+            LayerState { layer_index: 1, weights: Matrix::new(MANIFOLD_DIM, MANIFOLD_DIM, vec![0.0; MANIFOLD_DIM * MANIFOLD_DIM]), bias: Vector::zeros() },
+        ]);
+        let reply = worker.process_packet(PacketType::ParameterBroadcast(snapshot)).await;
+        assert!(
+            matches!(reply, Some(PacketType::Error { code: ErrorCode::InvalidRequest, .. })),
+            "❌ Singular snapshot was not rejected: {:?}", reply
+        );
+        assert_eq!(worker.fingerprint(), fingerprint, "❌ Rejected snapshot still modified the model");
+        assert_eq!(worker.epoch(), epoch, "❌ Rejected snapshot still advanced the epoch");
+    }
+
+    /// 🧪 Test 4: Layered Configuration (分层配置合并)
+    /// 覆盖只改变指定字段；diff 后再 merge 可还原目标配置；非法覆盖被拒绝。
+    #[test]
+    fn test_merge_applies_only_specified_overrides() {
+        println!("🧪 [Test] HyperParams Merge / Diff...");
+
+        let base = HyperParams::default();
+        let merged = base.merge(PartialHyperParams {
+            depth: Some(4),
+            learning_rate: Some(0.05),
+            ..PartialHyperParams::default()
+        }).expect("valid override");
+
+        assert_eq!(merged, HyperParams { depth: 4, learning_rate: 0.05, ..base.clone() });
+
+        // 空覆盖 = 原配置；diff 只包含变化的字段，且可往返
+        assert_eq!(base.merge(PartialHyperParams::default()).unwrap(), base);
+        let delta = base.diff(&merged);
+        assert_eq!(delta, PartialHyperParams { depth: Some(4), learning_rate: Some(0.05), ..PartialHyperParams::default() });
+        assert_eq!(base.merge(delta).unwrap(), merged);
+
+        // 合并后重新校验
+        let chaotic = PartialHyperParams { lipschitz_bound: Some(3.0), ..PartialHyperParams::default() };
+        assert!(base.merge(chaotic).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, MANIFOLD_DIM};
+    use crate::core::oracle::LogicOracle;
+    use crate::core::primes::WeightInitializer;
+    use crate::core::rng::{derive_seed, HtpRng};
+
+    /// 🧪 Test 1: Box-Muller Normality (高斯采样的统计检验)
+    /// 40000 个样本的均值 ≈ 0、方差 ≈ 1、偏度 ≈ 0、峰度 ≈ 3，
+    /// 且落在 ±1σ / ±2σ 内的比例符合 68-95 规则。
+    #[test]
+    fn test_gaussian_samples_are_standard_normal() {
+        println!("🧪 [Test] HtpRng::next_gaussian...");
+
+        let n = 40_000;
+        let mut rng = HtpRng::new(7);
+        let samples: Vec<f64> = (0..n).map(|_| rng.next_gaussian() as f64).collect();
+        assert!(samples.iter().all(|x| x.is_finite()));
+
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let moment = |k: i32| samples.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / n as f64;
+        let var = moment(2);
+        let skew = moment(3) / var.powf(1.5);
+        let kurtosis = moment(4) / (var * var);
+        let within = |k: f64| samples.iter().filter(|x| x.abs() < k).count() as f64 / n as f64;
+        println!("   > mean {:.4}, var {:.4}, skew {:.4}, kurtosis {:.4}, 1σ {:.4}, 2σ {:.4}",
+            mean, var, skew, kurtosis, within(1.0), within(2.0));
+
+        assert!(mean.abs() < 0.02, "❌ Mean drifted: {}", mean);
+        assert!((var - 1.0).abs() < 0.03, "❌ Variance is not 1: {}", var);
+        assert!(skew.abs() < 0.05, "❌ Distribution is skewed: {}", skew);
+        assert!((kurtosis - 3.0).abs() < 0.1, "❌ Kurtosis is not Gaussian: {}", kurtosis);
+        assert!((within(1.0) - 0.6827).abs() < 0.01);
+        assert!((within(2.0) - 0.9545).abs() < 0.005);
+
+        // 均匀采样的峰度约为 1.8，明显区别于高斯
+        let uniform: Vec<f64> = (0..n).map(|_| rng.next_uniform() as f64).collect();
+        let u_var = uniform.iter().map(|x| x * x).sum::<f64>() / n as f64;
+        let u_kurtosis = uniform.iter().map(|x| x.powi(4)).sum::<f64>() / n as f64 / (u_var * u_var);
+        assert!((u_kurtosis - 1.8).abs() < 0.1);
+
+        // 同一种子 => 同一序列
+        let mut a = HtpRng::new(99);
+        let mut b = HtpRng::new(99);
+        assert!((0..100).all(|_| a.next_gaussian() == b.next_gaussian()));
+    }
+
+    /// 🧪 Test 2: Gaussian Premise / Target / Xavier Normal (高斯合成数据与初始化)
+    /// 高斯前提位于单位球面上；高斯目标与前提的距离约为 0.1；Xavier Normal 的标准差为 sqrt(2 / (fan_in + fan_out))。
+    #[test]
+    fn test_gaussian_generators_match_intended_scale() {
+        println!("🧪 [Test] Gaussian Premise / Target / Xavier Normal...");
+
+        for seed in [0, 5, u64::MAX] {
+            let premise = LogicOracle::genesis_premise_gaussian(seed);
+            assert!((premise.norm() - 1.0).abs() < 1e-5);
+            assert_eq!(premise, LogicOracle::genesis_premise_gaussian(seed));
+
+            let target = LogicOracle::genesis_target_gaussian(&premise, seed);
+            let distance = target.sub(&premise).norm();
+            assert!((distance - 0.1).abs() < 0.01, "❌ Gaussian target distance {} is far from 0.1", distance);
+        }
+        assert_ne!(LogicOracle::genesis_premise_gaussian(1), LogicOracle::genesis_premise(1));
+
+        let w = WeightInitializer::init_matrix_gaussian(MANIFOLD_DIM, MANIFOLD_DIM, 3);
+        let n = w.data.len() as Float;
+        let mean = w.data.iter().sum::<Float>() / n;
+        let std = (w.data.iter().map(|x| (x - mean) * (x - mean)).sum::<Float>() / n).sqrt();
+        let expected = (2.0 / (2.0 * MANIFOLD_DIM as Float)).sqrt();
+        println!("   > Xavier normal std {:.5} (expected {:.5})", std, expected);
+        assert!(mean.abs() < 1e-3);
+        assert!((std / expected - 1.0).abs() < 0.01);
+    }
+
+    /// 🧪 Test 3: Derived Seeds (子种子派生)
+    /// (主种子, Epoch) 网格上的子种子两两不同 (`seed ^ epoch` 在 (1, 0) 与 (0, 1) 处撞车)，且可复现。
+    #[test]
+    fn test_derived_seeds_do_not_collide() {
+        use std::collections::HashSet;
+        println!("🧪 [Test] derive_seed...");
+
+        assert_ne!(derive_seed(1, 0), derive_seed(0, 1));
+
+        let seeds: HashSet<u64> = (0..64u64)
+            .flat_map(|seed| (0..64u64).map(move |epoch| derive_seed(seed, epoch)))
+            .collect();
+        assert_eq!(seeds.len(), 64 * 64, "❌ Derived seeds collided");
+        assert_eq!(derive_seed(42, 3), derive_seed(42, 3));
+    }
+
+    /// 🧪 Test 4: Shared Generator (初始化与扰动共用 HtpRng)
+    /// Xavier Uniform 与受控扰动的噪声逐位等于同一种子 HtpRng 的 U[-1, 1] 序列乘以各自的尺度。
+    #[test]
+    fn test_initializers_draw_from_htp_rng() {
+        use crate::core::neuron::HTPNeuron;
+        println!("🧪 [Test] WeightInitializer / perturb share HtpRng...");
+
+        let limit = (6.0 / (2.0 + 3.0) as Float).sqrt();
+        let mut rng = HtpRng::new(9);
+        let expected: Vec<Float> = (0..6).map(|_| rng.next_uniform() * limit).collect();
+        assert_eq!(WeightInitializer::init_matrix(2, 3, 9).data, expected);
+
+        let mut neuron = HTPNeuron::new();
+        let original = neuron.logic_gate().linear.data[0];
+        neuron.perturb(0.5, 11);
+        assert_eq!(neuron.logic_gate().linear.data[0], original + HtpRng::new(11).next_uniform() * 0.5);
+    }
+}
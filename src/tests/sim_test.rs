@@ -0,0 +1,60 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, Vector, MANIFOLD_DIM};
+    use crate::core::neuron::HTPNeuron;
+    use crate::core::primes::ConceptEmbedder;
+    use crate::net::sim::SimCluster;
+    use crate::net::wire::GradientUpdate;
+
+    /// 🧪 Test 1: Simulated Training Round (3 Worker / 1 PS 内存仿真)
+    /// 每个 Worker 持有一个样本 (x_i, y_i = x_i + b*)，以 ½||W·x + b - y||² 的梯度训练单层模型。
+    /// 每轮完整经过 Worker -> Aggregator -> PS -> Broadcast，所有 Worker 都必须与 PS 保持一致，
+    /// 且全局 Loss 收敛。
+    #[tokio::test]
+    async fn test_three_workers_one_ps_converge() {
+        println!("🧪 [Test] Simulated 3-Worker / 1-PS Training...");
+
+        let shift = Vector::new(vec![0.1; MANIFOLD_DIM]);
+        let samples: Vec<(Vector, Vector)> = (0..3)
+            .map(|i| {
+                let x = ConceptEmbedder::embed_token(100 + i);
+                let y = x.add(&shift);
+                (x, y)
+            })
+            .collect();
+
+        let residual = |model: &[HTPNeuron], (x, y): &(Vector, Vector)| -> Vector {
+            model[0].logic_gate().linear.matmul_vec(x).add(&model[0].logic_gate().translation).sub(y)
+        };
+        let loss = |model: &[HTPNeuron]| -> Float {
+            samples.iter()
+                .map(|s| residual(model, s).data.iter().map(|r| r * r).sum::<Float>())
+                .sum::<Float>() / samples.len() as Float
+        };
+
+        let mut cluster = SimCluster::new(3, 1, 0.5);
+        let initial = loss(&cluster.ps.model.load());
+
+        for round in 1..=40 {
+            let report = cluster.run_round(|idx, model| {
+                let r = residual(model, &samples[idx]);
+                let x = &samples[idx].0;
+                // dL/dW = r·x^T, dL/db = r
+                let weight_grad = r.data.iter()
+                    .flat_map(|&ri| x.data.iter().map(move |&xj| ri * xj))
+                    .collect();
+                vec![GradientUpdate { layer_index: 0, weight_grad, bias_grad: r.data, batch_size: 1 }]
+            }).await.expect("❌ Simulated round failed");
+
+            assert_eq!((report.epoch, report.contributors, report.synced_workers), (round, 3, 3));
+            assert!(cluster.in_sync(), "❌ Workers diverged from PS after round {}", round);
+        }
+
+        let trained = loss(&cluster.ps.model.load());
+        println!("   Loss: {:.6} -> {:.6}", initial, trained);
+        assert_eq!(cluster.ps.epoch(), 40);
+        assert!(trained < 1e-3 * initial, "❌ Simulated training did not converge ({} -> {})", initial, trained);
+    }
+}
@@ -0,0 +1,463 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, Matrix, Vector, MANIFOLD_DIM};
+    use crate::core::affine::AffineTuple;
+    use crate::core::primes::{ConceptEmbedder, WeightInitializer};
+    use crate::topology::folding::{FoldError, HyperFolder, NormGuard};
+    use crate::topology::merkle::{CausalTrace, OpType};
+    use crate::topology::tensor::{HyperTensor, MergeMode, TraceTooLarge};
+
+    fn timeline(len: usize) -> Vec<AffineTuple> {
+        (0..len)
+            .map(|i| AffineTuple::new(
+                WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 100 + i as u64),
+                ConceptEmbedder::embed_token(i as u32).scale(0.1),
+            ))
+            .collect()
+    }
+
+    /// 🧪 Test 1: Shard Merge == Single Fold (分片合并等价性)
+    /// 两个 Shard 分别折叠后 TimeCompose 合并，应与对拼接序列的一次性折叠一致。
+    #[test]
+    fn test_shard_merge_matches_single_fold() {
+        println!("🧪 [Test] Shard Merge vs Single Fold...");
+
+        let inputs = timeline(6);
+        let (head, tail) = inputs.split_at(3);
+
+        let shard_a = HyperTensor::forward(head, true).unwrap();
+        let shard_b = HyperTensor::forward(tail, true).unwrap();
+        let merged = shard_a.merge(&shard_b, MergeMode::TimeCompose);
+        let single = HyperTensor::forward(&inputs, false).unwrap();
+
+        let diff = merged.root.linear.data.iter().zip(&single.root.linear.data)
+            .chain(merged.root.translation.data.iter().zip(&single.root.translation.data))
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        println!("   > Max |merged - single|: {:.3e}", diff);
+        assert!(diff < 1e-4, "❌ Shard merge diverged from single fold");
+
+        // 两条 Trace 被拼成一个 DAG，末端是指向两个 Shard Root 的 Join 节点
+        let trace = merged.trace.expect("❌ Training merge must keep the trace");
+        let (len_a, len_b) = (shard_a.complexity(), shard_b.complexity());
+        assert_eq!(trace.nodes.len(), len_a + len_b + 1);
+        let join = trace.nodes.last().unwrap();
+        assert!(matches!(join.op, OpType::TimeCompose));
+        assert_eq!(join.parents, vec![len_a - 1, len_a + len_b - 1]);
+
+        // 梯度可以穿过 Join 节点流回每一个叶子
+        let grads = trace.backward(&AffineTuple::identity());
+        assert_eq!(grads.len(), trace.nodes.len());
+        assert!(grads[len_a].linear.data.iter().any(|g| g.abs() > 0.0), "❌ Gradient did not reach shard B");
+
+        // SpaceMerge: Root 为两分支均值
+        let fused = shard_a.merge(&shard_b, MergeMode::SpaceMerge);
+        let expected = shard_a.root.commutative_merge(&shard_b.root).unwrap();
+        assert_eq!(fused.root.translation.data, expected.translation.data);
+        assert!(matches!(fused.trace.unwrap().nodes.last().unwrap().op, OpType::SpaceMerge));
+    }
+
+    /// 🧪 Test 2: Root Equality Ignores Trace (只比较 Root)
+    /// 同一序列的不同折叠路径 (分片合并 / 一次折叠 / 推理模式) 结论相同即视为相等。
+    #[test]
+    fn test_root_approx_eq_ignores_trace() {
+        println!("🧪 [Test] HyperTensor Root Equality...");
+
+        let inputs = timeline(4);
+        let (head, tail) = inputs.split_at(1);
+
+        let single = HyperTensor::forward(&inputs, true).unwrap();
+        let merged = HyperTensor::forward(head, true).unwrap()
+            .merge(&HyperTensor::forward(tail, true).unwrap(), MergeMode::TimeCompose);
+        let fast = HyperTensor::forward(&inputs, false).unwrap();
+
+        // Trace 结构不同 (或根本没有 Trace)
+        let (t1, t2) = (single.trace.as_ref().unwrap(), merged.trace.as_ref().unwrap());
+        assert!(t1.nodes.iter().zip(&t2.nodes).any(|(a, b)| a.parents != b.parents));
+        assert!(fast.trace.is_none());
+
+        assert!(single.root_approx_eq(&merged, 1e-4));
+        assert!(single.root_approx_eq(&fast, 1e-4));
+
+        // 不同的序列结论不同
+        let other = HyperTensor::forward(&inputs[1..], false).unwrap();
+        assert!(!single.root_approx_eq(&other, 1e-4));
+    }
+
+    /// 🧪 Test 3: Trace Statistics (磁带规模统计)
+    /// 3 个叶子 + 1 次 Compose + 1 次 Merge，深度为 2，内存至少包含 5 个 D x D 矩阵。
+    #[test]
+    fn test_trace_stats_counts() {
+        println!("🧪 [Test] CausalTrace Stats...");
+
+        let mut trace = CausalTrace::new();
+        let a = trace.push_leaf(AffineTuple::identity());
+        let b = trace.push_leaf(AffineTuple::identity());
+        let c = trace.push_leaf(AffineTuple::identity());
+        let ab = trace.push_compose(a, b, AffineTuple::identity());
+        trace.push_n_ary_merge(vec![ab, c], AffineTuple::identity());
+
+        let stats = trace.stats();
+        assert_eq!(stats.leaf_count, 3);
+        assert_eq!(stats.compose_count, 1);
+        assert_eq!(stats.merge_count, 1);
+        assert_eq!(stats.total_nodes, 5);
+        assert_eq!(stats.max_depth, 2);
+
+        let matrix_bytes = MANIFOLD_DIM * MANIFOLD_DIM * std::mem::size_of::<f32>();
+        assert!(stats.estimated_bytes >= 5 * matrix_bytes);
+        assert!(stats.estimated_bytes < 6 * matrix_bytes);
+
+        // 空磁带
+        assert_eq!(CausalTrace::new().stats().max_depth, 0);
+    }
+
+    /// 🧪 Test 4: Guarded Time Fold (折叠溢出检测)
+    /// 每步放大 2 倍的链条必须在中途 (而非折叠结束后) 触发 Overflow；
+    /// 每步缩小一半的链条触发 Underflow；宽松护栏下结果与无护栏折叠一致。
+    #[test]
+    fn test_guarded_fold_detects_overflow_early() {
+        println!("🧪 [Test] Guarded Fold Overflow...");
+
+        // 小维度 (16) 以保持测试快速
+        let dim = 16;
+        let scaled_identity = |k: f32| {
+            let mut data = vec![0.0; dim * dim];
+            for i in 0..dim {
+                data[i * dim + i] = k;
+            }
+            AffineTuple::new(Matrix::new(dim, dim, data), Vector { data: vec![0.0; dim] })
+        };
+        let guard = NormGuard::new(1e-6, 1e6);
+
+        // 1. Overflow: ||2^k I||_F = 4 * 2^k，在 k ≈ 18 时越界，远早于 64 步
+        let expansive = vec![scaled_identity(2.0); 64];
+        match HyperFolder::fold_timeline_guarded(&expansive, &guard) {
+            Err(FoldError::Overflow { steps, norm }) => {
+                println!("   > Overflow at steps {:?} (norm = {:.3e})", steps, norm);
+                assert!(norm > guard.ceiling);
+                assert!(steps.len() < expansive.len(), "❌ Overflow was only detected at the final compose");
+            }
+            other => panic!("❌ Expected overflow, got {:?}", other),
+        }
+
+        // 2. Underflow
+        let contractive = vec![scaled_identity(0.5); 64];
+        assert!(matches!(
+            HyperFolder::fold_timeline_guarded(&contractive, &guard),
+            Err(FoldError::Underflow { .. })
+        ));
+
+        // 3. 宽松护栏: 与 fold_timeline 一致
+        let inputs = timeline(4);
+        let wide = NormGuard::new(0.0, Float::INFINITY);
+        let guarded = HyperFolder::fold_timeline_guarded(&inputs, &wide).unwrap().unwrap();
+        let unguarded = HyperFolder::fold_timeline(&inputs).unwrap();
+        let diff = guarded.linear.data.iter().zip(&unguarded.linear.data)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(diff < 1e-5, "❌ Guarded fold diverged from plain fold ({})", diff);
+        assert_eq!(HyperFolder::fold_timeline_guarded(&[], &guard), Ok(None));
+    }
+
+    /// 🧪 Test 5: Parallel Backward == Serial Backward (并行反向传播一致性)
+    /// 无论是折叠产生的树形磁带，还是带共享节点 / 空间融合的 DAG，两种实现的梯度必须完全一致。
+    #[test]
+    fn test_backward_parallel_matches_serial() {
+        println!("🧪 [Test] Parallel vs Serial Backward...");
+
+        // 1. HyperTensor 折叠产生的树
+        let tensor = HyperTensor::forward(&timeline(5), true).unwrap();
+        let trace = tensor.trace.as_ref().expect("training mode records a trace");
+        let grad_output = AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 7),
+            ConceptEmbedder::embed_token(3),
+        );
+        assert_eq!(trace.backward_parallel(&grad_output), trace.backward(&grad_output));
+
+        // 2. 手工构造的 DAG (小维度): 叶子 1 被两个 Compose 共享，随后空间融合再复合
+        let dim = 16;
+        let tuple = |seed: u64| AffineTuple::new(
+            WeightInitializer::init_matrix(dim, dim, seed),
+            Vector { data: (0..dim).map(|i| (seed as Float + i as Float).cos()).collect() },
+        );
+        let mut dag = CausalTrace::new();
+        let leaves: Vec<usize> = (0..4).map(|i| dag.push_leaf(tuple(i))).collect();
+        let value = |dag: &CausalTrace, id: usize| dag.nodes[id].value.clone();
+
+        let ab = value(&dag, leaves[1]).compose_unchecked(&value(&dag, leaves[0]));
+        let ab_id = dag.push_compose(leaves[0], leaves[1], ab);
+        let bc = value(&dag, leaves[2]).compose_unchecked(&value(&dag, leaves[1]));
+        let bc_id = dag.push_compose(leaves[1], leaves[2], bc);
+        let merged = value(&dag, ab_id).add_components(&value(&dag, bc_id)).scale(0.5);
+        let merged_id = dag.push_n_ary_merge(vec![ab_id, bc_id], merged);
+        let root = value(&dag, leaves[3]).compose_unchecked(&value(&dag, merged_id));
+        dag.push_compose(merged_id, leaves[3], root);
+
+        let grad = tuple(99);
+        let serial = dag.backward(&grad);
+        let parallel = dag.backward_parallel(&grad);
+        assert_eq!(parallel, serial, "❌ Parallel backward diverged on a shared-node DAG");
+        assert!(serial[leaves[1]].linear.frobenius_norm() > 0.0);
+    }
+
+    /// 🧪 Test 6: Trace Size Cap (训练模式节点上限)
+    /// 超过上限的输入在分配之前即返回 TraceTooLarge；推理模式不受影响，恰好达到上限的输入正常折叠。
+    #[test]
+    fn test_forward_rejects_oversized_trace() {
+        println!("🧪 [Test] HyperTensor Trace Cap...");
+
+        let inputs = timeline(4); // 4 个叶子 + 3 次 Compose = 7 个节点
+        assert_eq!(
+            HyperTensor::forward_with_limit(&inputs, true, 6).unwrap_err(),
+            TraceTooLarge { required: 7, limit: 6 }
+        );
+
+        assert!(HyperTensor::forward_with_limit(&inputs, false, 6).is_ok());
+        let exact = HyperTensor::forward_with_limit(&inputs, true, 7).expect("7 nodes fit the cap");
+        assert_eq!(exact.complexity(), 7);
+    }
+
+    /// 🧪 Test 7: Chunked Time Fold (分块折叠一致性)
+    /// 无论 chunk_size 取何值 (含 0、1、整除与不整除、大于时间线长度)，结果都与 fold_timeline 一致。
+    #[test]
+    fn test_chunked_fold_matches_fold_timeline() {
+        println!("🧪 [Test] Chunked Time Fold...");
+
+        // 小维度 (16) 以保持测试快速
+        let dim = 16;
+        let steps: Vec<AffineTuple> = (0..11u64)
+            .map(|seed| AffineTuple::new(
+                WeightInitializer::init_matrix(dim, dim, 300 + seed),
+                Vector { data: (0..dim).map(|i| (seed as Float + i as Float).sin()).collect() },
+            ))
+            .collect();
+        let reference = HyperFolder::fold_timeline(&steps).unwrap();
+
+        for chunk_size in [0, 1, 2, 3, 4, 11, 64] {
+            let chunked = HyperFolder::fold_timeline_chunked(&steps, chunk_size).unwrap();
+            let diff = chunked.linear.data.iter().zip(&reference.linear.data)
+                .chain(chunked.translation.data.iter().zip(&reference.translation.data))
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(diff < 1e-5, "❌ chunk_size = {} diverged from fold_timeline ({})", chunk_size, diff);
+        }
+        assert!(HyperFolder::fold_timeline_chunked(&[], 4).is_none());
+    }
+
+    /// 🧪 Test 8: Batch Backward == Per-Example Backward (批量反向传播一致性)
+    /// 并行批量结果必须与逐样本串行 backward 的叶子梯度逐位一致；无 Trace 的样本返回空列表。
+    #[test]
+    fn test_backward_batch_matches_serial() {
+        println!("🧪 [Test] Batch Backward...");
+
+        let inputs = timeline(6);
+        let tensors = vec![
+            HyperTensor::forward(&inputs[..3], true).unwrap(),
+            HyperTensor::forward(&inputs[2..], true).unwrap(),
+            HyperTensor::forward(&inputs[..2], true).unwrap()
+                .merge(&HyperTensor::forward(&inputs[4..], true).unwrap(), MergeMode::SpaceMerge),
+            HyperTensor::forward(&inputs, false).unwrap(),
+        ];
+        let grad_outputs: Vec<AffineTuple> = (0..tensors.len() as u64)
+            .map(|seed| AffineTuple::new(
+                WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 50 + seed),
+                ConceptEmbedder::embed_token(seed as u32),
+            ))
+            .collect();
+
+        let batch = HyperTensor::backward_batch(&tensors, &grad_outputs);
+        assert_eq!(batch.len(), tensors.len());
+
+        for (i, (tensor, grad_output)) in tensors.iter().zip(&grad_outputs).enumerate().take(3) {
+            let trace = tensor.trace.as_ref().unwrap();
+            let serial = trace.backward(grad_output);
+            let expected: Vec<AffineTuple> = trace.nodes.iter()
+                .filter(|node| matches!(node.op, OpType::LeafEmbedding))
+                .map(|node| serial[node.id].clone())
+                .collect();
+            assert_eq!(batch[i], expected, "❌ Example {} diverged from serial backward", i);
+        }
+        assert_eq!(batch[0].len(), 3);
+        assert_eq!(batch[2].len(), 4, "❌ Merged trace keeps the leaves of both shards");
+        assert!(batch[3].is_empty(), "❌ Inference tensors have no leaf gradients");
+    }
+
+    /// 🧪 Test 9: Reverse Time Fold (逆时折叠)
+    /// 逆时折叠 == 倒序时间线的正向折叠；逐项求逆后的逆时折叠与正向折叠复合为单位元。
+    #[test]
+    fn test_reverse_fold_matches_reversed_timeline() {
+        println!("🧪 [Test] Reverse Time Fold...");
+
+        // 小维度 (16)，线性部分取 I + 0.1·W 以保证可逆且条件数良好
+        let dim = 16;
+        let steps: Vec<AffineTuple> = (0..7u64)
+            .map(|seed| {
+                let mut linear = WeightInitializer::init_matrix(dim, dim, 500 + seed).scale(0.1);
+                for i in 0..dim {
+                    linear.data[i * dim + i] += 1.0;
+                }
+                AffineTuple::new(linear, Vector { data: (0..dim).map(|i| (seed as Float - i as Float).cos()).collect() })
+            })
+            .collect();
+        let max_diff = |a: &AffineTuple, b: &AffineTuple| a.linear.data.iter().zip(&b.linear.data)
+            .chain(a.translation.data.iter().zip(&b.translation.data))
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+
+        // 1. 逆时折叠 == 倒序后正向折叠
+        let reverse = HyperFolder::fold_timeline_reverse(&steps).unwrap();
+        let reversed: Vec<AffineTuple> = steps.iter().rev().cloned().collect();
+        let forward_of_reversed = HyperFolder::fold_timeline(&reversed).unwrap();
+        assert!(max_diff(&reverse, &forward_of_reversed) < 1e-5);
+
+        // 2. fold_timeline_reverse([A⁻¹]) ∘ fold_timeline(A) == I
+        let inverses: Vec<AffineTuple> = steps.iter().map(|a| a.inverse().expect("invertible")).collect();
+        let undo = HyperFolder::fold_timeline_reverse(&inverses).unwrap();
+        let forward = HyperFolder::fold_timeline(&steps).unwrap();
+        let round_trip = undo.compose(&forward).unwrap();
+        let mut eye = vec![0.0; dim * dim];
+        for i in 0..dim {
+            eye[i * dim + i] = 1.0;
+        }
+        let identity = AffineTuple::new(Matrix::new(dim, dim, eye), Vector { data: vec![0.0; dim] });
+        assert!(max_diff(&round_trip, &identity) < 1e-4, "❌ Reverse fold of inverses does not undo the forward fold");
+
+        assert!(HyperFolder::fold_timeline_reverse(&[]).is_none());
+    }
+
+    /// 🧪 Test 10: Trace Join (磁带拼接)
+    /// 两条各 3 个节点的磁带 (2 叶子 + 1 Compose) 经 Join 合并：ID 被重新编号，
+    /// 穿过 Join 的 backward 与对 4 个叶子一次性折叠后的 backward 给出相同的叶子梯度。
+    #[test]
+    fn test_trace_merge_backward_across_join() {
+        println!("🧪 [Test] CausalTrace Merge...");
+
+        let inputs: Vec<AffineTuple> = timeline(4).into_iter()
+            .map(|t| AffineTuple::new(t.linear.scale(0.5), t.translation))
+            .collect();
+        let left = HyperTensor::forward(&inputs[..2], true).unwrap().trace.unwrap();
+        let right = HyperTensor::forward(&inputs[2..], true).unwrap().trace.unwrap();
+        assert_eq!((left.nodes.len(), right.nodes.len()), (3, 3));
+
+        let merged = left.merge(right, OpType::TimeCompose);
+        assert_eq!(merged.nodes.len(), 7);
+        assert!(merged.nodes.iter().enumerate().all(|(i, n)| n.id == i), "❌ IDs must be renumbered densely");
+        assert_eq!(merged.nodes[5].parents, vec![3, 4]);
+        let join = merged.nodes.last().unwrap();
+        assert!(matches!(join.op, OpType::TimeCompose));
+        assert_eq!(join.parents, vec![2, 5]);
+
+        // 参照: 一次性折叠的磁带
+        let single = HyperTensor::forward(&inputs, true).unwrap();
+        assert!(single.root.linear.data.iter().zip(&join.value.linear.data).all(|(a, b)| (a - b).abs() < 1e-4));
+
+        let grad_output = AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(9));
+        let merged_grads = merged.backward(&grad_output);
+        let single_grads = single.trace.unwrap().backward(&grad_output);
+        for (merged_leaf, single_leaf) in [(0, 0), (1, 1), (3, 2), (4, 3)] {
+            let (a, b) = (&merged_grads[merged_leaf], &single_grads[single_leaf]);
+            let diff = a.linear.data.iter().zip(&b.linear.data)
+                .chain(a.translation.data.iter().zip(&b.translation.data))
+                .map(|(x, y)| (x - y).abs())
+                .fold(0.0, Float::max);
+            assert!(diff < 1e-3, "❌ Leaf {} gradient differs across join by {}", merged_leaf, diff);
+        }
+    }
+
+    /// 🧪 Test 11: FLOP Estimation (算力估算)
+    /// 估算与输入长度线性相关 (n - 1 次 Compose)，每次 Compose 的主导项随 D³ 增长。
+    #[test]
+    fn test_flop_estimate_scaling() {
+        println!("🧪 [Test] FLOP Estimation...");
+
+        assert_eq!(HyperTensor::estimate_flops(0), 0);
+        assert_eq!(HyperTensor::estimate_flops(1), 0);
+
+        let per_compose = HyperTensor::estimate_flops(2);
+        let d = MANIFOLD_DIM as u64;
+        assert_eq!(per_compose, 2 * d * d * d + 2 * d * d + d);
+        for n in [3, 8, 100] {
+            assert_eq!(HyperTensor::estimate_flops(n), (n as u64 - 1) * per_compose);
+        }
+
+        // D 翻倍 -> 每次 Compose 约 8 倍
+        let ratio = HyperTensor::estimate_flops_with_dim(2, 1024) as f64 / HyperTensor::estimate_flops_with_dim(2, 512) as f64;
+        assert!((ratio - 8.0).abs() < 0.05, "❌ Compose cost should scale as D³, got ratio {}", ratio);
+
+        // 与实际 Trace 的 Compose 节点数一致
+        let trace = HyperTensor::forward(&timeline(5), true).unwrap().trace.unwrap();
+        assert_eq!(trace.stats().compose_count as u64 * per_compose, HyperTensor::estimate_flops(5));
+    }
+
+    /// 🧪 Test 12: Weighted Merge Gradient Flow (加权融合的梯度分配)
+    /// 权重 [0.7, 0.2, 0.1] 下，每个分支收到的梯度恰为 (w_i / Σw) · dL/dOut。
+    #[test]
+    fn test_weighted_merge_distributes_gradient_by_weight() {
+        println!("🧪 [Test] Weighted Merge Backward...");
+
+        let branches = timeline(3);
+        let weights: Vec<Float> = vec![0.7, 0.2, 0.1];
+        let root = HyperFolder::fold_context_weighted(&branches, &weights).unwrap();
+
+        let mut trace = CausalTrace::new();
+        let leaves: Vec<usize> = branches.iter().map(|b| trace.push_leaf(b.clone())).collect();
+        trace.push_weighted_merge(leaves.clone(), weights.clone(), root);
+        assert_eq!(trace.stats().merge_count, 1);
+
+        let grad_output = AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(4));
+        let grads = trace.backward(&grad_output);
+        let total: Float = weights.iter().sum();
+        for (&leaf, &w) in leaves.iter().zip(&weights) {
+            assert_eq!(grads[leaf], grad_output.scale(w / total), "❌ Branch {} did not receive w/Σw of the gradient", leaf);
+        }
+        // 最重的分支收到最大的梯度
+        assert!(grads[0].translation.norm() > grads[1].translation.norm());
+        assert_eq!(trace.backward_parallel(&grad_output), grads);
+
+        // 等权重退化为 fold_context
+        let uniform = HyperFolder::fold_context_weighted(&branches, &[1.0, 1.0, 1.0]).unwrap();
+        let mean = HyperFolder::fold_context(&branches).unwrap();
+        assert!(uniform.translation.data.iter().zip(&mean.translation.data).all(|(a, b)| (a - b).abs() < 1e-6));
+        assert!(HyperFolder::fold_context_weighted(&branches, &[1.0, -1.0, 0.0]).is_none());
+    }
+
+    /// 🧪 Test 13: Masked Padding (填充屏蔽)
+    /// 在序列中间与末尾插入填充 Token 并屏蔽后，折叠结果与未填充的序列一致；
+    /// 训练模式下 Trace 只包含有效位置的叶子。
+    #[test]
+    fn test_masked_padding_matches_unpadded_fold() {
+        println!("🧪 [Test] Masked Forward...");
+
+        let tokens = timeline(5);
+        let pad = |seed: u64| AffineTuple::new(
+            WeightInitializer::init_matrix(MANIFOLD_DIM, MANIFOLD_DIM, 900 + seed),
+            ConceptEmbedder::embed_token(900 + seed as u32),
+        );
+        let padded = vec![
+            tokens[0].clone(), tokens[1].clone(), pad(0), tokens[2].clone(),
+            tokens[3].clone(), tokens[4].clone(), pad(1), pad(2),
+        ];
+        let mask = [true, true, false, true, true, true, false, false];
+
+        let reference = HyperTensor::forward(&tokens, true).unwrap();
+        let masked = HyperTensor::forward_masked(&padded, &mask, true).unwrap();
+        assert_eq!(masked.root, reference.root, "❌ Padding leaked into the fold");
+        assert_eq!(masked.trace.as_ref().unwrap().nodes.len(), 2 * tokens.len() - 1);
+
+        // 未屏蔽时填充确实会改变结果
+        let unmasked = HyperTensor::forward(&padded, true).unwrap();
+        assert!(!unmasked.root_approx_eq(&reference, 1e-3));
+
+        // 推理模式同样忽略填充
+        let fast = HyperTensor::forward_masked(&padded, &mask, false).unwrap();
+        assert!(fast.trace.is_none());
+        assert!(fast.root_approx_eq(&reference, 1e-4));
+
+        // 全部屏蔽 = 空序列 = 单位元
+        let none = HyperTensor::forward_masked(&padded, &[false; 8], true).unwrap();
+        assert!(none.root.is_identity());
+    }
+}
@@ -0,0 +1,452 @@
+// COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
+
+#[cfg(test)]
+mod tests {
+    use crate::core::algebra::{Float, Matrix, Vector};
+    use crate::core::affine::AffineTuple;
+    use crate::core::neuron::HTPNeuron;
+    use crate::core::oracle::LogicOracle;
+    use crate::core::param::HyperParams;
+    use crate::core::primes::ConceptEmbedder;
+    use crate::train_loop::{TrainingLoop, TargetMode, ModelCheckpoint, SimpleOptimizer, GradientAccumulator, TrainingMetrics, AdamOptimizer, OptimizerState, EpochStats, OneShotFact};
+
+    /// 🧪 Test 1: Full Affine Target (完整仿射目标)
+    /// SGD 必须同时把 Root 的 W 和 b 拉向目标变换。
+    #[test]
+    fn test_sgd_converges_to_affine_target() {
+        println!("🧪 [Test] SGD toward a full affine target...");
+
+        let params = HyperParams { learning_rate: 0.1, ..HyperParams::default() };
+        let mut trainer = TrainingLoop::new(params).with_target_mode(TargetMode::FullAffine);
+
+        let mut model = vec![HTPNeuron::new()];
+        let inputs = vec![AffineTuple::identity()];
+        let target = AffineTuple::new(
+            Matrix::identity().scale(0.9),
+            ConceptEmbedder::embed_token(5).scale(0.5),
+        );
+
+        let initial_loss = trainer.train_step_sgd(&mut model, &inputs, &target);
+        let mut loss = initial_loss;
+        for _ in 0..30 {
+            loss = trainer.train_step_sgd(&mut model, &inputs, &target);
+        }
+        println!("   > Affine Loss: {:.4} -> {:.4e}", initial_loss, loss);
+
+        let gate = model[0].logic_gate();
+        let matrix_err = LogicOracle::affine_loss(&AffineTuple::new(gate.linear.clone(), target.translation.clone()), &target);
+        let bias_err = LogicOracle::calculate_loss(&gate.translation, &target.translation);
+        assert!(loss < 1e-3 * initial_loss, "❌ SGD did not converge on the affine target");
+        assert!(matrix_err < 1e-3, "❌ Matrix part did not converge ({})", matrix_err);
+        assert!(bias_err < 1e-3, "❌ Bias part did not converge ({})", bias_err);
+    }
+
+    /// 🧪 Test 2: Keep-the-Best Snapshot (最佳模型快照)
+    /// Loss 先降后升，best_model 必须停留在最低点对应的权重。
+    #[test]
+    fn test_track_best_keeps_minimum() {
+        println!("🧪 [Test] Keep-the-Best Snapshot...");
+
+        let path = std::env::temp_dir().join(format!("htp_best_{}.bin", std::process::id()));
+        let mut trainer = TrainingLoop::new(HyperParams::default()).with_best_checkpoint(&path);
+        assert!(trainer.best_model().is_none());
+
+        let mut model = vec![HTPNeuron::new()];
+        let losses = [0.9, 0.5, 0.2, 0.4, 0.7];
+        for (step, &val_loss) in losses.iter().enumerate() {
+            model[0].gate_mut().translation.data[0] = step as f32;
+            let improved = trainer.track_best(val_loss, &model).unwrap();
+            assert_eq!(improved, step <= 2);
+        }
+
+        // 最低点出现在 step 2
+        assert_eq!(trainer.best_loss(), Some(0.2));
+        let best = trainer.best_model().unwrap();
+        assert_eq!(best[0].logic_gate().translation.data[0], 2.0);
+
+        // 磁盘上的快照与内存一致
+        let on_disk = ModelCheckpoint::load(&path).unwrap();
+        assert_eq!(on_disk.val_loss, 0.2);
+        assert_eq!(on_disk.neurons[0].logic_gate().translation.data[0], 2.0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// 🧪 Test 3: Heavy-Ball Momentum (动量加速)
+    /// 在二次型 L = ||b - t||² 上，相同步数下动量 SGD 比普通 SGD 更接近目标。
+    #[test]
+    fn test_momentum_accelerates_quadratic() {
+        println!("🧪 [Test] SGD Momentum vs Plain SGD...");
+
+        let target = ConceptEmbedder::embed_token(9);
+        let run = |momentum: f32| {
+            let mut opt = SimpleOptimizer::new(0.02).with_momentum(momentum);
+            let mut bias = Vector::zeros();
+            for _ in 0..40 {
+                // dL/db = 2 (b - t)
+                let grad = bias.sub(&target).scale(2.0);
+                opt.apply_bias_gradient(0, &mut bias, &grad);
+            }
+            LogicOracle::calculate_loss(&bias, &target)
+        };
+
+        let plain = run(0.0);
+        let heavy_ball = run(0.9);
+        println!("   > Loss after 40 steps: plain = {:.4e}, momentum = {:.4e}", plain, heavy_ball);
+        assert!(heavy_ball < plain * 0.1, "❌ Momentum did not accelerate convergence");
+
+        // momentum = 0 与手写的普通 SGD 完全一致
+        let mut opt = SimpleOptimizer::new(0.02);
+        let mut bias = Vector::zeros();
+        opt.apply_bias_gradient(3, &mut bias, &target);
+        assert_eq!(bias, target.scale(-0.02));
+    }
+
+    /// 🧪 Test 4: Per-Layer LR Multiplier (分层学习率)
+    /// lr_scale = 0.1 的层在相同梯度下的位移是未缩放层的 1/10。
+    #[test]
+    fn test_lr_scale_dampens_layer_update() {
+        println!("🧪 [Test] Per-Layer Learning-Rate Scale...");
+
+        let mut opt = SimpleOptimizer::new(0.1);
+        let mut frozen_ish = HTPNeuron::new().with_lr_scale(0.1);
+        let mut free = HTPNeuron::new();
+        assert_eq!(free.lr_scale, 1.0);
+
+        let grad_w = Matrix::identity();
+        let grad_b = ConceptEmbedder::embed_token(3);
+        opt.step_neuron(0, &mut frozen_ish, &grad_w, &grad_b);
+        opt.step_neuron(1, &mut free, &grad_w, &grad_b);
+
+        let moved = |n: &HTPNeuron| {
+            let dw = n.logic_gate().linear.data[0] - 1.0;
+            let db = n.logic_gate().translation.norm();
+            (dw.abs(), db)
+        };
+        let (dw0, db0) = moved(&frozen_ish);
+        let (dw1, db1) = moved(&free);
+        assert!((dw1 / dw0 - 10.0).abs() < 1e-3, "❌ Weight ratio {}", dw1 / dw0);
+        assert!((db1 / db0 - 10.0).abs() < 1e-3, "❌ Bias ratio {}", db1 / db0);
+    }
+
+    /// 🧪 Test 5: Empty Input Guard (空输入保护)
+    /// 空上下文不得触发训练：返回 0.0，模型与优化器状态保持不变。
+    #[test]
+    fn test_sgd_empty_input_is_noop() {
+        println!("🧪 [Test] SGD with Empty Input...");
+
+        let params = HyperParams { learning_rate: 0.1, ..HyperParams::default() };
+        let mut trainer = TrainingLoop::new(params).with_target_mode(TargetMode::FullAffine);
+
+        let mut model = vec![HTPNeuron::new()];
+        let before = model[0].logic_gate().clone();
+        let target = AffineTuple::new(Matrix::identity().scale(0.5), ConceptEmbedder::embed_token(3));
+
+        let loss = trainer.train_step_sgd(&mut model, &[], &target);
+        assert_eq!(loss, 0.0);
+        assert_eq!(model[0].logic_gate(), &before, "❌ Model was trained on an empty input");
+    }
+
+    /// 🧪 Test 6: Batch Gradient Accumulation (批量梯度累加)
+    /// 4 个样本的梯度按层求和后输出均值；取出后累加器清空，step 以均值梯度更新模型。
+    #[test]
+    fn test_gradient_accumulator_averages_batch() {
+        println!("🧪 [Test] Gradient Accumulator...");
+
+        let grad = |k: f32| AffineTuple::new(
+            Matrix::new(2, 2, vec![k, 2.0 * k, 0.0, -k]),
+            Vector { data: vec![k, 1.0] },
+        );
+
+        let mut acc = GradientAccumulator::new();
+        for k in [1.0, 2.0, 3.0] {
+            acc.add(0, &grad(k));
+        }
+        // 叶子布局: [input, layer 0, layer 1]，输入叶子的梯度不参与累加
+        acc.add_leaf_grads(&[grad(100.0), grad(4.0), grad(8.0)], 1, 2);
+        acc.add(1, &grad(4.0));
+        assert_eq!((acc.samples(0), acc.samples(1)), (4, 2));
+
+        // mean(1, 2, 3, 4) = 2.5, mean(8, 4) = 6
+        let (w0, b0) = acc.mean(0).unwrap();
+        assert_eq!(w0.data, vec![2.5, 5.0, 0.0, -2.5]);
+        assert_eq!(b0.data, vec![2.5, 1.0]);
+
+        let means = acc.drain_means();
+        assert_eq!(means.iter().map(|(layer, _, _)| *layer).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(means[1].1.data, vec![6.0, 12.0, 0.0, -6.0]);
+        assert!(acc.mean(0).is_none(), "❌ drain_means must reset the accumulator");
+
+        // step: W -= lr · mean(grad)
+        let mut model = vec![HTPNeuron::new()];
+        let g = AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(1));
+        acc.add(0, &g);
+        acc.add(0, &g.scale(3.0));
+        acc.step(&mut SimpleOptimizer::new(0.5), &mut model);
+        assert!((model[0].logic_gate().linear.data[0] - 0.0).abs() < 1e-6, "❌ 1 - 0.5 · 2 should be 0");
+        assert_eq!(acc.samples(0), 0);
+    }
+
+    /// 🧪 Test 7: Gradient Signal-to-Noise Ratio (梯度信噪比)
+    /// 几乎一致的梯度 SNR 很高；围绕零均值的随机梯度 SNR 很低。step 返回的指标携带 SNR。
+    #[test]
+    fn test_gradient_snr_separates_signal_from_noise() {
+        println!("🧪 [Test] Gradient SNR...");
+
+        let direction = AffineTuple::new(Matrix::identity(), ConceptEmbedder::embed_token(3));
+        let jitter = |seed: u32| AffineTuple::new(
+            Matrix::identity().scale(0.0),
+            ConceptEmbedder::embed_token(1000 + seed),
+        );
+
+        // 1. 信号主导: g_i = d + 0.001 · noise_i
+        let mut coherent = GradientAccumulator::new();
+        for i in 0..16 {
+            coherent.add(0, &direction.add_components(&jitter(i).scale(0.001)));
+        }
+
+        // 2. 噪声主导: g_i = ±noise_i
+        let mut noisy = GradientAccumulator::new();
+        for i in 0..16 {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            noisy.add(0, &jitter(i).scale(sign));
+        }
+
+        let (high, low) = (coherent.snr(), noisy.snr());
+        println!("   > SNR coherent: {:.2}, noisy: {:.4}", high, low);
+        assert!(high > 100.0, "❌ Near-identical gradients should have high SNR");
+        assert!(low < 1.0, "❌ Random gradients should have low SNR");
+
+        // 不足 2 个样本无从估计噪声
+        let mut single = GradientAccumulator::new();
+        single.add(0, &direction);
+        assert_eq!(single.snr(), 0.0);
+
+        let metrics = coherent.step(&mut SimpleOptimizer::new(0.1), &mut [HTPNeuron::new()]);
+        assert_eq!(metrics, TrainingMetrics { samples: 16, grad_snr: high });
+    }
+
+    /// 🧪 Test 8: Resumable Optimizer State (优化器断点续训)
+    /// Adam 在积累了矩估计后随检查点写盘并读回，下一步更新必须与不中断的运行逐位一致；
+    /// TrainingLoop 的检查点携带其驱动的优化器 (SGD 或 Adam)，续训同样逐位一致。
+    #[test]
+    fn test_adam_state_survives_checkpoint() {
+        println!("🧪 [Test] Resumable Adam State...");
+
+        let grad = |k: u32| (
+            Matrix::identity().scale(0.01 * k as f32),
+            ConceptEmbedder::embed_token(k),
+        );
+        let mut model = vec![HTPNeuron::new(), HTPNeuron::new()];
+        let mut adam = AdamOptimizer::new(0.01).with_betas(0.8, 0.99);
+        for k in 1..=3 {
+            for (layer, neuron) in model.iter_mut().enumerate() {
+                let (gw, gb) = grad(k + layer as u32);
+                adam.step_neuron(layer, neuron, &gw, &gb);
+            }
+        }
+        assert_eq!(adam.step_count(0), 3);
+
+        // 写盘 -> 读回
+        let path = std::env::temp_dir().join(format!("htp_adam_{}.bin", std::process::id()));
+        ModelCheckpoint { val_loss: 0.5, neurons: model.clone(), optimizer: Some(OptimizerState::Adam(adam.clone())) }
+            .save(&path)
+            .unwrap();
+        let restored = ModelCheckpoint::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let (mut resumed_model, Some(OptimizerState::Adam(mut resumed))) = (restored.neurons, restored.optimizer) else {
+            panic!("❌ Checkpoint lost the Adam state");
+        };
+        assert_eq!(resumed.step_count(0), 3);
+
+        // 下一步: 不中断 vs 续训
+        let (gw, gb) = grad(7);
+        let mut fresh_model = model.clone();
+        adam.step_neuron(0, &mut model[0], &gw, &gb);
+        resumed.step_neuron(0, &mut resumed_model[0], &gw, &gb);
+        assert_eq!(resumed_model[0].logic_gate(), model[0].logic_gate(), "❌ Resumed Adam step diverged");
+
+        // 从零开始的 Adam (丢失矩估计) 会给出不同的一步
+        let mut fresh = AdamOptimizer::new(0.01).with_betas(0.8, 0.99);
+        fresh.step_neuron(0, &mut fresh_model[0], &gw, &gb);
+        assert_ne!(fresh_model[0].logic_gate(), model[0].logic_gate());
+
+        // TrainingLoop 检查点携带 SGD 状态，并可续训
+        let mut trainer = TrainingLoop::new(HyperParams::default()).with_best_checkpoint(&path);
+        trainer.track_best(0.3, &model).unwrap();
+        let checkpoint = ModelCheckpoint::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(checkpoint.optimizer, Some(OptimizerState::Sgd(_))));
+        let mut resumed_trainer = TrainingLoop::new(HyperParams::default());
+        assert_eq!(resumed_trainer.resume_from(checkpoint).len(), 2);
+        assert_eq!(resumed_trainer.best_loss(), Some(0.3));
+
+        // 驱动 Adam 的 TrainingLoop: 检查点携带矩估计，续训后的下一步与不中断的运行逐位一致
+        let inputs = vec![ConceptEmbedder::embed_token(11).to_affine_leaf()];
+        let target = ConceptEmbedder::embed_token(12).to_affine_leaf();
+        let mut adam_trainer = TrainingLoop::new(HyperParams::default())
+            .with_optimizer(OptimizerState::Adam(AdamOptimizer::new(0.01)))
+            .with_best_checkpoint(&path);
+        let mut adam_model = vec![HTPNeuron::new()];
+        for _ in 0..3 {
+            adam_trainer.train_step_sgd(&mut adam_model, &inputs, &target);
+        }
+        adam_trainer.track_best(0.2, &adam_model).unwrap();
+        let checkpoint = ModelCheckpoint::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let Some(OptimizerState::Adam(saved)) = &checkpoint.optimizer else {
+            panic!("❌ TrainingLoop checkpoint lost the Adam state");
+        };
+        assert_eq!(saved.step_count(0), 3);
+
+        let mut resumed_trainer = TrainingLoop::new(HyperParams::default());
+        let mut resumed_model = resumed_trainer.resume_from(checkpoint);
+        adam_trainer.train_step_sgd(&mut adam_model, &inputs, &target);
+        resumed_trainer.train_step_sgd(&mut resumed_model, &inputs, &target);
+        assert_eq!(resumed_model[0].logic_gate(), adam_model[0].logic_gate(), "❌ Resumed TrainingLoop Adam step diverged");
+    }
+
+    /// 🧪 Test 9: Epoch Driver (完整数据集训练)
+    /// 6 个样本、Batch 大小 4 (最后一个 Batch 不满)：每个 Epoch 的平均 Loss 单调下降；
+    /// 相同种子的两次训练得到逐位一致的模型。
+    #[test]
+    fn test_train_epoch_reduces_loss() {
+        println!("🧪 [Test] TrainingLoop::train_epoch...");
+
+        let shift = ConceptEmbedder::embed_token(77).scale(0.5);
+        let dataset: Vec<(Vec<AffineTuple>, AffineTuple)> = (0..6)
+            .map(|i| {
+                let x = ConceptEmbedder::embed_token(200 + i);
+                let target = x.add(&shift).to_affine_leaf();
+                (vec![x.to_affine_leaf()], target)
+            })
+            .collect();
+        
+        let params = HyperParams { learning_rate: 0.1, ..HyperParams::default() };
+        let run = || {
+            let mut trainer = TrainingLoop::new(params.clone()).with_shuffle_seed(42);
+            let mut model = vec![HTPNeuron::new()];
+            let stats: Vec<EpochStats> = (0..3).map(|_| trainer.train_epoch(&mut model, &dataset, 4)).collect();
+            (model, stats)
+        };
+
+        let (model, stats) = run();
+        for s in &stats {
+            println!("   > Epoch avg loss: {:.6}", s.avg_loss);
+            assert_eq!((s.samples, s.batches), (6, 2));
+        }
+        assert!(stats.windows(2).all(|w| w[1].avg_loss < w[0].avg_loss), "❌ Epoch loss did not decrease");
+        assert!(stats[2].avg_loss < 0.2 * stats[0].avg_loss, "❌ Training barely progressed");
+
+        let (replay, _) = run();
+        assert_eq!(replay[0].logic_gate(), model[0].logic_gate(), "❌ Seeded epochs are not reproducible");
+    }
+
+    /// 🧪 Test 10: Mixed-Mode Training (通识学习 + 事实注入)
+    /// SGD 从 Batch 中学到 "平移 shift" 的通用规则并推广到未见过的输入；
+    /// 同时注入的一条任意事实被精确记住，且不破坏该规则。
+    #[test]
+    fn test_mixed_step_generalizes_and_memorizes() {
+        println!("🧪 [Test] TrainingLoop::train_step_mixed...");
+
+        let shift = ConceptEmbedder::embed_token(77).scale(0.5);
+        let batch: Vec<(Vec<AffineTuple>, AffineTuple)> = (0..4)
+            .map(|i| {
+                let x = ConceptEmbedder::embed_token(300 + i);
+                let target = x.add(&shift).to_affine_leaf();
+                (vec![x.to_affine_leaf()], target)
+            })
+            .collect();
+        let fact = OneShotFact {
+            layer: 0,
+            input: ConceptEmbedder::embed_token(900),
+            target: ConceptEmbedder::embed_token(901),
+        };
+
+        let params = HyperParams { learning_rate: 0.1, ..HyperParams::default() };
+        let mut trainer = TrainingLoop::new(params);
+        let mut model = vec![HTPNeuron::new()];
+
+        // 未见过的输入: 训练前后的泛化误差
+        let unseen = ConceptEmbedder::embed_token(555);
+        let generalization_loss = |model: &[HTPNeuron]| {
+            let gate = model[0].logic_gate();
+            let pred = gate.linear.matmul_vec(&unseen).add(&gate.translation);
+            LogicOracle::calculate_loss(&pred, &unseen.add(&shift))
+        };
+        let initial_unseen = generalization_loss(&model);
+
+        let mut first_sgd = None;
+        let mut last = None;
+        for _ in 0..8 {
+            let stats = trainer.train_step_mixed(&mut model, &batch, std::slice::from_ref(&fact));
+            assert_eq!((stats.sgd.samples, stats.sgd.batches, stats.fact_losses.len()), (4, 1, 1));
+            first_sgd.get_or_insert(stats.sgd.avg_loss);
+            last = Some(stats);
+        }
+        let last = last.unwrap();
+        let final_unseen = generalization_loss(&model);
+        println!("   > SGD batch loss: {:.5} -> {:.5}", first_sgd.unwrap(), last.sgd.avg_loss);
+        println!("   > Unseen input loss: {:.5} -> {:.5}", initial_unseen, final_unseen);
+        println!("   > Fact residual: {:.3e}", last.fact_losses[0]);
+
+        assert!(last.sgd.avg_loss < 0.2 * first_sgd.unwrap(), "❌ SGD did not learn the batch");
+        assert!(final_unseen < 0.2 * initial_unseen, "❌ Learned rule does not generalize to unseen input");
+        assert!(last.fact_losses[0] < 1e-3, "❌ Fact was not memorized ({})", last.fact_losses[0]);
+
+        // 越界的事实被跳过，不影响其余部分
+        let skipped = OneShotFact { layer: 5, ..fact };
+        assert!(trainer.train_step_mixed(&mut model, &batch, &[skipped]).fact_losses[0].is_nan());
+    }
+
+    /// 🧪 Test 11: Regularized Fact Injection (带正则的事实注入)
+    /// 启用正则 (μ > 0) 时，事实注入以 SGD 之后的权重为锚点，且不改动全局偏置：
+    /// 与事实输入正交的保留输入 (held-out) 的输出与 "只做 SGD" 完全一致，事实被部分吸收。
+    #[test]
+    fn test_regularized_mixed_step_preserves_sgd_on_held_out_inputs() {
+        println!("🧪 [Test] Regularized train_step_mixed keeps SGD behaviour...");
+
+        let shift = ConceptEmbedder::embed_token(77).scale(0.5);
+        let batch: Vec<(Vec<AffineTuple>, AffineTuple)> = (0..4)
+            .map(|i| {
+                let x = ConceptEmbedder::embed_token(300 + i);
+                let target = x.add(&shift).to_affine_leaf();
+                (vec![x.to_affine_leaf()], target)
+            })
+            .collect();
+        let fact = OneShotFact {
+            layer: 0,
+            input: ConceptEmbedder::embed_token(900),
+            target: ConceptEmbedder::embed_token(901),
+        };
+
+        let params = HyperParams { learning_rate: 0.1, ..HyperParams::default() };
+        let mu = 0.5;
+        let mut sgd_only = vec![HTPNeuron::new()];
+        let mut mixed = vec![HTPNeuron::new()];
+        TrainingLoop::new(params.clone()).with_identity_regularization(mu).train_step_mixed(&mut sgd_only, &batch, &[]);
+        let stats = TrainingLoop::new(params).with_identity_regularization(mu)
+            .train_step_mixed(&mut mixed, &batch, std::slice::from_ref(&fact));
+
+        // 1. 全局偏置只来自 SGD
+        assert_eq!(mixed[0].logic_gate().translation, sgd_only[0].logic_gate().translation, "❌ Fact leaked into the global bias");
+
+        // 2. 与事实输入正交的保留输入: 输出与只做 SGD 时一致
+        let max_drift = (500..508)
+            .map(|i| {
+                let held_out = ConceptEmbedder::embed_token(i).reject_from(&fact.input);
+                let a = sgd_only[0].logic_gate().linear.matmul_vec(&held_out).add(&sgd_only[0].logic_gate().translation);
+                let b = mixed[0].logic_gate().linear.matmul_vec(&held_out).add(&mixed[0].logic_gate().translation);
+                a.sub(&b).norm()
+            })
+            .fold(0.0, Float::max);
+        println!("   > Held-out drift: {:.3e}", max_drift);
+        assert!(max_drift < 1e-5, "❌ Fact injection disturbed held-out inputs: {}", max_drift);
+
+        // 3. 事实被部分吸收: 残差比例 μ / (μ + ||x||²)
+        let gate = sgd_only[0].logic_gate();
+        let before = LogicOracle::calculate_loss(&gate.linear.matmul_vec(&fact.input).add(&gate.translation), &fact.target);
+        let ratio = mu / (mu + fact.input.norm().powi(2));
+        println!("   > Fact loss: {:.5} -> {:.5} (expected ratio² {:.3})", before, stats.fact_losses[0], ratio * ratio);
+        assert!((stats.fact_losses[0] - before * ratio * ratio).abs() < 1e-3 * before, "❌ Fact residual does not match the graded pull");
+    }
+}
@@ -1,9 +1,52 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
+use std::ops::Range;
+
 use rayon::prelude::*;
 use crate::core::affine::AffineTuple;
 use crate::core::algebra::Float;
 
+/// 🚧 NormGuard: 时间折叠的数值护栏
+///
+/// 每次 compose 之后检查复合矩阵的 Frobenius 范数是否落在 [floor, ceiling] 内。
+/// 深层折叠中矩阵元素可能在 `verify_integrity` 之前就已溢出为 inf 或跌入非规格化数，
+/// 护栏可以在发散发生的那一步立即中止折叠。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormGuard {
+    /// 范数下限 (低于此值视为 Underflow)
+    pub floor: Float,
+    /// 范数上限 (高于此值视为 Overflow)
+    pub ceiling: Float,
+}
+
+impl NormGuard {
+    pub fn new(floor: Float, ceiling: Float) -> Self {
+        NormGuard { floor, ceiling }
+    }
+
+    fn check(&self, steps: &Range<usize>, tuple: &AffineTuple) -> Result<(), FoldError> {
+        let norm = tuple.linear.frobenius_norm();
+        // NaN 按溢出处理
+        if norm.is_nan() || norm > self.ceiling {
+            return Err(FoldError::Overflow { steps: steps.clone(), norm });
+        }
+        if norm < self.floor {
+            return Err(FoldError::Underflow { steps: steps.clone(), norm });
+        }
+        Ok(())
+    }
+}
+
+/// ❌ FoldError: 带护栏的时间折叠失败原因
+/// `steps` 为发生越界的那次 compose 所覆盖的时间线区间 (左闭右开)。
+#[derive(Clone, Debug, PartialEq)]
+pub enum FoldError {
+    /// 复合矩阵范数超过上限 (或出现 NaN / inf)
+    Overflow { steps: Range<usize>, norm: Float },
+    /// 复合矩阵范数低于下限
+    Underflow { steps: Range<usize>, norm: Float },
+}
+
 /// 📦 Accumulator (Monoid Structure)
 /// 
 /// 引入 Monoid 结构以修复空间折叠的结合律问题。
@@ -68,6 +111,9 @@ impl HyperFolder {
     /// 并行化原理: 
     /// 虽然矩阵乘法不满足交换律 (A*B != B*A)，但满足结合律 ((A*B)*C = A*(B*C))。
     /// 因此我们可以将长链切分为 Chunk 并行计算，最后再合并。
+    ///
+    /// 热路径：使用 `compose_unchecked`，不做逐步的 Lipschitz 硬检查
+    /// (稳定但逐步扩张的长链是合法的；需要护栏时用 `fold_timeline_guarded`)。
     pub fn fold_timeline(timeline: &[AffineTuple]) -> Option<AffineTuple> {
         if timeline.is_empty() { return None; }
 
@@ -77,14 +123,76 @@ impl HyperFolder {
             .cloned()
             .reduce_with(|prev_step, next_step| {
                 // ⚠️ Crucial: Maintain Causal Order
-                // compose(prev) means: new_matrix = self * prev
-                // So we want: next_step.compose(&prev_step)
-                next_step.compose(&prev_step).expect("Time Folding Error: Lipschitz bound violated?")
+                // compose_unchecked(prev) means: new_matrix = self * prev
+                // So we want: next_step.compose_unchecked(&prev_step)
+                next_step.compose_unchecked(&prev_step)
             });
 
         result
     }
 
+    /// ⏪ Reverse Time Folding (逆时折叠)
+    ///
+    /// 按相反的因果顺序复合时间线: T_rev = A_a * A_b * ... * A_z (先执行 A_z，最后执行 A_a)，
+    /// 等价于 `fold_timeline` 作用于倒序后的时间线。用于反因果动力学分析。
+    ///
+    /// 与逆变换的关系: 对逐项求逆后的时间线做逆时折叠，得到的正是正向折叠的逆：
+    /// fold_timeline_reverse([A⁻¹]) = A_a⁻¹ * ... * A_z⁻¹ = (A_z * ... * A_a)⁻¹，
+    /// 因此它与 `fold_timeline(timeline)` 复合后为单位元。
+    pub fn fold_timeline_reverse(timeline: &[AffineTuple]) -> Option<AffineTuple> {
+        if timeline.is_empty() { return None; }
+
+        timeline.par_iter()
+            .cloned()
+            .reduce_with(|prev_step, next_step| {
+                // 与 fold_timeline 相反: 先执行后面的步骤
+                prev_step.compose_unchecked(&next_step)
+            })
+    }
+
+    /// 🧩 Chunked Time Folding (粒度调优)
+    ///
+    /// Rayon 默认会把时间线切得很碎，对 D x D 的重量级 compose 而言，线程调度开销可能占主导。
+    /// 这里先把时间线按 `chunk_size` 切成连续的 Chunk，每个 Chunk 内串行折叠 (缓存友好)，
+    /// 再对各 Chunk 的结果做并行树形归约。结果与 `fold_timeline` 在浮点误差内一致。
+    ///
+    /// `chunk_size` 为 0 时按 1 处理 (退化为逐元素并行)。
+    pub fn fold_timeline_chunked(timeline: &[AffineTuple], chunk_size: usize) -> Option<AffineTuple> {
+        if timeline.is_empty() { return None; }
+
+        timeline.par_chunks(chunk_size.max(1))
+            .map(|chunk| {
+                // Chunk 内部：从左到右串行复合 (next ∘ acc)
+                chunk[1..].iter().fold(chunk[0].clone(), |acc, next_step| {
+                    next_step.compose_unchecked(&acc)
+                })
+            })
+            .reduce_with(|prev_chunk, next_chunk| {
+                next_chunk.compose_unchecked(&prev_chunk)
+            })
+    }
+
+    /// 🚧 Guarded Time Folding
+    ///
+    /// 与 `fold_timeline` 相同的并行折叠，但每次 compose 后用 `guard` 检查范数，
+    /// 一旦越界立即返回 `FoldError`，错误中携带越界时所覆盖的时间线区间。
+    pub fn fold_timeline_guarded(timeline: &[AffineTuple], guard: &NormGuard) -> Result<Option<AffineTuple>, FoldError> {
+        if timeline.is_empty() { return Ok(None); }
+
+        timeline.par_iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, step)| Ok((i..i + 1, step)))
+            .try_reduce_with(|(prev_steps, prev_step), (next_steps, next_step)| {
+                let composed = next_step.compose_unchecked(&prev_step);
+                let steps = prev_steps.start..next_steps.end;
+                guard.check(&steps, &composed)?;
+                Ok((steps, composed))
+            })
+            .transpose()
+            .map(|folded| folded.map(|(_, root)| root))
+    }
+
     /// 🌌 Space Folding (Parallel -> Unified)
     /// 
     /// 物理含义: 将多个独立的上下文分支 (Branches) 融合为一个统一的上下文。
@@ -108,6 +216,27 @@ impl HyperFolder {
         final_acc.finalize()
     }
     
+    /// ⚖️ Weighted Context Folding (加权空间折叠 / 注意力)
+    ///
+    /// Root = Σ w_i · A_i / Σ w。权重全部相等时与 `fold_context` 一致。
+    /// 分支为空、权重个数与分支不符或 Σ w = 0 时返回 None。
+    /// 训练模式下以 `CausalTrace::push_weighted_merge` 记录，梯度按 w_i / Σ w 分配给各分支。
+    pub fn fold_context_weighted(branches: &[AffineTuple], weights: &[Float]) -> Option<AffineTuple> {
+        if branches.is_empty() || branches.len() != weights.len() {
+            return None;
+        }
+        let total: Float = weights.iter().sum();
+        if total == 0.0 {
+            return None;
+        }
+
+        let sum = branches.iter()
+            .zip(weights)
+            .map(|(branch, &w)| branch.scale(w))
+            .reduce(|acc, term| acc.add_components(&term))?;
+        Some(sum.scale(1.0 / total))
+    }
+
     /// 🧱 Layer Folding (Deep Stacking)
     /// 
     /// 用于将上一层的输出折叠为下一层的输入。
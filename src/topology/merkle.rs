@@ -2,6 +2,7 @@
 
 use crate::core::algebra::{Matrix, Vector, Float};
 use crate::core::affine::AffineTuple;
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 
 // ⚠️ [REFACTOR NOTICE]:
@@ -22,6 +23,10 @@ pub enum OpType {
     
     /// 叶子节点嵌入
     LeafEmbedding, 
+
+    /// 加权空间融合 Σ w_i · A_i / Σ w (注意力)
+    /// 拓扑：N-ary，权重存放在 `TraceNode::weights` (与 parents 一一对应)
+    WeightedSpaceMerge,
 }
 
 /// 📍 TraceNode: 计算图中的节点
@@ -37,8 +42,32 @@ pub struct TraceNode {
     
     // 缓存的前向传播值 (Forward Value)，用于计算局部梯度
     pub value: AffineTuple, 
+
+    /// ⚖️ 各父节点的融合权重 (仅 WeightedSpaceMerge 使用，其余为空)
+    #[serde(default)]
+    pub weights: Vec<Float>,
 }
 
+/// 📊 TraceStats: 梯度磁带的规模统计 (用于容量规划)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStats {
+    /// 叶子节点数 (LeafEmbedding)
+    pub leaf_count: usize,
+    /// 时间演化节点数 (TimeCompose)
+    pub compose_count: usize,
+    /// 空间融合节点数 (SpaceMerge 与 WeightedSpaceMerge)
+    pub merge_count: usize,
+    /// 总节点数
+    pub total_nodes: usize,
+    /// DAG 最大深度 (叶子深度为 0)
+    pub max_depth: usize,
+    /// 估算的内存占用 (字节)：每个节点缓存一个 D x D 矩阵 + D 维向量 + 父节点列表
+    pub estimated_bytes: usize,
+}
+
+/// 📨 (父节点 ID, dL/dParent 的贡献)
+type GradContribution = (usize, AffineTuple);
+
 /// 🎞️ CausalTrace: 因果追踪器 (The Gradient Tape)
 ///
 /// 记录了从输入 Token 到最终结论的所有变换步骤。
@@ -65,6 +94,7 @@ impl CausalTrace {
             op: OpType::LeafEmbedding,
             parents: vec![],
             value,
+            weights: Vec::new(),
         });
         id
     }
@@ -78,6 +108,7 @@ impl CausalTrace {
             op: OpType::TimeCompose,
             parents: vec![prev_id, next_id], // 注意顺序: [Prev, Next]
             value: result,
+            weights: Vec::new(),
         });
         id
     }
@@ -91,19 +122,154 @@ impl CausalTrace {
             op: OpType::SpaceMerge,
             parents: parent_ids,
             value: result,
+            weights: Vec::new(),
+        });
+        id
+    }
+
+    /// 记录一个加权空间融合操作 (注意力)
+    /// `weights[i]` 对应 `parent_ids[i]`，前向值应为 Σ w_i · A_i / Σ w (见 HyperFolder::fold_context_weighted)。
+    pub fn push_weighted_merge(&mut self, parent_ids: Vec<usize>, weights: Vec<Float>, result: AffineTuple) -> usize {
+        assert_eq!(parent_ids.len(), weights.len(), "Weighted merge: one weight per parent");
+        let id = self.nodes.len();
+        self.nodes.push(TraceNode {
+            id,
+            op: OpType::WeightedSpaceMerge,
+            parents: parent_ids,
+            value: result,
+            weights,
         });
         id
     }
 
+    /// 🧩 Trace Join (拼接并行计算的子图)
+    ///
+    /// 两个 Shard 分别折叠时，各自的 Node ID 都从 0 开始。这里把 `other` 的 ID
+    /// (连同父节点引用与 active_path) 整体平移 `self.nodes.len()` 后追加到末尾，
+    /// 再追加一个以两侧 Root (各自的最后一个节点) 为父节点的 Join 节点，作为新 DAG 的 Root：
+    /// * `TimeCompose`: other 紧接在 self 之后，父节点 [self, other]，值为 other ∘ self；
+    /// * `SpaceMerge`: 二元融合，值为两侧 Root 的均值。
+    ///
+    /// ⚠️ 两条磁带都必须非空；`LeafEmbedding` 与 (缺少权重的) `WeightedSpaceMerge`
+    /// 不能作为 Join 运算，均会 panic。
+    pub fn merge(mut self, other: CausalTrace, join_op: OpType) -> CausalTrace {
+        let (Some(left), Some(right)) = (self.nodes.last(), other.nodes.last()) else {
+            panic!("Merge Error: cannot join an empty trace");
+        };
+        let join_value = match join_op {
+            OpType::TimeCompose => right.value.compose_unchecked(&left.value),
+            OpType::SpaceMerge => left.value.commutative_merge(&right.value).expect("Merge Error"),
+            OpType::LeafEmbedding | OpType::WeightedSpaceMerge => {
+                panic!("Merge Error: {:?} is not a join operation", join_op)
+            }
+        };
+
+        let offset = self.nodes.len();
+        let left_id = offset - 1;
+        let right_id = offset + other.nodes.len() - 1;
+        self.nodes.extend(other.nodes.into_iter().map(|node| TraceNode {
+            id: node.id + offset,
+            parents: node.parents.iter().map(|p| p + offset).collect(),
+            ..node
+        }));
+        self.active_path.extend(other.active_path.iter().map(|id| id + offset));
+
+        match join_op {
+            OpType::TimeCompose => self.push_compose(left_id, right_id, join_value),
+            _ => self.push_n_ary_merge(vec![left_id, right_id], join_value),
+        };
+        self
+    }
+
+    /// 🔍 Structural Validation (结构校验)
+    ///
+    /// 本地录制的磁带天然满足拓扑序；从网络收到的磁带则必须先校验，backward 才能安全地按 ID 索引：
+    /// * 节点 ID 连续 (`nodes[i].id == i`)，父节点 ID 严格小于自身 ID，active_path 不越界；
+    /// * 各节点的值形状自洽 (W 的数据长度为 rows x cols，b 的长度为 rows)；
+    /// * TimeCompose 恰有两个父节点且形状可复合，SpaceMerge / WeightedSpaceMerge 的父节点与自身同形。
+    pub fn validate(&self) -> Result<(), String> {
+        let shape = |t: &AffineTuple| (t.linear.rows, t.linear.cols);
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if node.id != idx {
+                return Err(format!("Node at position {} carries ID {}", idx, node.id));
+            }
+            if let Some(&bad) = node.parents.iter().find(|&&p| p >= idx) {
+                return Err(format!("Node {} references parent {} that does not precede it", idx, bad));
+            }
+            let (rows, cols) = shape(&node.value);
+            if node.value.linear.data.len() != rows * cols || node.value.translation.data.len() != rows {
+                return Err(format!("Node {} has an inconsistent value shape", idx));
+            }
+
+            let parent_shape = |k: usize| shape(&self.nodes[node.parents[k]].value);
+            let consistent = match node.op {
+                OpType::LeafEmbedding => node.parents.is_empty(),
+                OpType::TimeCompose => {
+                    // Out = Next ∘ Prev: (rows_n x cols_n) · (rows_p x cols_p)
+                    node.parents.len() == 2 && {
+                        let ((rows_p, cols_p), (rows_n, cols_n)) = (parent_shape(0), parent_shape(1));
+                        cols_n == rows_p && (rows_n, cols_p) == (rows, cols)
+                    }
+                }
+                OpType::SpaceMerge | OpType::WeightedSpaceMerge => {
+                    (0..node.parents.len()).all(|k| parent_shape(k) == (rows, cols))
+                }
+            };
+            if !consistent {
+                return Err(format!("Node {} ({:?}) does not match its parents", idx, node.op));
+            }
+        }
+        if let Some(&bad) = self.active_path.iter().find(|&&id| id >= self.nodes.len()) {
+            return Err(format!("Active path references missing node {}", bad));
+        }
+        Ok(())
+    }
+
+    /// 📊 Trace Statistics (规模统计)
+    ///
+    /// 在执行 backward() 之前评估磁带有多 "重"：各 OpType 的节点数、DAG 深度与内存估算。
+    /// 节点按拓扑序存储 (父节点 ID 总是更小)，因此一次正向扫描即可求出深度。
+    pub fn stats(&self) -> TraceStats {
+        let mut stats = TraceStats {
+            leaf_count: 0,
+            compose_count: 0,
+            merge_count: 0,
+            total_nodes: self.nodes.len(),
+            max_depth: 0,
+            estimated_bytes: 0,
+        };
+        let mut depths = vec![0usize; self.nodes.len()];
+
+        for node in &self.nodes {
+            match node.op {
+                OpType::LeafEmbedding => stats.leaf_count += 1,
+                OpType::TimeCompose => stats.compose_count += 1,
+                OpType::SpaceMerge | OpType::WeightedSpaceMerge => stats.merge_count += 1,
+            }
+
+            depths[node.id] = node.parents.iter()
+                .map(|&p| depths[p] + 1)
+                .max()
+                .unwrap_or(0);
+            stats.max_depth = stats.max_depth.max(depths[node.id]);
+
+            let floats = node.value.linear.data.len() + node.value.translation.data.len();
+            stats.estimated_bytes += std::mem::size_of::<TraceNode>()
+                + floats * std::mem::size_of::<Float>()
+                + node.parents.len() * std::mem::size_of::<usize>();
+        }
+
+        stats
+    }
+
     /// 📉 Auto-Differentiation Engine (自动微分引擎)
     ///
     /// 给定最终输出的梯度 dL/dOutput，反向计算所有中间节点的梯度。
     pub fn backward(&self, grad_output: &AffineTuple) -> Vec<AffineTuple> {
-        let mut grads = vec![AffineTuple::identity(); self.nodes.len()];
-        // 实际上应该初始化为 0 (Zero Gradient)，这里用 identity 暂代占位，
-        // 真实实现中 AffineTuple 需要实现 zero()。
-        // [FIX]: 假设 AffineTuple::zeros() 存在 (我们在 affine.rs 补上了)。
-        let mut grads = vec![AffineTuple::zeros(); self.nodes.len()];
+        // 梯度初始化为零元 (Zero Gradient)，形状与各节点的前向值一致
+        let mut grads: Vec<AffineTuple> = self.nodes.iter()
+            .map(|node| node.value.scale(0.0))
+            .collect();
         
         // 初始化末端梯度
         if let Some(last_node) = self.nodes.last() {
@@ -112,50 +278,151 @@ impl CausalTrace {
 
         // 反向遍历 (Reverse Topological Order)
         for node in self.nodes.iter().rev() {
-            let current_grad = grads[node.id].clone(); // Clone to avoid borrow conflict
+            // Accumulate Gradient: Grad[Parent] += 局部梯度
+            // 一个节点可能被多个下游节点使用 (DAG)，因此必须累加而非覆盖
+            for (parent_id, contribution) in self.local_grads(node, &grads[node.id]) {
+                grads[parent_id] = grads[parent_id].add_components(&contribution);
+            }
+        }
+        
+        grads
+    }
 
-            match node.op {
-                OpType::LeafEmbedding => {
-                    // 叶子节点，梯度停止流动 (或者传给 Embedding Layer)
-                },
-                OpType::TimeCompose => {
-                    // Compose: Out = Next * Prev
-                    // Inputs: parents[0] (Prev), parents[1] (Next)
-                    if node.parents.len() == 2 {
-                        let prev_idx = node.parents[0];
-                        let next_idx = node.parents[1];
-                        // let prev_val = &self.nodes[prev_idx].value; // 如需计算 Jacobian
-                        // let next_val = &self.nodes[next_idx].value;
-
-                        // Chain Rule (Simplification):
-                        // 真实的矩阵梯度传播非常复杂，这里仅示意梯度流动路径
-                        // dL/dPrev += ...
-                        // dL/dNext += ...
-                        // grads[prev_idx] = grads[prev_idx].add(&propagated_grad_prev);
-                        // grads[next_idx] = grads[next_idx].add(&propagated_grad_next);
-                    }
-                },
-                OpType::SpaceMerge => {
-                    // 🌌 N-ary Merge Gradient Distribution
-                    // Out = (Sum Inputs) / N
-                    // dL/dInput_i = (1/N) * dL/dOut
-                    
-                    let n = node.parents.len() as Float;
-                    if n > 0.0 {
-                        let scale_factor = 1.0 / n;
-                        let grad_share = current_grad.scale(scale_factor);
-
-                        for &parent_id in &node.parents {
-                            // Accumulate Gradient: Grad[Parent] += Grad_Share
-                            // 需要把 grad_share 累加进去，因为一个节点可能参与多个 Merge (虽然在这个 Tree 里一般只有一次)
-                            let new_grad = grads[parent_id].add_components(&grad_share);
-                            grads[parent_id] = new_grad;
-                        }
-                    }
+    /// ⚡ Parallel Auto-Differentiation (分层并行反向传播)
+    ///
+    /// 在树形折叠中，兄弟子树彼此独立，可以同时求导。
+    /// 按 "到输出的最长距离" 对节点分层：同一层内的节点互不依赖，用 Rayon 并行计算局部梯度；
+    /// 层与层之间在汇合点 (Join Point) 合并。
+    ///
+    /// 每个节点的入向梯度按下游节点 ID 从大到小累加 (与串行 `backward` 的顺序一致)，
+    /// 因此结果与 `backward` 逐位相同。
+    pub fn backward_parallel(&self, grad_output: &AffineTuple) -> Vec<AffineTuple> {
+        let mut grads: Vec<AffineTuple> = self.nodes.iter()
+            .map(|node| node.value.scale(0.0))
+            .collect();
+        let Some(last_node) = self.nodes.last() else {
+            return grads;
+        };
+
+        // 1. 分层: level = 到输出节点的最长路径长度 (不可达节点不参与，梯度保持为零)
+        let mut levels: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        levels[last_node.id] = Some(0);
+        for node in self.nodes.iter().rev() {
+            let Some(level) = levels[node.id] else { continue };
+            for &parent_id in &node.parents {
+                levels[parent_id] = Some(levels[parent_id].map_or(level + 1, |l| l.max(level + 1)));
+            }
+        }
+        let depth = levels.iter().flatten().max().copied().unwrap_or(0);
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); depth + 1];
+        for (id, level) in levels.iter().enumerate() {
+            if let Some(level) = level {
+                layers[*level].push(id);
+            }
+        }
+
+        // 2. 逐层推进: 同层并行求局部梯度，层间串行合并
+        // pending[p] 收集 (下游节点 ID, 贡献)，轮到 p 时一次性累加
+        let mut pending: Vec<Vec<(usize, AffineTuple)>> = vec![Vec::new(); self.nodes.len()];
+        for layer in layers {
+            let results: Vec<(usize, AffineTuple, Vec<GradContribution>)> = layer.par_iter()
+                .map(|&id| {
+                    let grad = if id == last_node.id {
+                        grad_output.clone()
+                    } else {
+                        let mut incoming: Vec<&(usize, AffineTuple)> = pending[id].iter().collect();
+                        incoming.sort_by_key(|(consumer, _)| std::cmp::Reverse(*consumer));
+                        incoming.iter().fold(grads[id].clone(), |acc, (_, g)| acc.add_components(g))
+                    };
+                    let contributions = self.local_grads(&self.nodes[id], &grad);
+                    (id, grad, contributions)
+                })
+                .collect();
+
+            for (id, grad, contributions) in results {
+                grads[id] = grad;
+                pending[id].clear();
+                for (parent_id, contribution) in contributions {
+                    pending[parent_id].push((id, contribution));
                 }
             }
         }
-        
+
         grads
     }
+
+    /// 🔗 Local Jacobian: 计算单个节点对其各父节点的梯度贡献
+    /// 返回 (父节点 ID, dL/dParent 的贡献) 列表，由调用方负责累加。
+    fn local_grads(&self, node: &TraceNode, current_grad: &AffineTuple) -> Vec<(usize, AffineTuple)> {
+        match node.op {
+            OpType::LeafEmbedding => {
+                // 叶子节点，梯度停止流动 (或者传给 Embedding Layer)
+                Vec::new()
+            },
+            OpType::TimeCompose => {
+                // Compose: Out = Next ∘ Prev
+                //   W = W_n · W_p,   b = W_n · b_p + b_n
+                // Chain Rule (G = dL/dOut):
+                //   dL/dW_n = G_W · W_p^T + g_b · b_p^T,   dL/db_n = g_b
+                //   dL/dW_p = W_n^T · G_W,                dL/db_p = W_n^T · g_b
+                if node.parents.len() != 2 {
+                    return Vec::new();
+                }
+                let prev_idx = node.parents[0];
+                let next_idx = node.parents[1];
+                let prev_val = &self.nodes[prev_idx].value;
+                let next_val = &self.nodes[next_idx].value;
+
+                let grad_next = AffineTuple::new(
+                    current_grad.linear.gemm(false, &prev_val.linear, true)
+                        .add(&outer(&current_grad.translation, &prev_val.translation)),
+                    current_grad.translation.clone(),
+                );
+                let grad_prev = AffineTuple::new(
+                    next_val.linear.gemm(true, &current_grad.linear, false),
+                    next_val.linear.transpose_matmul_vec(&current_grad.translation),
+                );
+
+                vec![(next_idx, grad_next), (prev_idx, grad_prev)]
+            },
+            OpType::SpaceMerge => {
+                // 🌌 N-ary Merge Gradient Distribution
+                // Out = (Sum Inputs) / N
+                // dL/dInput_i = (1/N) * dL/dOut
+                if node.parents.is_empty() {
+                    return Vec::new();
+                }
+                let grad_share = current_grad.scale(1.0 / node.parents.len() as Float);
+                node.parents.iter()
+                    .map(|&parent_id| (parent_id, grad_share.clone()))
+                    .collect()
+            }
+            OpType::WeightedSpaceMerge => {
+                // ⚖️ Weighted Merge Gradient Distribution
+                // Out = Σ w_i · Input_i / Σ w
+                // dL/dInput_i = (w_i / Σ w) * dL/dOut
+                let total: Float = node.weights.iter().sum();
+                if node.parents.len() != node.weights.len() || total == 0.0 {
+                    return Vec::new();
+                }
+                node.parents.iter()
+                    .zip(&node.weights)
+                    .map(|(&parent_id, &w)| (parent_id, current_grad.scale(w / total)))
+                    .collect()
+            }
+        }
+    }
+}
+
+// ==================================================================
+// 🔧 Jacobian Helpers (转置乘积直接用 Matrix::gemm)
+// ==================================================================
+
+/// 外积 $u \cdot v^T$
+fn outer(u: &Vector, v: &Vector) -> Matrix {
+    let mut data = Vec::with_capacity(u.data.len() * v.data.len());
+    for &ui in &u.data {
+        data.extend(v.data.iter().map(|&vj| ui * vj));
+    }
+    Matrix::new(u.data.len(), v.data.len(), data)
 }
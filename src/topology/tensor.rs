@@ -1,10 +1,34 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
+use rayon::prelude::*;
 use serde::{Serialize, Deserialize};
 use crate::core::affine::AffineTuple;
-use crate::core::algebra::Vector;
+use crate::core::algebra::{Vector, Float, MANIFOLD_DIM};
 use crate::topology::folding::HyperFolder;
-use crate::topology::merkle::CausalTrace;
+use crate::topology::merkle::{CausalTrace, OpType};
+
+/// 🔗 MergeMode: 两个 HyperTensor 的拼接方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeMode {
+    /// ⏳ 时间接续: `other` 是 `self` 之后的片段 (Root = other ∘ self)
+    TimeCompose,
+    /// 🌌 空间融合: 两个并行分支取均值 (Root = (self + other) / 2)
+    SpaceMerge,
+}
+
+/// 🧱 训练模式下 Trace 节点数的默认上限
+/// 每个节点持有一个 D x D 矩阵 (D = 512 时约 1 MiB)，默认上限约合 1 GiB。
+pub const DEFAULT_MAX_TRACE_NODES: usize = 1024;
+
+/// ❌ TraceTooLarge: 输入过长，训练模式的 Trace 将超过节点上限
+/// 在分配任何节点之前返回，避免病态输入把进程拖入 OOM。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceTooLarge {
+    /// 完整折叠所需的节点数 (n 个叶子 + n - 1 个 Compose)
+    pub required: usize,
+    /// 配置的上限
+    pub limit: usize,
+}
 
 /// 🧠 HyperTensor: 全息逻辑张量
 ///
@@ -39,18 +63,55 @@ impl HyperTensor {
     /// * `training_mode`: 
     ///     - `true`: 开启梯度追踪 (慢速，生成 Trace)。
     ///     - `false`: 开启并行折叠 (极速，无 Trace)。
-    pub fn forward(inputs: &[AffineTuple], training_mode: bool) -> Self {
+    ///
+    /// 训练模式下 Trace 节点数受 `DEFAULT_MAX_TRACE_NODES` 限制，超出时返回 `TraceTooLarge`。
+    pub fn forward(inputs: &[AffineTuple], training_mode: bool) -> Result<Self, TraceTooLarge> {
+        Self::forward_with_limit(inputs, training_mode, DEFAULT_MAX_TRACE_NODES)
+    }
+
+    /// 🧱 Forward Pass (自定义 Trace 上限)
+    ///
+    /// 与 `forward` 相同，但训练模式的节点上限由 `max_trace_nodes` 指定。
+    /// 推理模式不生成 Trace，不受上限约束。
+    pub fn forward_with_limit(
+        inputs: &[AffineTuple],
+        training_mode: bool,
+        max_trace_nodes: usize,
+    ) -> Result<Self, TraceTooLarge> {
         if inputs.is_empty() {
-            return Self::identity();
+            return Ok(Self::identity());
         }
 
         if training_mode {
-            Self::fold_with_trace(inputs)
+            // 二叉归约: n 个叶子 + (n - 1) 个 Compose 节点
+            let required = 2 * inputs.len() - 1;
+            if required > max_trace_nodes {
+                return Err(TraceTooLarge { required, limit: max_trace_nodes });
+            }
+            Ok(Self::fold_with_trace(inputs))
         } else {
-            Self::fold_fast(inputs)
+            Ok(Self::fold_fast(inputs))
         }
     }
 
+    /// 🎭 Masked Forward Pass (批量训练中的填充屏蔽)
+    ///
+    /// 变长序列补齐到同一长度后，填充位置不应影响折叠结果。
+    /// `mask[i] == true` 表示第 i 个位置是有效 Token，`false` 为填充：填充位置按单位元 (no-op) 处理，
+    /// 即直接从时间线中剔除 (而不是插入单位矩阵再参与归约)，因此结果与直接折叠未填充的序列一致。
+    /// 训练模式下 Trace 的叶子只对应有效位置 (按原顺序编号)，填充位置不产生梯度。
+    ///
+    /// ⚠️ `mask` 与 `inputs` 长度不一致时 panic。
+    pub fn forward_masked(inputs: &[AffineTuple], mask: &[bool], training_mode: bool) -> Result<Self, TraceTooLarge> {
+        assert_eq!(inputs.len(), mask.len(), "forward_masked: mask length must match the input length");
+        let kept: Vec<AffineTuple> = inputs.iter()
+            .zip(mask)
+            .filter(|(_, &valid)| valid)
+            .map(|(input, _)| input.clone())
+            .collect();
+        Self::forward(&kept, training_mode)
+    }
+
     /// 🏎️ Fast Folding (Inference Mode)
     /// 利用 Rayon 进行并行规约，速度极快，但不保留梯度图。
     fn fold_fast(inputs: &[AffineTuple]) -> Self {
@@ -119,7 +180,7 @@ impl HyperTensor {
                     // Execute Logic: Next * Prev (Time Compose)
                     // or Merge (Space Fold) depending on context.
                     // Assume Time Folding for sequence tensor:
-                    let result = next_val.compose(prev_val).expect("Fold Error");
+                    let result = next_val.compose_unchecked(prev_val);
                     
                     // Record in Tape
                     let new_id = trace.push_compose(prev_id, next_id, result.clone());
@@ -146,6 +207,93 @@ impl HyperTensor {
         }
     }
     
+    /// 🧩 Shard Merge (分片合并 / Map-Reduce)
+    ///
+    /// 将两个分别折叠好的 HyperTensor 合并为一个。
+    /// 用于长文档的分片并行处理：每个 Shard 单独 forward，最后两两 merge。
+    ///
+    /// * `TimeCompose`: `other` 紧接在 `self` 之后，等价于对拼接序列做一次完整折叠。
+    /// * `SpaceMerge`: 两个分支并列融合，对应 Trace 中的二元 SpaceMerge 节点。
+    ///
+    /// 训练模式下，两条 Trace 会被拼接成同一个 DAG，并追加一个 Join 节点作为新的 Root，
+    /// 因此 backward() 可以穿过合并点流回两个 Shard 的叶子。
+    /// 若只有一侧带 Trace，另一侧的 Root 作为常量叶子接入；两侧都没有 Trace 时结果也没有。
+    pub fn merge(&self, other: &Self, mode: MergeMode) -> HyperTensor {
+        let root = match mode {
+            MergeMode::TimeCompose => other.root.compose_unchecked(&self.root),
+            MergeMode::SpaceMerge => self.root.commutative_merge(&other.root).expect("Merge Error"),
+        };
+
+        if self.trace.is_none() && other.trace.is_none() {
+            return HyperTensor { root, trace: None };
+        }
+
+        let join_op = match mode {
+            MergeMode::TimeCompose => OpType::TimeCompose,
+            MergeMode::SpaceMerge => OpType::SpaceMerge,
+        };
+        let trace = Self::shard_trace(self).merge(Self::shard_trace(other), join_op);
+
+        HyperTensor {
+            root,
+            trace: Some(trace),
+        }
+    }
+
+    /// 取出一个 Shard 的 Trace 用于拼接；没有 Trace 的 Shard 以其 Root 作为单叶子 Trace 接入。
+    fn shard_trace(shard: &HyperTensor) -> CausalTrace {
+        match &shard.trace {
+            Some(t) if !t.nodes.is_empty() => t.clone(),
+            _ => {
+                let mut trace = CausalTrace::new();
+                trace.push_leaf(shard.root.clone());
+                trace
+            }
+        }
+    }
+
+    /// 📦 Batch Backward (数据并行的批量反向传播)
+    ///
+    /// 对一个 Batch 中的每个样本 (`tensors[i]`，对应输出梯度 `grad_outputs[i]`) 执行 backward，
+    /// 样本之间用 Rayon 并行。返回每个样本的叶子梯度 (按叶子在 Trace 中的顺序)，
+    /// 对 forward 产生的张量即时间线顺序，可直接交给 GradientAccumulator。
+    /// 没有 Trace 的张量 (推理模式) 返回空列表。
+    pub fn backward_batch(tensors: &[HyperTensor], grad_outputs: &[AffineTuple]) -> Vec<Vec<AffineTuple>> {
+        assert_eq!(tensors.len(), grad_outputs.len(), "Batch backward: one output gradient per tensor");
+
+        tensors.par_iter()
+            .zip(grad_outputs.par_iter())
+            .map(|(tensor, grad_output)| {
+                let Some(trace) = &tensor.trace else {
+                    return Vec::new();
+                };
+                trace.backward(grad_output)
+                    .into_iter()
+                    .zip(&trace.nodes)
+                    .filter(|(_, node)| matches!(node.op, OpType::LeafEmbedding))
+                    .map(|(grad, _)| grad)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// ⚖️ Root Equality (近似相等)
+    /// 只比较 Root 仿射元组 (W 与 b 的逐元素误差均 <= tol)，忽略 Trace。
+    /// Trace 只是计算过程的记录，同一结论可以由不同的折叠路径得到。
+    pub fn root_approx_eq(&self, other: &Self, tol: Float) -> bool {
+        let (a, b) = (&self.root, &other.root);
+        if a.linear.rows != b.linear.rows
+            || a.linear.cols != b.linear.cols
+            || a.translation.data.len() != b.translation.data.len()
+        {
+            return false;
+        }
+
+        a.linear.data.iter().zip(&b.linear.data)
+            .chain(a.translation.data.iter().zip(&b.translation.data))
+            .all(|(x, y)| (x - y).abs() <= tol)
+    }
+
     /// 🔍 Introspection (自省)
     /// 打印逻辑折叠的深度和复杂度。
     pub fn complexity(&self) -> usize {
@@ -154,4 +302,23 @@ impl HyperTensor {
             None => 0, // 快速模式下不可知
         }
     }
+
+    /// 🧮 FLOP Estimation (算力估算，用于容量规划)
+    ///
+    /// 折叠 `input_len` 个元组 (D = MANIFOLD_DIM) 所需的浮点运算数。两种模式都是二叉归约，
+    /// 恰好执行 n - 1 次 Compose，因此这是序列长度与 D 的闭式解，无需实际 forward。
+    /// 见 `estimate_flops_with_dim`。
+    pub fn estimate_flops(input_len: usize) -> u64 {
+        Self::estimate_flops_with_dim(input_len, MANIFOLD_DIM)
+    }
+
+    /// 🧮 指定维度 D 下的 FLOP 估算
+    ///
+    /// 每次 Compose: W_n · W_p (2D³) + W_n · b_p (2D²) + 偏置相加 (D)。
+    /// 只计主干运算，不含单位元快速路径的节省与稳定性检查；训练模式的 backward 另需约 2 倍。
+    pub fn estimate_flops_with_dim(input_len: usize, dim: usize) -> u64 {
+        let d = dim as u64;
+        let per_compose = 2 * d * d * d + 2 * d * d + d;
+        input_len.saturating_sub(1) as u64 * per_compose
+    }
 }
@@ -1,11 +1,21 @@
 // COPYRIGHT (C) 2025 M-Patek. ALL RIGHTS RESERVED.
 
-use crate::core::algebra::{Vector, Matrix, Float, MANIFOLD_DIM};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Serialize, Deserialize};
+use tracing::{debug, warn};
+
+use crate::core::algebra::{Vector, Matrix, Float};
 use crate::core::affine::AffineTuple;
 use crate::core::neuron::HTPNeuron;
-use crate::core::oracle::LogicOracle;
+use crate::core::oracle::{LogicOracle, SOLVER_PROXIMITY_WEIGHT};
 use crate::core::param::HyperParams;
-use crate::topology::tensor::HyperTensor;
+use crate::core::rng::derive_seed;
+use crate::topology::tensor::{HyperTensor, DEFAULT_MAX_TRACE_NODES};
 
 /// 🏋️ TrainingLoop: 逻辑进化训练器
 ///
@@ -14,56 +24,456 @@ use crate::topology::tensor::HyperTensor;
 /// 2. Algebraic Solver (顿悟/One-Shot): 通过代数逆运算，瞬间学会特定事实。
 pub struct TrainingLoop {
     params: HyperParams,
-    optimizer: SimpleOptimizer,
+    /// 🧭 驱动 SGD 更新的优化器 (默认 SimpleOptimizer)，随检查点原样保存与恢复
+    optimizer: OptimizerState,
+    target_mode: TargetMode,
+
+    /// 🧲 Solver 的恒等先验强度 μ (0 = 无先验)，见 LogicOracle::compute_ideal_update_regularized
+    identity_weight: Float,
+
+    /// 🧱 SGD 前向传播的 Trace 节点上限 (防止超长输入 OOM)
+    max_trace_nodes: usize,
+
+    /// 🏆 Best Model: 迄今验证集 Loss 最低的模型快照
+    best: Option<ModelCheckpoint>,
+    /// 💾 (可选) 每次刷新 Best 时同步写盘的路径
+    best_path: Option<PathBuf>,
+
+    /// 🔀 train_epoch 打乱样本顺序的种子
+    shuffle_seed: u64,
+    /// 📚 train_epoch 已完成的 Epoch 数 (与种子组合，保证每个 Epoch 的顺序不同但可复现)
+    epochs_completed: u64,
+}
+
+/// 📚 Dataset: 监督样本集合
+/// 每个样本为 (输入上下文, 目标 Root)，与 `train_step_sgd` 的参数一致。
+pub trait Dataset {
+    /// 样本数
+    fn len(&self) -> usize;
+
+    /// 第 i 个样本 (0 <= i < len)
+    fn get(&self, i: usize) -> (Vec<AffineTuple>, AffineTuple);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 📦 内存数据集: 直接以样本列表作为 Dataset
+impl Dataset for Vec<(Vec<AffineTuple>, AffineTuple)> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn get(&self, i: usize) -> (Vec<AffineTuple>, AffineTuple) {
+        self[i].clone()
+    }
+}
+
+/// 📊 EpochStats: 一个 Epoch 的训练统计
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EpochStats {
+    /// 参与训练的样本数 (不含被跳过的样本)
+    pub samples: usize,
+    /// 执行的优化器更新次数
+    pub batches: usize,
+    /// 样本平均 Loss (无样本时为 0)
+    pub avg_loss: Float,
+}
+
+/// 💡 OneShotFact: 需要被 One-Shot Solver 精确记住的单条事实
+/// "第 `layer` 层的神经元必须把 `input` 映射到 `target`"
+#[derive(Clone, Debug, PartialEq)]
+pub struct OneShotFact {
+    pub layer: usize,
+    pub input: Vector,
+    pub target: Vector,
+}
+
+/// 🔀 MixedStepStats: 一次混合训练步的统计
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MixedStepStats {
+    /// SGD 部分 (整个 Batch 视为一次更新，batches 为 0 或 1)
+    pub sgd: EpochStats,
+    /// 每条事实注入后的残差 Loss (与 facts 一一对应；层号越界的事实记为 NaN)
+    pub fact_losses: Vec<Float>,
+}
+
+/// 💾 ModelCheckpoint: 模型快照 (内存中或磁盘上)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelCheckpoint {
+    /// 快照时的验证集 Loss
+    pub val_loss: Float,
+    /// 各层神经元 (不含输出缓存)
+    pub neurons: Vec<HTPNeuron>,
+    /// 🧭 快照时的优化器状态 (学习率、步数、动量/矩估计)，断点续训时原样恢复
+    #[serde(default)]
+    pub optimizer: Option<OptimizerState>,
+}
+
+/// 🧭 OptimizerState: TrainingLoop 驱动的优化器 (同时也是检查点中携带的优化器状态)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum OptimizerState {
+    Sgd(SimpleOptimizer),
+    Adam(AdamOptimizer),
+}
+
+impl OptimizerState {
+    /// 📜 开启 / 关闭证明模式 (转发给内部的优化器)
+    pub fn with_linear_proof_mode(self, enabled: bool) -> Self {
+        match self {
+            OptimizerState::Sgd(optimizer) => OptimizerState::Sgd(optimizer.with_linear_proof_mode(enabled)),
+            OptimizerState::Adam(optimizer) => OptimizerState::Adam(optimizer.with_linear_proof_mode(enabled)),
+        }
+    }
+}
+
+/// 🦶 Optimizer: 对单个神经元执行一步更新的优化器
+/// `GradientAccumulator::step` 与 `TrainingLoop` 通过它驱动 SGD / Adam。
+pub trait Optimizer {
+    fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector);
+}
+
+impl Optimizer for OptimizerState {
+    fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector) {
+        match self {
+            OptimizerState::Sgd(optimizer) => optimizer.step_neuron(layer, neuron, grad_w, grad_b),
+            OptimizerState::Adam(optimizer) => optimizer.step_neuron(layer, neuron, grad_w, grad_b),
+        }
+    }
+}
+
+impl Optimizer for SimpleOptimizer {
+    fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector) {
+        SimpleOptimizer::step_neuron(self, layer, neuron, grad_w, grad_b);
+    }
+}
+
+impl Optimizer for AdamOptimizer {
+    fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector) {
+        AdamOptimizer::step_neuron(self, layer, neuron, grad_w, grad_b);
+    }
+}
+
+impl ModelCheckpoint {
+    /// 写入磁盘 (bincode)
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let bytes = bincode::serialize(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, bytes).map_err(|e| format!("Failed to write checkpoint {}: {}", path.display(), e))
+    }
+
+    /// 从磁盘读取
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read checkpoint {}: {}", path.display(), e))?;
+        bincode::deserialize(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// 🎯 TargetMode: SGD 的监督目标
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetMode {
+    /// 只监督 Root 的平移部分 (一个目标点)
+    Translation,
+    /// 监督完整的仿射变换 (W 与 b)，让网络学会一个目标变换
+    FullAffine,
 }
 
 impl TrainingLoop {
     pub fn new(params: HyperParams) -> Self {
         TrainingLoop {
             params: params.clone(),
-            optimizer: SimpleOptimizer::new(params.learning_rate),
+            optimizer: OptimizerState::Sgd(SimpleOptimizer::new(params.learning_rate))
+                .with_linear_proof_mode(params.linear_proof_mode),
+            target_mode: TargetMode::Translation,
+            identity_weight: 0.0,
+            max_trace_nodes: DEFAULT_MAX_TRACE_NODES,
+            best: None,
+            best_path: None,
+            shuffle_seed: 0,
+            epochs_completed: 0,
         }
     }
 
+    /// 🧭 替换优化器 (例如 `OptimizerState::Adam`)
+    /// 配置开启了 `linear_proof_mode` 时，同时在该优化器上开启证明模式。
+    pub fn with_optimizer(mut self, optimizer: OptimizerState) -> Self {
+        self.adopt_optimizer(optimizer);
+        self
+    }
+
+    /// 🧭 Helper: 安装优化器，并按配置强制证明模式 (不会把优化器自带的证明模式关掉)
+    fn adopt_optimizer(&mut self, optimizer: OptimizerState) {
+        self.optimizer = if self.params.linear_proof_mode {
+            optimizer.with_linear_proof_mode(true)
+        } else {
+            optimizer
+        };
+    }
+
+    /// 🎯 设置监督目标 (默认只监督平移部分)
+    pub fn with_target_mode(mut self, mode: TargetMode) -> Self {
+        self.target_mode = mode;
+        self
+    }
+
+    /// 🧲 One-Shot Solver 向单位矩阵正则化 (强度 μ)，让瞬间学习留在稳定流形内
+    /// 接近项取单位权重 (`SOLVER_PROXIMITY_WEIGHT`)，正交方向上的拉回比例 μ/(1 + μ) 随 μ 连续变化。
+    /// 混合模式 (`train_step_mixed`) 的事实注入以 SGD 后的权重为锚点，同一 μ 作为其接近项强度。
+    pub fn with_identity_regularization(mut self, identity_weight: Float) -> Self {
+        self.identity_weight = identity_weight;
+        self
+    }
+
+    /// 🧱 设置 SGD 前向传播的 Trace 节点上限 (默认 DEFAULT_MAX_TRACE_NODES)
+    pub fn with_max_trace_nodes(mut self, max_trace_nodes: usize) -> Self {
+        self.max_trace_nodes = max_trace_nodes;
+        self
+    }
+
+    /// 🔀 设置 train_epoch 的打乱种子 (默认 0)
+    pub fn with_shuffle_seed(mut self, seed: u64) -> Self {
+        self.shuffle_seed = seed;
+        self
+    }
+
+    /// 💾 刷新 Best Model 时同时写入磁盘
+    pub fn with_best_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.best_path = Some(path.into());
+        self
+    }
+
+    /// 🏆 Keep-the-Best: 仅当验证集 Loss 改善时才保存模型快照
+    ///
+    /// 更差的中间状态直接丢弃。返回本次是否刷新了 Best。
+    /// 若配置了磁盘路径，刷新时同步写盘，写盘失败返回 Err (内存中的 Best 仍已更新)。
+    pub fn track_best(&mut self, val_loss: Float, neurons: &[HTPNeuron]) -> Result<bool, String> {
+        let improved = match &self.best {
+            Some(best) => val_loss < best.val_loss,
+            None => !val_loss.is_nan(),
+        };
+        if !improved {
+            return Ok(false);
+        }
+
+        let checkpoint = ModelCheckpoint {
+            val_loss,
+            neurons: neurons.to_vec(),
+            optimizer: Some(self.optimizer.clone()),
+        };
+        let saved = match &self.best_path {
+            Some(path) => checkpoint.save(path),
+            None => Ok(()),
+        };
+        self.best = Some(checkpoint);
+        saved.map(|_| true)
+    }
+
+    /// 🏆 取回迄今最好的模型 (尚未记录时为 None)
+    pub fn best_model(&self) -> Option<&[HTPNeuron]> {
+        self.best.as_ref().map(|b| b.neurons.as_slice())
+    }
+
+    /// 🏆 迄今最好的验证集 Loss
+    pub fn best_loss(&self) -> Option<Float> {
+        self.best.as_ref().map(|b| b.val_loss)
+    }
+
+    /// ⏯️ 断点续训：从检查点恢复优化器状态 (SGD 或 Adam) 与 Best 记录，返回其中的模型
+    /// 检查点不含优化器状态 (旧格式) 时保留当前优化器。
+    pub fn resume_from(&mut self, checkpoint: ModelCheckpoint) -> Vec<HTPNeuron> {
+        match &checkpoint.optimizer {
+            Some(optimizer) => self.adopt_optimizer(optimizer.clone()),
+            None => warn!("⚠️ Checkpoint has no optimizer state; resuming with a fresh optimizer."),
+        }
+        let neurons = checkpoint.neurons.clone();
+        self.best = Some(checkpoint);
+        neurons
+    }
+
     /// 📉 Mode 1: Gradient Descent Step (反向传播)
     /// 适用于学习通用规律 (Generalization)
+    ///
+    /// 时间线 = 输入上下文 (inputs) + 模型各层 (Layer 0 最先作用)。
+    /// 反向传播后，每层逻辑门按其对应叶子节点的梯度更新。
+    ///
+    /// ⚠️ 空输入 (无上下文) 时不训练：直接返回 0.0 并记录警告，
+    /// 否则模型会被拟合到单位元上下文，产生无意义的梯度。
+    /// 时间线过长 (Trace 超过 `max_trace_nodes`) 时同样跳过并记录警告。
     pub fn train_step_sgd(
         &mut self, 
+        model: &mut [HTPNeuron],
         inputs: &[AffineTuple], 
         target_root: &AffineTuple
     ) -> Float {
-        // 1. Forward Pass (with Trace)
-        // 开启 training_mode=true 以记录梯度磁带
-        let hyper_tensor = HyperTensor::forward(inputs, true);
-        
-        // 2. Compute Loss
-        // L = || Prediction - Target ||^2
-        // 这里简化为只计算 Translation (Bias) 的误差，实际应包含 Linear 部分
-        let loss = LogicOracle::calculate_loss(
-            &hyper_tensor.root.translation, 
-            &target_root.translation
+        let Some((loss, leaf_grads)) = self.sample_gradients(model, inputs, target_root) else {
+            return 0.0;
+        };
+
+        // 4. Update Weights (Optimizer Step)
+        // 叶子节点 ID 与时间线下标一致：Layer i 对应叶子 inputs.len() + i
+        for (layer_idx, neuron) in model.iter_mut().enumerate() {
+            let grad = &leaf_grads[inputs.len() + layer_idx];
+            self.optimizer.step_neuron(layer_idx, neuron, &grad.linear, &grad.translation);
+        }
+
+        loss
+    }
+
+    /// 📚 Epoch Driver: 对整个数据集执行一遍 Mini-Batch SGD
+    ///
+    /// 样本顺序按 `derive_seed(shuffle_seed, 已完成的 Epoch 数)` 确定性打乱，每 `batch_size` 个样本
+    /// 经 `GradientAccumulator` 求平均梯度后由优化器更新一次 (batch_size 为 0 时按 1 处理)。
+    /// 被跳过的样本 (空上下文 / Trace 超限) 不计入统计。
+    /// 返回的平均 Loss 是各样本在其所属 Batch 更新之前的 Loss。
+    pub fn train_epoch(&mut self, model: &mut [HTPNeuron], dataset: &impl Dataset, batch_size: usize) -> EpochStats {
+        let mut order: Vec<usize> = (0..dataset.len()).collect();
+        order.shuffle(&mut StdRng::seed_from_u64(derive_seed(self.shuffle_seed, self.epochs_completed)));
+        self.epochs_completed += 1;
+
+        let mut stats = EpochStats::default();
+        let mut total_loss = 0.0f64;
+        for batch in order.chunks(batch_size.max(1)) {
+            let (samples, batch_loss) = self.sgd_batch_step(model, dataset, batch);
+            if samples > 0 {
+                stats.samples += samples;
+                stats.batches += 1;
+                total_loss += batch_loss;
+            }
+        }
+
+        if stats.samples > 0 {
+            stats.avg_loss = (total_loss / stats.samples as f64) as Float;
+        }
+        debug!(samples = stats.samples, batches = stats.batches, avg_loss = stats.avg_loss, "📚 Epoch finished");
+        stats
+    }
+
+    /// 🔀 Mixed Mode: 通识学习 + 事实注入
+    ///
+    /// 先把整个 `sgd_batch` 作为一个 Mini-Batch 执行一次 SGD 更新 (慢速学习通用模式)，
+    /// 再把每条事实注入对应层 (`inject_fact`)。注入以 SGD 之后的权重为锚点
+    /// (`LogicOracle::compute_ideal_update_proximal`，强度取 `with_identity_regularization` 的 μ)：
+    /// 修正是沿事实输入方向的秩一更新 ΔW = E·xᵀ / (μ + ||x||²)，且不改动偏置，
+    /// 因此与 x 正交的输入保持 SGD 学到的行为不变；μ > 0 时事实只被部分吸收 (残差比例 μ / (μ + ||x||²))。
+    pub fn train_step_mixed(
+        &mut self,
+        model: &mut [HTPNeuron],
+        sgd_batch: &impl Dataset,
+        facts: &[OneShotFact]
+    ) -> MixedStepStats {
+        let indices: Vec<usize> = (0..sgd_batch.len()).collect();
+        let (samples, total_loss) = self.sgd_batch_step(model, sgd_batch, &indices);
+        let sgd = EpochStats {
+            samples,
+            batches: usize::from(samples > 0),
+            avg_loss: if samples > 0 { (total_loss / samples as f64) as Float } else { 0.0 },
+        };
+
+        let fact_losses = facts.iter()
+            .map(|fact| match model.get_mut(fact.layer) {
+                Some(neuron) => self.inject_fact(neuron, fact),
+                None => {
+                    warn!(layer = fact.layer, depth = model.len(), "⚠️ One-shot fact targets a missing layer. Skipping.");
+                    Float::NAN
+                }
+            })
+            .collect();
+
+        MixedStepStats { sgd, fact_losses }
+    }
+
+    /// 💡 把单条事实以秩一更新写入神经元的线性部分 (偏置不变)，返回注入后的残差 Loss
+    fn inject_fact(&self, neuron: &mut HTPNeuron, fact: &OneShotFact) -> Float {
+        let initial_loss = LogicOracle::calculate_loss(&neuron.absorb(&fact.input), &fact.target);
+        if initial_loss < self.params.tolerance_epsilon {
+            return initial_loss;
+        }
+
+        let delta_w = LogicOracle::compute_ideal_update_proximal(
+            &fact.input,
+            &fact.target,
+            neuron.logic_gate(),
+            self.identity_weight
         );
+        let candidate = AffineTuple::new(neuron.logic_gate().linear.add(&delta_w), neuron.logic_gate().translation.clone());
+        if let Err(e) = self.params.admit_gate(&candidate) {
+            warn!(layer = fact.layer, error = %e, "📜 Fact injection rejected in linear proof mode");
+            return initial_loss;
+        }
+        neuron.gate_mut().linear = candidate.linear;
 
-        // 3. Backward Pass (Auto-Diff)
-        // 从 Trace 中反向推导梯度
-        if let Some(trace) = &hyper_tensor.trace {
-            // 计算输出层的梯度 dL/dOut
-            // dL/dOut = 2 * (Pred - Target)
-            let diff = hyper_tensor.root.translation.sub(&target_root.translation);
-            let grad_output = AffineTuple::new(
-                Matrix::new(MANIFOLD_DIM, MANIFOLD_DIM, vec![0.0; MANIFOLD_DIM*MANIFOLD_DIM]), // 简化: 忽略矩阵梯度
-                diff.scale(2.0)
-            );
+        LogicOracle::calculate_loss(&neuron.absorb(&fact.input), &fact.target)
+    }
 
-            // 反向传播到叶子节点
-            let _leaf_grads = trace.backward(&grad_output);
+    /// 📦 对 `dataset` 中 `indices` 指定的样本求平均梯度并更新一次
+    /// 返回 (参与的样本数, Loss 总和)；全部样本被跳过时不更新。
+    fn sgd_batch_step(&mut self, model: &mut [HTPNeuron], dataset: &impl Dataset, indices: &[usize]) -> (usize, f64) {
+        let mut accumulator = GradientAccumulator::new();
+        let mut samples = 0;
+        let mut total_loss = 0.0f64;
+        for &i in indices {
+            let (inputs, target) = dataset.get(i);
+            if let Some((loss, leaf_grads)) = self.sample_gradients(model, &inputs, &target) {
+                accumulator.add_leaf_grads(&leaf_grads, inputs.len(), model.len());
+                total_loss += loss as f64;
+                samples += 1;
+            }
+        }
+        if samples > 0 {
+            accumulator.step(&mut self.optimizer, model);
+        }
+        (samples, total_loss)
+    }
 
-            // 4. Update Weights (Optimizer Step)
-            // 在真实实现中，这里会根据 leaf_grads 更新对应的 Embedding 或 Neuron 权重
-            // self.optimizer.step(&mut model_params, &leaf_grads);
+    /// 🔁 单样本的前向 + 反向传播 (不更新权重)
+    /// 返回 (Loss, 叶子梯度)；空上下文或 Trace 超限时记录警告并返回 None。
+    fn sample_gradients(
+        &self,
+        model: &[HTPNeuron],
+        inputs: &[AffineTuple],
+        target_root: &AffineTuple
+    ) -> Option<(Float, Vec<AffineTuple>)> {
+        // 0. Guard: 空上下文
+        if inputs.is_empty() {
+            warn!("⚠️ train_step_sgd called with empty input. Skipping step.");
+            return None;
         }
 
-        loss
+        // 1. Forward Pass (with Trace)
+        // 开启 training_mode=true 以记录梯度磁带
+        let mut timeline = inputs.to_vec();
+        timeline.extend(model.iter().map(|neuron| neuron.logic_gate().clone()));
+        let hyper_tensor = match HyperTensor::forward_with_limit(&timeline, true, self.max_trace_nodes) {
+            Ok(tensor) => tensor,
+            Err(e) => {
+                warn!("⚠️ train_step_sgd: trace needs {} nodes (limit {}). Skipping step.", e.required, e.limit);
+                return None;
+            }
+        };
+        let root = &hyper_tensor.root;
+        
+        // 2. Compute Loss & Output Gradient dL/dOut
+        let (loss, grad_output) = match self.target_mode {
+            TargetMode::Translation => {
+                // L = || b_pred - b_target ||^2,  dL/db = 2 * (b_pred - b_target)
+                let loss = LogicOracle::calculate_loss(&root.translation, &target_root.translation);
+                let mut grad = AffineTuple::zeros();
+                grad.translation = root.translation.sub(&target_root.translation).scale(2.0);
+                (loss, grad)
+            }
+            TargetMode::FullAffine => {
+                // L = || W_pred - W_target ||_F^2 + || b_pred - b_target ||^2
+                // dL/dOut = 2 * (Pred - Target)  (对 W 与 b 同时成立)
+                let loss = LogicOracle::affine_loss(root, target_root);
+                let grad = root.add_components(&target_root.scale(-1.0)).scale(2.0);
+                (loss, grad)
+            }
+        };
+
+        // 3. Backward Pass (Auto-Diff)
+        // 从 Trace 中反向推导梯度
+        let trace = hyper_tensor.trace.as_ref()?;
+        Some((loss, trace.backward(&grad_output)))
     }
 
     /// ⚡ Mode 2: Algebraic One-Shot Solver (瞬间学习)
@@ -86,17 +496,27 @@ impl TrainingLoop {
 
         // 2. Solve for Delta W (The Magic)
         // 询问 Oracle：我需要怎么改权重，才能让 input 完美映射到 target？
-        let delta_w = LogicOracle::compute_ideal_update(
+        let delta_w = LogicOracle::compute_ideal_update_regularized(
             input_state, 
             target_state, 
-            &neuron.logic_gate
+            neuron.logic_gate(),
+            self.identity_weight,
+            SOLVER_PROXIMITY_WEIGHT
         );
 
         // 3. Apply Update Immediately
         // W_new = W_old + Delta_W * Learning_Rate
         // (Solver 模式下 LR 通常为 1.0，即完全接受建议)
         let w_update = delta_w.scale(1.0); 
-        neuron.logic_gate.linear = neuron.logic_gate.linear.add(&w_update);
+        let updated = neuron.logic_gate().linear.add(&w_update);
+
+        // 📜 证明模式: 求解结果不可逆时拒绝写入，逻辑门保持不变
+        let candidate = AffineTuple::new(updated, neuron.logic_gate().translation.clone());
+        if let Err(e) = self.params.admit_gate(&candidate) {
+            warn!(error = %e, "📜 One-shot solve rejected in linear proof mode");
+            return initial_loss;
+        }
+        neuron.gate_mut().linear = candidate.linear;
         
         // 同时修正 Bias (Fix fixed-point drift)
         neuron.force_learn_bias(input_state, target_state);
@@ -109,19 +529,339 @@ impl TrainingLoop {
     }
 }
 
-/// 🔧 SimpleOptimizer: 基础梯度下降优化器
+/// 📈 TrainingMetrics: 一个 Batch 的训练指标
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrainingMetrics {
+    /// 本 Batch 累加的样本梯度数 (所有层合计)
+    pub samples: usize,
+    /// 📡 梯度信噪比 (见 `GradientAccumulator::snr`)；趋近噪声水平即视为收敛
+    pub grad_snr: Float,
+}
+
+/// 🧺 GradientAccumulator: 批量训练的梯度累加器
+///
+/// backward() 为每个叶子返回一个 AffineTuple 梯度 (W 与 b)。
+/// 这里按层号把一个 Batch 内各样本的梯度求和，结束时输出平均后的 (Matrix, Vector) 梯度，
+/// 再交给 Optimizer 做一次更新 (autodiff 与 optimizer 之间的粘合层)。
+#[derive(Default)]
+pub struct GradientAccumulator {
+    /// 层号 -> (梯度和, 样本数, 各样本梯度范数平方和)
+    sums: HashMap<usize, (AffineTuple, usize, f64)>,
+}
+
+/// 梯度 (W 与 b) 的范数平方，用 f64 累加以减小方差计算中的抵消误差
+fn grad_norm_sq(grad: &AffineTuple) -> f64 {
+    grad.linear.data.iter()
+        .chain(&grad.translation.data)
+        .map(|&x| (x as f64) * (x as f64))
+        .sum()
+}
+
+impl GradientAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ➕ 累加某一层的一个样本梯度
+    pub fn add(&mut self, layer: usize, grad: &AffineTuple) {
+        let norm_sq = grad_norm_sq(grad);
+        self.sums.entry(layer)
+            .and_modify(|(sum, n, sum_sq)| {
+                *sum = sum.add_components(grad);
+                *n += 1;
+                *sum_sq += norm_sq;
+            })
+            .or_insert_with(|| (grad.clone(), 1, norm_sq));
+    }
+
+    /// ➕ 累加一次 backward 的叶子梯度
+    /// 与 train_step_sgd 的时间线布局一致：Layer i 对应叶子 `first_layer_leaf + i`。
+    pub fn add_leaf_grads(&mut self, leaf_grads: &[AffineTuple], first_layer_leaf: usize, depth: usize) {
+        for layer in 0..depth {
+            if let Some(grad) = leaf_grads.get(first_layer_leaf + layer) {
+                self.add(layer, grad);
+            }
+        }
+    }
+
+    /// 📊 某一层已累加的样本数
+    pub fn samples(&self, layer: usize) -> usize {
+        self.sums.get(&layer).map_or(0, |(_, n, _)| *n)
+    }
+
+    /// 📡 梯度信噪比: $\|\mu\| / \sigma$
+    ///
+    /// μ 为 Batch 内各样本梯度的均值，σ² = E[‖g - μ‖²] 为样本梯度围绕均值的总方差；
+    /// 多层时分别对信号 ‖μ‖² 与噪声 σ² 求和。各样本方向一致时 SNR 很高 (仍在下降)，
+    /// 趋近 O(1/√n) 时梯度已被噪声主导 (已收敛)。
+    /// 样本数不足 2 的层不参与统计；没有可统计的层时返回 0，噪声严格为 0 时返回 +∞。
+    pub fn snr(&self) -> Float {
+        let (mut signal, mut noise) = (0.0f64, 0.0f64);
+        for (sum, n, sum_sq) in self.sums.values().filter(|(_, n, _)| *n >= 2) {
+            let n = *n as f64;
+            let mean_sq = grad_norm_sq(sum) / (n * n);
+            signal += mean_sq;
+            noise += (sum_sq / n - mean_sq).max(0.0);
+        }
+        if signal == 0.0 {
+            0.0
+        } else if noise == 0.0 {
+            Float::INFINITY
+        } else {
+            (signal / noise).sqrt() as Float
+        }
+    }
+
+    /// 📈 当前 Batch 的训练指标
+    pub fn metrics(&self) -> TrainingMetrics {
+        TrainingMetrics {
+            samples: self.sums.values().map(|(_, n, _)| *n).sum(),
+            grad_snr: self.snr(),
+        }
+    }
+
+    /// ➗ 某一层的平均梯度 (尚无样本时为 None)
+    pub fn mean(&self, layer: usize) -> Option<(Matrix, Vector)> {
+        self.sums.get(&layer).map(|(sum, n, _)| {
+            let scale = 1.0 / *n as Float;
+            (sum.linear.scale(scale), sum.translation.scale(scale))
+        })
+    }
+
+    /// 📤 取出所有层的平均梯度 (按层号排序) 并清空累加器，准备下一个 Batch
+    pub fn drain_means(&mut self) -> Vec<(usize, Matrix, Vector)> {
+        let mut means: Vec<(usize, Matrix, Vector)> = self.sums.drain()
+            .map(|(layer, (sum, n, _))| {
+                let scale = 1.0 / n as Float;
+                (layer, sum.linear.scale(scale), sum.translation.scale(scale))
+            })
+            .collect();
+        means.sort_by_key(|(layer, _, _)| *layer);
+        means
+    }
+
+    /// 🦶 Batch 结束：用平均梯度对模型执行一步更新并清空累加器
+    /// 超出模型深度的层号被忽略。返回 (并记录) 本 Batch 的训练指标。
+    pub fn step(&mut self, optimizer: &mut impl Optimizer, model: &mut [HTPNeuron]) -> TrainingMetrics {
+        let metrics = self.metrics();
+        debug!(samples = metrics.samples, grad_snr = metrics.grad_snr, "📡 Batch gradient SNR");
+        for (layer, grad_w, grad_b) in self.drain_means() {
+            if let Some(neuron) = model.get_mut(layer) {
+                optimizer.step_neuron(layer, neuron, &grad_w, &grad_b);
+            }
+        }
+        metrics
+    }
+}
+
+/// 📜 证明模式: 更新后的逻辑门不可逆时回滚到更新前的值 (`previous` 为 None 表示未开启)
+/// 只回滚权重；优化器的动量 / 矩估计仍记录了这一步的梯度。
+fn rollback_if_singular(layer: usize, neuron: &mut HTPNeuron, previous: Option<AffineTuple>) {
+    let Some(previous) = previous else { return };
+    if let Err(e) = HyperParams::require_invertible(neuron.logic_gate()) {
+        warn!(layer, error = %e, "📜 Optimizer step would make the gate singular. Rolling back.");
+        neuron.set_logic_gate(previous);
+    }
+}
+
+/// 🔧 SimpleOptimizer: 基础梯度下降优化器 (支持 Heavy-Ball 动量)
+///
+/// v = momentum · v - lr · grad
+/// W = W + v
+///
+/// 速度缓冲按层号 (layer index) 分别维护。momentum = 0 时退化为普通 SGD，且不分配缓冲。
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SimpleOptimizer {
     learning_rate: Float,
+    momentum: Float,
+    /// 📜 证明模式: 使逻辑门不可逆的一步被回滚 (见 HyperParams::linear_proof_mode)
+    #[serde(default)]
+    linear_proof_mode: bool,
+    /// 🏃 各层权重的速度缓冲
+    velocity_w: HashMap<usize, Matrix>,
+    /// 🏃 各层偏置的速度缓冲
+    velocity_b: HashMap<usize, Vector>,
 }
 
 impl SimpleOptimizer {
     pub fn new(lr: Float) -> Self {
-        SimpleOptimizer { learning_rate: lr }
+        SimpleOptimizer {
+            learning_rate: lr,
+            momentum: 0.0,
+            linear_proof_mode: false,
+            velocity_w: HashMap::new(),
+            velocity_b: HashMap::new(),
+        }
+    }
+
+    /// 🏃 设置动量系数 (典型值 0.9)
+    pub fn with_momentum(mut self, momentum: Float) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    /// 📜 开启证明模式: 更新后逻辑门不可逆时回滚该步 (通常取 `HyperParams::linear_proof_mode`)
+    pub fn with_linear_proof_mode(mut self, enabled: bool) -> Self {
+        self.linear_proof_mode = enabled;
+        self
+    }
+
+    /// W = W + v,  v = momentum · v - lr · Grad
+    pub fn apply_gradient(&mut self, layer: usize, weights: &mut Matrix, grad: &Matrix) {
+        self.weight_step(layer, weights, grad, 1.0);
+    }
+
+    /// b = b + v,  v = momentum · v - lr · Grad
+    pub fn apply_bias_gradient(&mut self, layer: usize, bias: &mut Vector, grad: &Vector) {
+        self.bias_step(layer, bias, grad, 1.0);
+    }
+
+    /// 🧠 对一个神经元执行完整的一步更新 (W 与 b)
+    /// 有效学习率 = lr × neuron.lr_scale，更新后自动使输出缓存失效。
+    /// 绑定 (共享权重) 的层先拉取最新的共享逻辑门，避免覆盖其他绑定层刚写入的更新。
+    pub fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector) {
+        let lr_scale = neuron.lr_scale;
+        let mut gate = neuron.gate_mut();
+        let previous = self.linear_proof_mode.then(|| gate.clone());
+        self.weight_step(layer, &mut gate.linear, grad_w, lr_scale);
+        self.bias_step(layer, &mut gate.translation, grad_b, lr_scale);
+        drop(gate);
+        rollback_if_singular(layer, neuron, previous);
+    }
+
+    fn weight_step(&mut self, layer: usize, weights: &mut Matrix, grad: &Matrix, lr_scale: Float) {
+        let factor = -self.learning_rate * lr_scale;
+        if self.momentum == 0.0 {
+            weights.add_scaled(grad, factor);
+            return;
+        }
+
+        let step = grad.scale(factor);
+        let momentum = self.momentum;
+        let v = self.velocity_w
+            .entry(layer)
+            .and_modify(|v| *v = v.scale(momentum).add(&step))
+            .or_insert(step);
+        weights.add_scaled(v, 1.0);
+    }
+
+    fn bias_step(&mut self, layer: usize, bias: &mut Vector, grad: &Vector, lr_scale: Float) {
+        let step = grad.scale(-self.learning_rate * lr_scale);
+        if self.momentum == 0.0 {
+            *bias = bias.add(&step);
+            return;
+        }
+
+        let momentum = self.momentum;
+        let v = self.velocity_b
+            .entry(layer)
+            .and_modify(|v| *v = v.scale(momentum).add(&step))
+            .or_insert(step);
+        *bias = bias.add(v);
+    }
+}
+
+/// 🧭 AdamOptimizer: 自适应矩估计优化器
+///
+/// m = β1 · m + (1 - β1) · g,   v = β2 · v + (1 - β2) · g²
+/// W = W - lr · m̂ / (√v̂ + ε),  其中 m̂ = m / (1 - β1^t), v̂ = v / (1 - β2^t)
+///
+/// 矩估计与步数 t 按层号分别维护，且全部可序列化：
+/// 断点续训时从检查点恢复，不会重新经历偏差修正的 warmup。
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdamOptimizer {
+    learning_rate: Float,
+    beta1: Float,
+    beta2: Float,
+    epsilon: Float,
+    /// 📜 证明模式: 使逻辑门不可逆的一步被回滚 (见 HyperParams::linear_proof_mode)
+    #[serde(default)]
+    linear_proof_mode: bool,
+    /// 🔢 各层已执行的步数 t
+    steps: HashMap<usize, u64>,
+    /// 📈 各层的一阶/二阶矩 (W 与 b 展平存放)
+    moments: HashMap<usize, AdamMoments>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct AdamMoments {
+    m_w: Vec<Float>,
+    v_w: Vec<Float>,
+    m_b: Vec<Float>,
+    v_b: Vec<Float>,
+}
+
+impl AdamOptimizer {
+    /// 默认 β1 = 0.9, β2 = 0.999, ε = 1e-8
+    pub fn new(lr: Float) -> Self {
+        AdamOptimizer {
+            learning_rate: lr,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            linear_proof_mode: false,
+            steps: HashMap::new(),
+            moments: HashMap::new(),
+        }
+    }
+
+    /// ⚙️ 设置矩估计的衰减率 (β1, β2)
+    pub fn with_betas(mut self, beta1: Float, beta2: Float) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    /// 📜 开启证明模式: 更新后逻辑门不可逆时回滚该步 (通常取 `HyperParams::linear_proof_mode`)
+    pub fn with_linear_proof_mode(mut self, enabled: bool) -> Self {
+        self.linear_proof_mode = enabled;
+        self
+    }
+
+    /// 🔢 某一层已执行的步数
+    pub fn step_count(&self, layer: usize) -> u64 {
+        self.steps.get(&layer).copied().unwrap_or(0)
+    }
+
+    /// 🧠 对一个神经元执行完整的一步更新 (W 与 b)
+    /// 有效学习率 = lr × neuron.lr_scale，更新后自动使输出缓存失效。
+    /// 绑定 (共享权重) 的层先拉取最新的共享逻辑门。
+    pub fn step_neuron(&mut self, layer: usize, neuron: &mut HTPNeuron, grad_w: &Matrix, grad_b: &Vector) {
+        let lr = self.learning_rate * neuron.lr_scale;
+        let mut gate = neuron.gate_mut();
+        let previous = self.linear_proof_mode.then(|| gate.clone());
+        let t = self.steps.entry(layer).or_insert(0);
+        *t += 1;
+        let t = *t as i32;
+
+        let moments = self.moments.entry(layer).or_default();
+        let hyper = (self.beta1, self.beta2, self.epsilon);
+        Self::adam_step(&mut gate.linear.data, &grad_w.data, &mut moments.m_w, &mut moments.v_w, lr, hyper, t);
+        Self::adam_step(&mut gate.translation.data, &grad_b.data, &mut moments.m_b, &mut moments.v_b, lr, hyper, t);
+        drop(gate);
+        rollback_if_singular(layer, neuron, previous);
     }
 
-    /// W = W - lr * Grad
-    pub fn apply_gradient(&self, weights: &mut Matrix, grad: &Matrix) {
-        let step = grad.scale(-self.learning_rate);
-        *weights = weights.add(&step);
+    fn adam_step(
+        params: &mut [Float],
+        grad: &[Float],
+        m: &mut Vec<Float>,
+        v: &mut Vec<Float>,
+        lr: Float,
+        (beta1, beta2, epsilon): (Float, Float, Float),
+        t: i32,
+    ) {
+        // 首次见到该层 (或形状变化) 时矩估计从零开始
+        if m.len() != grad.len() {
+            *m = vec![0.0; grad.len()];
+            *v = vec![0.0; grad.len()];
+        }
+        let bias_1 = 1.0 - beta1.powi(t);
+        let bias_2 = 1.0 - beta2.powi(t);
+        for (((p, &g), m), v) in params.iter_mut().zip(grad).zip(m.iter_mut()).zip(v.iter_mut()) {
+            *m = beta1 * *m + (1.0 - beta1) * g;
+            *v = beta2 * *v + (1.0 - beta2) * g * g;
+            *p -= lr * (*m / bias_1) / ((*v / bias_2).sqrt() + epsilon);
+        }
     }
 }